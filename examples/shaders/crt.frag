@@ -0,0 +1,33 @@
+// A CRT look: darkened scanlines and a vignette.
+//
+// Use it with:
+//     RunConfig {
+//         fragment_shaders: vec![include_str!("../shaders/crt.frag").to_owned()],
+//         ..RunConfig::default()
+//     }
+precision highp float;
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D tex;
+uniform vec2 resolution;
+uniform float time;
+uniform float screen_height;
+
+void main() {
+    vec3 pixel = texture(tex, v_tex_coords).rgb;
+
+    // Bright in the middle of each pico8 row, dark in between.
+    float row = v_tex_coords.y * screen_height;
+    float scanline = 0.75 + 0.25 * cos(6.2831853 * (row - 0.5));
+
+    // Darker towards the corners.
+    vec2 from_center = v_tex_coords - 0.5;
+    float vignette = 1.0 - dot(from_center, from_center) * 0.8;
+
+    // A barely visible flicker.
+    float flicker = 0.98 + 0.02 * sin(time * 60.0);
+
+    color = vec4(pixel * scanline * vignette * flicker, 1.0);
+}
@@ -0,0 +1,39 @@
+//! Times `cls` and `rectfill`, which fill whole rows at once, against setting each pixel
+//! with `pset`:
+//!
+//! ```sh
+//! cargo bench -p runty8-core --bench fill
+//! ```
+
+use runty8_core::{Pico8, Resources};
+use std::time::Instant;
+
+const CALLS: u32 = 2000;
+
+fn time(name: &str, pico8: &mut Pico8, draw: impl Fn(&mut Pico8)) {
+    draw(pico8);
+
+    let start = Instant::now();
+    for _ in 0..CALLS {
+        draw(pico8);
+    }
+    let micros = start.elapsed().as_secs_f64() * 1e6 / f64::from(CALLS);
+
+    println!("{name}: {micros:.1}us per screen");
+}
+
+fn main() {
+    let mut pico8 = Pico8::new(Resources::blank("."));
+
+    time("cls", &mut pico8, |pico8| pico8.cls(1));
+    time("rectfill", &mut pico8, |pico8| {
+        pico8.rectfill(0, 0, 127, 127, 2)
+    });
+    time("pset every pixel", &mut pico8, |pico8| {
+        for y in 0..128 {
+            for x in 0..128 {
+                pico8.pset(x, y, 2);
+            }
+        }
+    });
+}
@@ -0,0 +1,47 @@
+//! Times `map` calls big enough for the `parallel-map` feature to kick in.
+//! Compare the two with:
+//!
+//! ```sh
+//! cargo bench -p runty8-core --bench map
+//! cargo bench -p runty8-core --bench map --features parallel-map
+//! ```
+
+use runty8_core::{Pico8, Resources};
+use std::time::Instant;
+
+const CALLS: u32 = 2000;
+
+fn main() {
+    let mut resources = Resources::blank(".");
+    for x in 0..128 {
+        for y in 0..128 {
+            resources.sprite_sheet.set(x, y, ((x + y) % 15 + 1) as u8);
+        }
+    }
+    for x in 0..128 {
+        for y in 0..64 {
+            resources.map.mset(x, y, ((x + y) % 255 + 1) as u8);
+        }
+    }
+    let mut pico8 = Pico8::new(resources);
+
+    // A screenful of tiles, then the whole map (mostly off screen).
+    for (name, w, h) in [("screen", 16, 16), ("whole map", 128, 64)] {
+        pico8.map(0, 0, 0, 0, w, h, 0);
+
+        let start = Instant::now();
+        for _ in 0..CALLS {
+            pico8.map(0, 0, 0, 0, w, h, 0);
+        }
+        let micros = start.elapsed().as_secs_f64() * 1e6 / f64::from(CALLS);
+
+        println!(
+            "map {name} ({w}x{h} cells, parallel-map {}): {micros:.1}us per call",
+            if cfg!(feature = "parallel-map") {
+                "on"
+            } else {
+                "off"
+            },
+        );
+    }
+}
@@ -0,0 +1,717 @@
+//! A software synth playing pico8 sounds, mixing up to [`CHANNELS`] of them at once.
+
+use crate::sfx::{Effect, Instrument, Note, Sound};
+use crate::{Music, Sfx};
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Samples per second of the (mono) audio produced by [`Audio::fill`].
+pub const SAMPLE_RATE: u32 = 22050;
+/// How many sounds can play at the same time.
+pub const CHANNELS: usize = 4;
+// Notes of a speed 1 sound last 183 samples.
+const SAMPLES_PER_TICK: usize = 183;
+// Short fades at the start and end of notes avoid clicks.
+const RAMP_SAMPLES: usize = 64;
+
+/// The sounds being played, shared between the game (which starts them)
+/// and the audio backend (which asks for samples to output).
+#[derive(Clone, Debug, Default)]
+pub struct Audio {
+    mixer: Arc<Mutex<Mixer>>,
+}
+
+impl Audio {
+    /// Fills `samples` with the next [`SAMPLE_RATE`] Hz samples, in `-1.0..=1.0`.
+    pub fn fill(&self, samples: &mut [f32]) {
+        self.mixer().fill(samples);
+    }
+
+    pub(crate) fn mixer(&self) -> MutexGuard<'_, Mixer> {
+        // The mixer stays usable even if a thread panicked while holding it.
+        self.mixer.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Mixer {
+    channels: [Option<Channel>; CHANNELS],
+    music: Option<MusicPlayer>,
+}
+
+#[derive(Debug)]
+struct MusicPlayer {
+    // Copies of the game's assets, as the audio thread can't borrow them.
+    sfx: Sfx,
+    music: Music,
+    pattern: usize,
+    // Samples left until the next pattern.
+    remaining: usize,
+    // Channels sound effects only use if every other channel is busy.
+    channel_mask: u8,
+    volume: f32,
+    // Added to `volume` after every sample, while fading in or out.
+    fade_step: f32,
+}
+
+impl MusicPlayer {
+    /// The pattern to play after the current one.
+    fn next_pattern(&self) -> Option<usize> {
+        let current = self.music.get(self.pattern)?;
+
+        if current.stop {
+            None
+        } else if current.loop_back {
+            let loop_start = (0..self.pattern)
+                .rev()
+                .find(|&pattern| self.music.get(pattern).map_or(false, |p| p.loop_start));
+
+            Some(loop_start.unwrap_or(self.pattern))
+        } else {
+            let next = self.pattern + 1;
+
+            self.music
+                .get(next)
+                .filter(|pattern| !pattern.is_empty())
+                .map(|_| next)
+        }
+    }
+}
+
+/// How much the volume changes per sample, to fade in `fade_millis`.
+fn fade_step(fade_millis: u32) -> f32 {
+    1000.0 / (fade_millis.max(1) as f32 * SAMPLE_RATE as f32)
+}
+
+impl Mixer {
+    /// Plays `sound` on `channel`, or on a free one if `None`.
+    /// Starts at note `offset`, and plays `length` notes (or until the end, looping if the sound loops).
+    pub(crate) fn play(
+        &mut self,
+        sound_id: usize,
+        sound: &Sound,
+        channel: Option<usize>,
+        offset: usize,
+        length: Option<usize>,
+    ) {
+        let channel = channel.unwrap_or_else(|| self.free_channel(sound_id));
+        if let Some(slot) = self.channels.get_mut(channel) {
+            let channel = Channel::new(sound_id, sound.clone(), offset, length);
+            // Offsets past the end have nothing to play.
+            *slot = (channel.note < channel.end).then_some(channel);
+        }
+    }
+
+    /// An idle channel (preferably one that isn't reserved for music),
+    /// or else one already playing `sound_id`, or else the first one.
+    fn free_channel(&self, sound_id: usize) -> usize {
+        let reserved = self.music.as_ref().map_or(0, |music| music.channel_mask);
+        let position = |predicate: &dyn Fn(usize, &Option<Channel>) -> bool| {
+            (0..CHANNELS).find(|&index| predicate(index, &self.channels[index]))
+        };
+
+        position(&|index, channel| channel.is_none() && reserved & (1 << index) == 0)
+            .or_else(|| position(&|_, channel| channel.is_none()))
+            .or_else(|| {
+                position(&|_, channel| channel.as_ref().map(|c| c.sound_id) == Some(sound_id))
+            })
+            .unwrap_or(0)
+    }
+
+    /// Plays the music starting at `pattern`, fading in for `fade_millis`.
+    /// Sound effects avoid the channels in `channel_mask` while it plays.
+    pub(crate) fn play_music(
+        &mut self,
+        pattern: usize,
+        sfx: &Sfx,
+        music: &Music,
+        fade_millis: u32,
+        channel_mask: u8,
+    ) {
+        self.stop_music(0);
+        if music.get(pattern).is_none() {
+            return;
+        }
+
+        self.music = Some(MusicPlayer {
+            sfx: sfx.clone(),
+            music: music.clone(),
+            pattern,
+            remaining: 0,
+            channel_mask,
+            volume: if fade_millis == 0 { 1.0 } else { 0.0 },
+            fade_step: if fade_millis == 0 {
+                0.0
+            } else {
+                fade_step(fade_millis)
+            },
+        });
+        self.start_pattern(pattern);
+    }
+
+    /// Stops the music, fading out for `fade_millis` first.
+    pub(crate) fn stop_music(&mut self, fade_millis: u32) {
+        match self.music.as_mut() {
+            Some(music) if fade_millis > 0 => music.fade_step = -fade_step(fade_millis),
+            _ => {
+                self.music = None;
+                for slot in self.channels.iter_mut() {
+                    if slot.as_ref().map_or(false, |channel| channel.music) {
+                        *slot = None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The music pattern being played.
+    pub(crate) fn music_pattern(&self) -> Option<usize> {
+        self.music.as_ref().map(|music| music.pattern)
+    }
+
+    fn start_pattern(&mut self, index: usize) {
+        let Some(player) = self.music.as_mut() else {
+            return;
+        };
+        let Some(pattern) = player
+            .music
+            .get(index)
+            .filter(|pattern| !pattern.is_empty())
+        else {
+            self.stop_music(0);
+            return;
+        };
+
+        player.pattern = index;
+        // The leftmost channel that doesn't loop decides how long the pattern lasts,
+        // or the leftmost channel if they all loop.
+        let mut length = None;
+        let mut looping_length = None;
+
+        for (slot, sound_id) in self.channels.iter_mut().zip(pattern.channels) {
+            let sound = sound_id.and_then(|sound_id| player.sfx.get(sound_id.into()));
+            match (sound_id, sound) {
+                (Some(sound_id), Some(sound)) => {
+                    let mut channel = Channel::new(sound_id.into(), sound.clone(), 0, None);
+                    channel.music = true;
+
+                    let samples = Sound::NOTES * channel.note_samples();
+                    if sound.loops() {
+                        looping_length.get_or_insert(samples);
+                    } else {
+                        length.get_or_insert(samples);
+                    }
+
+                    *slot = Some(channel);
+                }
+                _ => {
+                    if slot.as_ref().map_or(false, |channel| channel.music) {
+                        *slot = None;
+                    }
+                }
+            }
+        }
+
+        player.remaining = length.or(looping_length).unwrap_or(0);
+    }
+
+    /// Moves the music along by a sample, returning its volume.
+    fn advance_music(&mut self) -> f32 {
+        let Some(player) = self.music.as_mut() else {
+            return 0.0;
+        };
+
+        player.volume += player.fade_step;
+        if player.volume >= 1.0 {
+            player.volume = 1.0;
+            player.fade_step = 0.0;
+        } else if player.volume <= 0.0 && player.fade_step < 0.0 {
+            self.stop_music(0);
+            return 0.0;
+        }
+
+        if player.remaining > 0 {
+            player.remaining -= 1;
+        } else {
+            match player.next_pattern() {
+                Some(next) => self.start_pattern(next),
+                None => self.stop_music(0),
+            }
+        }
+
+        self.music.as_ref().map_or(0.0, |music| music.volume)
+    }
+
+    /// Stops whatever `channel` is playing, or every channel if `None`.
+    pub(crate) fn stop(&mut self, channel: Option<usize>) {
+        match channel {
+            Some(channel) => {
+                if let Some(channel) = self.channels.get_mut(channel) {
+                    *channel = None;
+                }
+            }
+            None => self.channels = Default::default(),
+        }
+    }
+
+    /// The sound playing on `channel`.
+    pub(crate) fn playing(&self, channel: usize) -> Option<usize> {
+        self.channels
+            .get(channel)?
+            .as_ref()
+            .map(|channel| channel.sound_id)
+    }
+
+    fn fill(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            let music_volume = self.advance_music();
+            let mut mixed = 0.0;
+
+            for slot in self.channels.iter_mut() {
+                if let Some(channel) = slot {
+                    let volume = if channel.music { music_volume } else { 1.0 };
+
+                    match channel.next_sample() {
+                        Some(value) => mixed += value * volume,
+                        None => *slot = None,
+                    }
+                }
+            }
+
+            // Leaves some headroom for several channels playing at once.
+            *sample = (mixed * 0.4).clamp(-1.0, 1.0);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Channel {
+    sound_id: usize,
+    // Whether the channel was started by the music.
+    music: bool,
+    sound: Sound,
+    // Index of the note being played, and how many of its samples were already played.
+    note: usize,
+    sample: usize,
+    // Index of the last note to play, plus one, if not looping.
+    end: usize,
+    looping: bool,
+    previous: Option<Note>,
+    // Samples played since the sound started.
+    elapsed: usize,
+    phase: f32,
+    detuned_phase: f32,
+    noise: f32,
+    noise_seed: u32,
+}
+
+impl Channel {
+    fn new(sound_id: usize, sound: Sound, offset: usize, length: Option<usize>) -> Self {
+        let offset = offset.min(Sound::NOTES);
+        let end = length.map_or(Sound::NOTES, |length| offset.saturating_add(length));
+
+        Self {
+            sound_id,
+            music: false,
+            looping: length.is_none() && sound.loops(),
+            end: end.min(Sound::NOTES),
+            note: offset,
+            sample: 0,
+            sound,
+            previous: None,
+            elapsed: 0,
+            phase: 0.0,
+            detuned_phase: 0.0,
+            noise: 0.0,
+            noise_seed: 0x1234_5678,
+        }
+    }
+
+    fn note_samples(&self) -> usize {
+        SAMPLES_PER_TICK * self.sound.speed.max(1) as usize
+    }
+
+    /// The next sample, or `None` once the sound is over.
+    fn next_sample(&mut self) -> Option<f32> {
+        if self.note >= self.end {
+            return None;
+        }
+
+        let note = self.sound.notes[self.note];
+        let note_samples = self.note_samples();
+        let progress = self.sample as f32 / note_samples as f32;
+        let (pitch, volume) = self.apply_effect(note, progress);
+
+        let step = frequency(pitch) / SAMPLE_RATE as f32;
+        let wave = self.waveform(note.instrument, step);
+        let value = wave * volume / 7.0 * self.envelope(note_samples);
+
+        self.elapsed += 1;
+        self.sample += 1;
+        if self.sample == note_samples {
+            self.sample = 0;
+            self.previous = Some(note);
+            self.note += 1;
+
+            if self.looping && self.note == self.sound.loop_end as usize {
+                self.note = self.sound.loop_start as usize;
+            }
+        }
+
+        Some(value)
+    }
+
+    /// Pitch (in semitones) and volume (in `0.0..=7.0`) of `note` at `progress` (`0.0..1.0`) through it.
+    fn apply_effect(&self, note: Note, progress: f32) -> (f32, f32) {
+        let pitch = note.pitch as f32;
+        let volume = note.volume as f32;
+
+        match note.effect {
+            Effect::None => (pitch, volume),
+            Effect::Slide => {
+                let previous = self.previous.unwrap_or(note);
+                let lerp = |from: u8, to: f32| from as f32 + (to - from as f32) * progress;
+
+                (lerp(previous.pitch, pitch), lerp(previous.volume, volume))
+            }
+            Effect::Vibrato => {
+                let seconds = self.elapsed as f32 / SAMPLE_RATE as f32;
+
+                (pitch + 0.5 * (2.0 * PI * 7.5 * seconds).sin(), volume)
+            }
+            Effect::Drop => (pitch * (1.0 - progress), volume),
+            Effect::FadeIn => (pitch, volume * progress),
+            Effect::FadeOut => (pitch, volume * (1.0 - progress)),
+            Effect::ArpFast | Effect::ArpSlow => {
+                let ticks_per_note = if note.effect == Effect::ArpFast { 4 } else { 8 };
+                let step = (self.elapsed / SAMPLES_PER_TICK / ticks_per_note) % 4;
+                let group = self.note & !3;
+
+                (self.sound.notes[group + step].pitch as f32, volume)
+            }
+        }
+    }
+
+    /// Fades in notes that follow silence, and fades out notes followed by silence.
+    fn envelope(&self, note_samples: usize) -> f32 {
+        let silent = |note: Option<&Note>| note.map_or(true, |note| note.volume == 0);
+        let ramp = |samples: usize| (samples as f32 / RAMP_SAMPLES as f32).min(1.0);
+
+        let mut envelope = 1.0;
+        if silent(self.previous.as_ref()) {
+            envelope *= ramp(self.sample);
+        }
+        if self.note + 1 >= self.end || silent(self.sound.notes.get(self.note + 1)) {
+            envelope *= ramp(note_samples - self.sample);
+        }
+
+        envelope
+    }
+
+    /// Advances the waveform by `step` periods and returns its value, in `-1.0..=1.0`.
+    fn waveform(&mut self, instrument: Instrument, step: f32) -> f32 {
+        let previous_phase = self.phase;
+        self.phase = (self.phase + step).fract();
+        self.detuned_phase = (self.detuned_phase + step * 1.01).fract();
+        let phase = self.phase;
+
+        fn triangle(phase: f32) -> f32 {
+            4.0 * (phase - 0.5).abs() - 1.0
+        }
+
+        match instrument {
+            Instrument::Triangle => triangle(phase),
+            Instrument::TiltedSaw => {
+                if phase < 0.875 {
+                    2.0 * phase / 0.875 - 1.0
+                } else {
+                    1.0 - 2.0 * (phase - 0.875) / 0.125
+                }
+            }
+            Instrument::Saw => 2.0 * phase - 1.0,
+            Instrument::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Instrument::Pulse => {
+                if phase < 0.3125 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Instrument::Organ => (triangle(phase) + triangle((2.0 * phase).fract())) / 2.0,
+            Instrument::Noise => {
+                // A new random value twice per period, so noise follows the pitch.
+                if (phase < 0.5) != (previous_phase < 0.5) {
+                    // xorshift32
+                    self.noise_seed ^= self.noise_seed << 13;
+                    self.noise_seed ^= self.noise_seed >> 17;
+                    self.noise_seed ^= self.noise_seed << 5;
+                    self.noise = self.noise_seed as f32 / u32::MAX as f32 * 2.0 - 1.0;
+                }
+
+                self.noise
+            }
+            // Two slightly detuned triangles drifting in and out of phase.
+            Instrument::Phaser => (triangle(phase) + triangle(self.detuned_phase)) / 2.0,
+        }
+    }
+}
+
+/// Frequency, in Hz, of the note `pitch` semitones above C-0 (A-2, pitch 33, being 440 Hz).
+fn frequency(pitch: f32) -> f32 {
+    440.0 * 2f32.powf((pitch - 33.0) / 12.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{frequency, Audio, Mixer, SAMPLES_PER_TICK};
+    use crate::sfx::{Instrument, Note, Sound};
+    use crate::{Music, Pattern, Sfx};
+
+    fn sound(instrument: Instrument) -> Sound {
+        let mut sound = Sound {
+            speed: 1,
+            ..Sound::default()
+        };
+        sound.notes[0] = Note {
+            pitch: 33,
+            instrument,
+            volume: 7,
+            ..Note::default()
+        };
+
+        sound
+    }
+
+    #[test]
+    fn a_2_is_440_hz() {
+        assert!((frequency(33.0) - 440.0).abs() < 0.001);
+        assert!((frequency(45.0) - 880.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn sounds_stop_after_their_last_note() {
+        let mut mixer = Mixer::default();
+        mixer.play(3, &sound(Instrument::Square), None, 0, None);
+        assert_eq!(mixer.playing(0), Some(3));
+
+        let mut samples = vec![0.0; SAMPLES_PER_TICK * 32];
+        mixer.fill(&mut samples);
+
+        assert!(samples[..SAMPLES_PER_TICK].iter().any(|&s| s != 0.0));
+        assert!(samples[SAMPLES_PER_TICK..].iter().all(|&s| s == 0.0));
+        // The last sample was generated, the channel notices it's done on the next one.
+        mixer.fill(&mut [0.0]);
+        assert_eq!(mixer.playing(0), None);
+    }
+
+    #[test]
+    fn length_limits_notes_played() {
+        let mut mixer = Mixer::default();
+        mixer.play(0, &sound(Instrument::Saw), Some(2), 0, Some(1));
+
+        let mut samples = vec![0.0; SAMPLES_PER_TICK + 1];
+        mixer.fill(&mut samples);
+
+        assert_eq!(mixer.playing(2), None);
+    }
+
+    #[test]
+    fn looping_sounds_keep_playing() {
+        let mut sound = sound(Instrument::Triangle);
+        sound.loop_start = 0;
+        sound.loop_end = 1;
+        let mut mixer = Mixer::default();
+        mixer.play(0, &sound, None, 0, None);
+
+        let mut samples = vec![0.0; SAMPLES_PER_TICK * 100];
+        mixer.fill(&mut samples);
+
+        assert_eq!(mixer.playing(0), Some(0));
+        assert!(samples[SAMPLES_PER_TICK * 99..].iter().any(|&s| s != 0.0));
+    }
+
+    #[test]
+    fn sounds_use_free_channels() {
+        let audio = Audio::default();
+        let mut mixer = audio.mixer();
+
+        for sound_id in 0..5 {
+            mixer.play(sound_id, &sound(Instrument::Noise), None, 0, None);
+        }
+
+        // The fifth sound replaces the first one.
+        assert_eq!(mixer.playing(0), Some(4));
+        assert_eq!(mixer.playing(3), Some(3));
+
+        mixer.stop(Some(3));
+        assert_eq!(mixer.playing(3), None);
+        mixer.stop(None);
+        assert!((0..4).all(|channel| mixer.playing(channel).is_none()));
+    }
+
+    #[test]
+    fn waveforms_stay_in_range() {
+        for instrument in [
+            Instrument::Triangle,
+            Instrument::TiltedSaw,
+            Instrument::Saw,
+            Instrument::Square,
+            Instrument::Pulse,
+            Instrument::Organ,
+            Instrument::Noise,
+            Instrument::Phaser,
+        ] {
+            let mut mixer = Mixer::default();
+            mixer.play(0, &sound(instrument), None, 0, None);
+
+            let mut samples = vec![0.0; SAMPLES_PER_TICK];
+            mixer.fill(&mut samples);
+
+            assert!(
+                samples.iter().all(|s| (-1.0..=1.0).contains(s)),
+                "{instrument:?}"
+            );
+            assert!(samples.iter().any(|s| s.abs() > 0.05), "{instrument:?}");
+        }
+    }
+
+    // Sound 0 lasts a note, sound 1 lasts 2 and loops.
+    fn song(patterns: &[Pattern]) -> (Sfx, Music) {
+        let mut sfx = Sfx::new();
+        *sfx.get_mut(0).unwrap() = sound(Instrument::Square);
+        *sfx.get_mut(1).unwrap() = Sound {
+            loop_start: 0,
+            loop_end: 2,
+            ..sound(Instrument::Saw)
+        };
+
+        let mut music = Music::new();
+        for (index, pattern) in patterns.iter().enumerate() {
+            *music.get_mut(index).unwrap() = pattern.clone();
+        }
+
+        (sfx, music)
+    }
+
+    fn pattern(channels: [Option<u8>; 4]) -> Pattern {
+        Pattern {
+            channels,
+            ..Pattern::default()
+        }
+    }
+
+    const PATTERN_SAMPLES: usize = SAMPLES_PER_TICK * Sound::NOTES;
+
+    #[test]
+    fn music_plays_patterns_in_order() {
+        let (sfx, music) = song(&[
+            pattern([Some(1), Some(0), None, None]),
+            pattern([None, None, Some(0), None]),
+        ]);
+        let mut mixer = Mixer::default();
+
+        mixer.play_music(0, &sfx, &music, 0, 0);
+        assert_eq!(mixer.music_pattern(), Some(0));
+        assert_eq!((mixer.playing(0), mixer.playing(1)), (Some(1), Some(0)));
+
+        // The non looping sound decides when the pattern ends.
+        mixer.fill(&mut vec![0.0; PATTERN_SAMPLES + 1]);
+        assert_eq!(mixer.music_pattern(), Some(1));
+        assert_eq!(mixer.playing(0), None);
+        assert_eq!(mixer.playing(2), Some(0));
+
+        // The next pattern is empty, so the music stops.
+        mixer.fill(&mut vec![0.0; PATTERN_SAMPLES + 1]);
+        assert_eq!(mixer.music_pattern(), None);
+    }
+
+    #[test]
+    fn music_loops_back_and_stops() {
+        let (sfx, music) = song(&[
+            pattern([Some(0), None, None, None]),
+            Pattern {
+                loop_start: true,
+                ..pattern([Some(0), None, None, None])
+            },
+            Pattern {
+                loop_back: true,
+                ..pattern([Some(0), None, None, None])
+            },
+        ]);
+        let mut mixer = Mixer::default();
+        mixer.play_music(0, &sfx, &music, 0, 0);
+
+        let mut patterns = vec![];
+        for _ in 0..5 {
+            mixer.fill(&mut vec![0.0; PATTERN_SAMPLES + 1]);
+            patterns.push(mixer.music_pattern());
+        }
+        assert_eq!(patterns, [Some(1), Some(2), Some(1), Some(2), Some(1)]);
+
+        let (sfx, music) = song(&[
+            Pattern {
+                stop: true,
+                ..pattern([Some(0), None, None, None])
+            },
+            pattern([Some(0), None, None, None]),
+        ]);
+        mixer.play_music(0, &sfx, &music, 0, 0);
+        mixer.fill(&mut vec![0.0; PATTERN_SAMPLES + 1]);
+        assert_eq!(mixer.music_pattern(), None);
+    }
+
+    #[test]
+    fn music_fades_out() {
+        // Loops forever, unless stopped.
+        let (sfx, music) = song(&[Pattern {
+            loop_back: true,
+            ..pattern([Some(1), None, None, None])
+        }]);
+        let mut mixer = Mixer::default();
+        mixer.play_music(0, &sfx, &music, 0, 0);
+
+        mixer.stop_music(1000);
+        mixer.fill(&mut vec![0.0; super::SAMPLE_RATE as usize / 2]);
+        assert_eq!(mixer.music_pattern(), Some(0));
+
+        mixer.fill(&mut vec![0.0; super::SAMPLE_RATE as usize / 2 + 1]);
+        assert_eq!(mixer.music_pattern(), None);
+        assert_eq!(mixer.playing(0), None);
+    }
+
+    #[test]
+    fn sfx_avoid_music_channels() {
+        let (sfx, music) = song(&[pattern([Some(1), None, None, None])]);
+        let mut mixer = Mixer::default();
+        mixer.play_music(0, &sfx, &music, 0, 0b0011);
+
+        mixer.play(0, &sound(Instrument::Square), None, 0, None);
+
+        assert_eq!(mixer.playing(2), Some(0));
+    }
+
+    #[test]
+    fn out_of_range_offsets_and_lengths_are_clamped() {
+        let mut sound = sound(Instrument::Square);
+        sound.notes = [sound.notes[0]; Sound::NOTES];
+        let mut mixer = Mixer::default();
+
+        // Only the last two notes are left to play.
+        mixer.play(0, &sound, Some(0), 30, Some(usize::MAX));
+        mixer.play(1, &sound, Some(1), 1000, Some(2));
+        assert_eq!((mixer.playing(0), mixer.playing(1)), (Some(0), None));
+
+        let mut samples = vec![0.0; 4 * SAMPLES_PER_TICK];
+        mixer.fill(&mut samples);
+        let (playing, after) = samples.split_at(2 * SAMPLES_PER_TICK);
+        assert!(playing.iter().any(|&sample| sample != 0.0));
+        assert!(after.iter().all(|&sample| sample == 0.0));
+        assert_eq!(mixer.playing(0), None);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{Button, GamepadButton, Key};
+
+/// A physical input that can be bound to one of Pico8's buttons.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PhysicalInput {
+    /// A keyboard key.
+    Key(Key),
+    /// A gamepad button.
+    Gamepad(GamepadButton),
+}
+
+impl From<Key> for PhysicalInput {
+    fn from(key: Key) -> Self {
+        Self::Key(key)
+    }
+}
+
+impl From<GamepadButton> for PhysicalInput {
+    fn from(button: GamepadButton) -> Self {
+        Self::Gamepad(button)
+    }
+}
+
+/// Which physical inputs press which Pico8 buttons.
+///
+/// A button can have any number of bindings, so keyboard and gamepad work at the same time:
+///
+/// ```
+/// use runty8_core::{Button, ButtonMap, GamepadButton, Key};
+///
+/// let button_map = ButtonMap::empty()
+///     .bind(Key::Z, Button::Circle)
+///     .bind(GamepadButton::South, Button::Circle);
+///
+/// assert_eq!(button_map.button(Key::Z.into()), Some(Button::Circle));
+/// ```
+///
+/// The left gamepad stick always drives the directional buttons.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ButtonMap {
+    bindings: Vec<(PhysicalInput, Button)>,
+}
+
+impl ButtonMap {
+    /// A map with no bindings at all.
+    pub fn empty() -> Self {
+        Self { bindings: vec![] }
+    }
+
+    /// Binds `input` to `button`, replacing the input's previous binding (if any).
+    pub fn bind(mut self, input: impl Into<PhysicalInput>, button: Button) -> Self {
+        let input = input.into();
+        self.bindings.retain(|&(bound, _)| bound != input);
+        self.bindings.push((input, button));
+
+        self
+    }
+
+    /// Removes every binding to `button`.
+    pub fn unbind(mut self, button: Button) -> Self {
+        self.bindings.retain(|&(_, bound)| bound != button);
+
+        self
+    }
+
+    /// The button `input` is bound to.
+    pub fn button(&self, input: PhysicalInput) -> Option<Button> {
+        self.bindings
+            .iter()
+            .find(|&&(bound, _)| bound == input)
+            .map(|&(_, button)| button)
+    }
+
+    /// Every input bound to `button`.
+    pub fn inputs(&self, button: Button) -> impl Iterator<Item = PhysicalInput> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |&&(_, bound)| bound == button)
+            .map(|&(input, _)| input)
+    }
+}
+
+impl Default for ButtonMap {
+    /// X/Escape, C/Space and P on the keyboard, A/B, the d-pad and Start on gamepads (see [`GamepadButton`]).
+    fn default() -> Self {
+        Self::empty()
+            .bind(Key::X, Button::Cross)
+            .bind(Key::Escape, Button::Cross)
+            .bind(Key::C, Button::Circle)
+            .bind(Key::Enter, Button::Circle)
+            .bind(Key::LeftArrow, Button::Left)
+            .bind(Key::RightArrow, Button::Right)
+            .bind(Key::UpArrow, Button::Up)
+            .bind(Key::DownArrow, Button::Down)
+            .bind(GamepadButton::East, Button::Cross)
+            .bind(GamepadButton::South, Button::Circle)
+            .bind(GamepadButton::DPadLeft, Button::Left)
+            .bind(GamepadButton::DPadRight, Button::Right)
+            .bind(GamepadButton::DPadUp, Button::Up)
+            .bind(GamepadButton::DPadDown, Button::Down)
+            .bind(Key::P, Button::Pause)
+            .bind(GamepadButton::Start, Button::Pause)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ButtonMap, PhysicalInput};
+    use crate::{Button, GamepadButton, Key};
+
+    #[test]
+    fn binding_an_input_again_replaces_it() {
+        let button_map = ButtonMap::empty()
+            .bind(Key::Z, Button::Circle)
+            .bind(Key::Z, Button::Cross);
+
+        assert_eq!(button_map.button(Key::Z.into()), Some(Button::Cross));
+        assert_eq!(button_map.inputs(Button::Circle).count(), 0);
+    }
+
+    #[test]
+    fn buttons_can_have_many_inputs() {
+        let inputs: Vec<PhysicalInput> = ButtonMap::default().inputs(Button::Circle).collect();
+
+        assert_eq!(
+            inputs,
+            vec![
+                Key::C.into(),
+                Key::Enter.into(),
+                GamepadButton::South.into()
+            ]
+        );
+    }
+
+    #[test]
+    fn unbind_removes_every_input() {
+        let button_map = ButtonMap::default().unbind(Button::Left);
+
+        assert_eq!(button_map.inputs(Button::Left).count(), 0);
+        assert_eq!(button_map.button(Key::LeftArrow.into()), None);
+    }
+}
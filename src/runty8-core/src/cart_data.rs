@@ -0,0 +1,134 @@
+//! Pico8's [persistent cart data](<https://pico-8.fandom.com/wiki/Cartdata>), for `dget` and `dset`.
+//!
+//! Each cart id gets its own file in the assets path (local storage on the web),
+//! which is rewritten on every `dset`.
+
+use itertools::Itertools;
+
+use crate::serialize::{self, Serialize};
+
+/// How many numbers a cart can persist.
+pub(crate) const LENGTH: usize = 256;
+
+#[derive(Debug)]
+pub(crate) struct CartData {
+    id: String,
+    values: [f32; LENGTH],
+}
+
+impl CartData {
+    pub(crate) fn file_name(id: &str) -> String {
+        format!("cart_data_{id}.txt")
+    }
+
+    /// Pico8 ids are up to 64 lowercase letters, digits and underscores,
+    /// which also keeps them safe to use in file names.
+    pub(crate) fn is_valid_id(id: &str) -> bool {
+        !id.is_empty()
+            && id.len() <= 64
+            && id
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    }
+
+    /// Loads the data saved for `id`, or starts with all zeroes.
+    /// Also returns whether there was saved data.
+    pub(crate) fn load(assets_path: &str, id: &str) -> (Self, bool) {
+        let file_path = format!("{assets_path}/{}", Self::file_name(id));
+        let saved = serialize::read(&file_path).and_then(|contents| {
+            Self::deserialize(&contents)
+                .map_err(|error| log::warn!("Ignoring invalid cart data in {file_path}: {error}"))
+                .ok()
+        });
+
+        let found = saved.is_some();
+        let values = saved.unwrap_or([0.0; LENGTH]);
+
+        (
+            Self {
+                id: id.to_owned(),
+                values,
+            },
+            found,
+        )
+    }
+
+    /// 0 outside of `0..256`.
+    pub(crate) fn get(&self, index: usize) -> f32 {
+        self.values.get(index).copied().unwrap_or(0.0)
+    }
+
+    /// Sets and saves the value at `index`, ignored outside of `0..256`.
+    pub(crate) fn set(&mut self, assets_path: &str, index: usize, value: f32) {
+        if let Some(stored) = self.values.get_mut(index) {
+            *stored = value;
+            serialize::serialize(assets_path, &Self::file_name(&self.id), self);
+        }
+    }
+
+    fn deserialize(contents: &str) -> Result<[f32; LENGTH], String> {
+        let mut values = [0.0; LENGTH];
+
+        for (index, line) in contents.lines().enumerate() {
+            let value = values
+                .get_mut(index)
+                .ok_or_else(|| format!("More than {LENGTH} values"))?;
+
+            *value = line
+                .trim()
+                .parse()
+                .map_err(|error| format!("Line {}: {error}", index + 1))?;
+        }
+
+        Ok(values)
+    }
+}
+
+impl Serialize for CartData {
+    fn serialize(&self) -> String {
+        self.values.iter().join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Pico8, Resources};
+
+    fn pico8(assets_path: &str) -> Pico8 {
+        Pico8::new(Resources::blank(assets_path))
+    }
+
+    #[test]
+    fn dset_survives_reloading_the_cart() {
+        let assets_path = std::env::temp_dir().join("runty8-cart-data-test");
+        std::fs::create_dir_all(&assets_path).unwrap();
+        let assets_path = assets_path.to_str().unwrap();
+        let _ = std::fs::remove_file(format!("{assets_path}/cart_data_high_scores.txt"));
+
+        let mut first_run = pico8(assets_path);
+        assert!(!first_run.cartdata("high_scores"));
+        first_run.dset(3, 42.5);
+        first_run.dset(255, -1.25);
+        // Ignored, like reads past the end.
+        first_run.dset(256, 7.0);
+        drop(first_run);
+
+        let mut second_run = pico8(assets_path);
+        assert!(second_run.cartdata("high_scores"));
+        assert_eq!(second_run.dget(3), 42.5);
+        assert_eq!(second_run.dget(255), -1.25);
+        assert_eq!(second_run.dget(0), 0.0);
+        assert_eq!(second_run.dget(256), 0.0);
+    }
+
+    #[test]
+    fn dget_and_dset_need_cartdata() {
+        let mut pico8 = pico8(".");
+
+        pico8.dset(0, 1.0);
+        assert_eq!(pico8.dget(0), 0.0);
+
+        assert!(!pico8.cartdata("Not A Valid Id"));
+        assert!(!pico8.cartdata("../escaping"));
+    }
+}
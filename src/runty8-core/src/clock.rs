@@ -0,0 +1,110 @@
+//! Wall clock time, for `stat(80..=85)` and `stat(90..=95)`.
+
+/// The current UTC date and time: year, month, day, hour, minute and second.
+///
+/// Web builds don't have access to a clock yet, and get all zeros.
+pub(crate) fn now() -> [u32; 6] {
+    #[cfg(not(target_arch = "wasm32"))]
+    return date_time(unix_secs());
+
+    #[cfg(target_arch = "wasm32")]
+    [0; 6]
+}
+
+/// Like [`now`], in the local time zone.
+///
+/// Only Unix systems know their time zone here: elsewhere this is UTC.
+pub(crate) fn local_now() -> [u32; 6] {
+    #[cfg(unix)]
+    {
+        let unix_secs = unix_secs();
+        let local_secs = unix_secs as i64 + utc_offset_secs(unix_secs);
+
+        date_time(local_secs.max(0) as u64)
+    }
+
+    #[cfg(not(unix))]
+    now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn unix_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// How far ahead of UTC the local time zone is at `unix_secs`, 0 if it's unknown.
+#[cfg(unix)]
+fn utc_offset_secs(unix_secs: u64) -> i64 {
+    let Ok(time) = libc::time_t::try_from(unix_secs) else {
+        return 0;
+    };
+    // Safety: `localtime_r` only writes to `local`, which is a plain C struct.
+    let mut local: libc::tm = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::localtime_r(&time, &mut local) }.is_null() {
+        0
+    } else {
+        local.tm_gmtoff as i64
+    }
+}
+
+/// Splits seconds since the Unix epoch into a UTC date and time.
+fn date_time(unix_secs: u64) -> [u32; 6] {
+    let (days, secs) = (unix_secs / 86400, unix_secs % 86400);
+    let [year, month, day] = civil_from_days(days);
+
+    [
+        year,
+        month,
+        day,
+        (secs / 3600) as u32,
+        (secs / 60 % 60) as u32,
+        (secs % 60) as u32,
+    ]
+}
+
+/// Year, month and day of `days` since 1970-01-01,
+/// from <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: u64) -> [u32; 3] {
+    // Counted from 0000-03-01, so that leap days end the year.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    [year as u32, month as u32, day as u32]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::date_time;
+
+    #[cfg(unix)]
+    #[test]
+    fn utc_offsets_are_within_a_day() {
+        let offset = super::utc_offset_secs(1_700_000_000);
+
+        assert!(offset.abs() <= 14 * 3600);
+        assert_eq!(offset % (15 * 60), 0);
+    }
+
+    #[test]
+    fn splits_unix_time() {
+        assert_eq!(date_time(0), [1970, 1, 1, 0, 0, 0]);
+        assert_eq!(date_time(951_825_599), [2000, 2, 29, 11, 59, 59]);
+        assert_eq!(date_time(1_700_000_000), [2023, 11, 14, 22, 13, 20]);
+    }
+}
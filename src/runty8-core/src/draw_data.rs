@@ -1,50 +1,156 @@
 use crate::flags::Flags;
+use crate::font::CustomFont;
 use crate::map::Map;
 use crate::sprite_sheet::SpriteSheet;
+use crate::text::{self, TextOp};
 use crate::util::{min_max, MinMax};
 use crate::Color;
 use crate::{draw, font};
+use std::ops::Range;
 
 use crate::sprite_sheet::Sprite;
+#[cfg(all(feature = "parallel-map", not(target_arch = "wasm32")))]
+use crate::thread_pool;
 
+/// Pico8's screen size, which games get unless they ask for another one.
 const WIDTH: usize = 128;
 const NUM_COMPONENTS: usize = 3;
+const SPRITE_SHEET_WIDTH: i32 = 128;
 
-/// A raw buffer made up of `RGB` components: [R, G, B, R, G, B, ...].
-type Buffer = [u8; NUM_COMPONENTS * WIDTH * WIDTH];
-const BLACK_BUFFER: Buffer = [0; NUM_COMPONENTS * WIDTH * WIDTH];
+// Only black is transparent.
+const DEFAULT_TRANSPARENCY: u16 = 1;
 
+// Pico8's pen color when a cart starts, and after `color()`.
+pub(crate) const DEFAULT_PEN: Color = 6;
 const ORIGINAL_PALETTE: [Color; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
 
 // Handles actually drawing to the screen buffer
 #[derive(Debug)]
 pub struct DrawData {
-    buffer: Buffer,
+    width: usize,
+    height: usize,
+    // What's drawn on screen, as colors of the draw palette, row by row.
+    pixels: Vec<Color>,
+    // `pixels` converted to RGB through the screen palette: [R, G, B, R, G, B, ...].
+    buffer: Vec<u8>,
     // Maybe these properties below should be in internal state?
-    transparent_color: Option<Color>,
+    // Bit `c` is set if color `c` is transparent.
+    transparency: u16,
     draw_palette: [Color; 16],
+    screen_palette: [Color; 16],
     camera: (i32, i32),
+    clip: ClipRect,
+    // See `DrawData::fillp`
+    fill_pattern: u16,
+    fill_transparent: bool,
+    // Where `print_at_cursor` prints next.
+    cursor: (i32, i32),
+    // The last color drawn with, see `DrawData::color`.
+    pen: Color,
+    // Where `line_to` continues from, see `DrawData::set_line_end`.
+    line_end: Option<(i32, i32)>,
+    // Replaces the built-in font, see `DrawData::set_font`.
+    font: Option<Box<CustomFont>>,
+}
+
+/// Screen region that drawing is restricted to, see [`DrawData::clip`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ClipRect {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl ClipRect {
+    fn contains(&self, x: i32, y: i32) -> bool {
+        self.x <= x && x < self.right() && self.y <= y && y < self.bottom()
+    }
+
+    /// The column right after the rectangle. Saturates, as games can pass any size.
+    fn right(&self) -> i32 {
+        self.x.saturating_add(self.w)
+    }
+
+    /// The row right below the rectangle. Saturates like [`ClipRect::right`].
+    fn bottom(&self) -> i32 {
+        self.y.saturating_add(self.h)
+    }
+
+    /// The part of `self` that's also inside `other`.
+    fn intersect(self, other: Self) -> Self {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = self.right().min(other.right());
+        let y1 = self.bottom().min(other.bottom());
+
+        Self {
+            x: x0,
+            y: y0,
+            w: x1.saturating_sub(x0).max(0),
+            h: y1.saturating_sub(y0).max(0),
+        }
+    }
 }
 
 impl DrawData {
     pub fn new() -> Self {
+        Self::with_size(WIDTH, WIDTH)
+    }
+
+    /// A screen `width` by `height` pixels big, instead of pico8's 128 by 128.
+    pub fn with_size(width: usize, height: usize) -> Self {
         DrawData {
-            buffer: BLACK_BUFFER,
-            transparent_color: Some(0),
+            width,
+            height,
+            pixels: vec![0; width * height],
+            buffer: vec![0; NUM_COMPONENTS * width * height],
+            transparency: DEFAULT_TRANSPARENCY,
             draw_palette: ORIGINAL_PALETTE,
+            screen_palette: ORIGINAL_PALETTE,
             camera: (0, 0),
+            clip: Self::screen(width, height),
+            fill_pattern: 0,
+            fill_transparent: false,
+            cursor: (0, 0),
+            pen: DEFAULT_PEN,
+            line_end: None,
+            font: None,
+        }
+    }
+
+    /// The whole screen, as a clipping rectangle.
+    fn screen(width: usize, height: usize) -> ClipRect {
+        ClipRect {
+            x: 0,
+            y: 0,
+            w: width as i32,
+            h: height as i32,
         }
     }
 
+    /// The screen's width and height, in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Changes the screen's size, which clears it and resets the clipping rectangle.
+    pub(crate) fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width * height];
+        self.buffer = vec![0; NUM_COMPONENTS * width * height];
+        self.clip = Self::screen(width, height);
+        self.refresh_buffer();
+    }
+
     pub(crate) fn append_camera(&mut self, x: i32, y: i32) {
         self.camera(self.camera.0 + x, self.camera.1 + y);
     }
 
     fn set_pixel_with_transparency(&mut self, index: usize, color: Color) {
-        if let Some(transparent_color) = self.transparent_color {
-            if color == transparent_color {
-                return;
-            }
+        if self.transparency & (1 << color) != 0 {
+            return;
         }
 
         self.set_pixel(index, color);
@@ -53,53 +159,137 @@ impl DrawData {
     fn set_pixel(&mut self, index: usize, color: Color) {
         // https://pico-8.fandom.com/wiki/Pal
         let color = self.draw_palette[color as usize];
-        let c = get_color(color);
+        self.pixels[index] = color;
+        self.write_rgb(index, color);
+    }
 
-        #[allow(clippy::identity_op)]
-        {
-            let r = ((c >> 16) & 0x0000FF) as u8;
-            let g = ((c >> 8) & 0x0000FF) as u8;
-            let b = ((c >> 0) & 0x0000FF) as u8;
-            self.buffer[NUM_COMPONENTS * index + 0] = r;
-            self.buffer[NUM_COMPONENTS * index + 1] = g;
-            self.buffer[NUM_COMPONENTS * index + 2] = b;
-        }
+    /// Converts the pixel at `index` to RGB, through the screen palette.
+    fn write_rgb(&mut self, index: usize, color: Color) {
+        write_rgb(&mut self.buffer, &self.screen_palette, index, color);
     }
 
-    pub fn buffer(&self) -> &Buffer {
+    /// What's drawn on screen as the RGB bytes of each pixel, row by row.
+    pub fn buffer(&self) -> &[u8] {
         &self.buffer
     }
 
-    fn print_char(&mut self, index: usize, x: i32, y: i32, color: Color) {
-        let char_data = font::FONT.get(index).unwrap_or(&font::MISSING_CHAR);
+    /// Shows what `other` shows, to draw on top of it without touching `other`.
+    pub(crate) fn copy_screen(&mut self, other: &DrawData) {
+        if (self.width, self.height) != (other.width, other.height) {
+            *self = Self::with_size(other.width, other.height);
+        }
+        self.pixels.clone_from(&other.pixels);
+        self.buffer.clone_from(&other.buffer);
+    }
+
+    /// What's drawn on screen as palette indices, one per pixel, row by row.
+    ///
+    /// These are what pico8 keeps in screen memory: the draw palette is already applied,
+    /// but not the screen palette (see [`crate::Pico8::screen_pal`]), which [`DrawData::buffer`] goes through.
+    pub fn index_buffer(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    /// A hash of [`DrawData::index_buffer`], for checking that a frame looks the same in tests.
+    ///
+    /// It hashes palette indices rather than RGB colors: swapping draw palette colors changes it,
+    /// but screen palette changes don't. The hash (64 bit FNV-1a) is the same on every platform.
+    pub fn frame_hash(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        self.pixels.iter().fold(OFFSET_BASIS, |hash, &color| {
+            (hash ^ u64::from(color)).wrapping_mul(PRIME)
+        })
+    }
+
+    fn print_char(&mut self, char: char, x: i32, y: i32, color: Color) {
+        if let Some(&glyph) = self.font.as_ref().and_then(|font| font.glyph(char)) {
+            for (y_offset, row) in (0..).zip(glyph) {
+                for x_offset in 0..8 {
+                    if row & (1 << x_offset) != 0 {
+                        self.plot(x + x_offset, y + y_offset, color);
+                    }
+                }
+            }
+
+            return;
+        }
+
+        if let Some(&glyph) = font::wide_glyph(char) {
+            for (y_offset, row) in (0..).zip(glyph) {
+                for x_offset in 0..7 {
+                    if row & (1 << (6 - x_offset)) != 0 {
+                        self.plot(x + x_offset, y + y_offset, color);
+                    }
+                }
+            }
+
+            return;
+        }
+
+        // Characters outside the font are drawn as `MISSING_CHAR`.
+        let char_data = (char as usize)
+            .checked_sub(font::FIRST_CHAR as usize)
+            .and_then(|index| font::FONT.get(index))
+            .unwrap_or(&font::MISSING_CHAR);
 
         for x_offset in 0..4_i32 {
             for y_offset in 0..6 {
                 let print = char_data[(x_offset + y_offset * 4) as usize] != 0;
 
                 if print {
-                    self.pset(x + x_offset, y + y_offset, color);
+                    self.plot(x + x_offset, y + y_offset, color);
                 }
             }
         }
     }
 
+    /// Draws a single pixel in world coordinates, ignoring the fill pattern.
+    fn plot(&mut self, x: i32, y: i32, color: Color) {
+        let (x, y) = self.apply_camera(x, y);
+        if let Some(index) = self.index(x, y) {
+            self.set_pixel(index, color);
+        }
+    }
+
+    /// Which color the fill pattern draws at screen position (x, y), if any.
+    /// The low 4 bits of `color` are used where the pattern is unset, the high 4 where it's set.
+    fn pattern_color(&self, x: i32, y: i32, color: Color) -> Option<Color> {
+        let bit = 15 - ((x & 3) + 4 * (y & 3));
+
+        if self.fill_pattern & (1 << bit) == 0 {
+            Some(color & 0x0F)
+        } else if self.fill_transparent {
+            None
+        } else {
+            Some(color >> 4)
+        }
+    }
+
     fn apply_camera(&self, x: i32, y: i32) -> (i32, i32) {
         (x - self.camera.0, y - self.camera.1)
     }
 
+    /// The clipping rectangle where it is before the camera moves it,
+    /// i.e. what can be drawn to in the coordinates that are drawn with.
+    fn visible_rect(&self) -> ClipRect {
+        let (x, y) = self.unapply_camera(self.clip.x, self.clip.y);
+
+        ClipRect { x, y, ..self.clip }
+    }
+
     /// Inverse of `apply_camera`
     fn unapply_camera(&self, x: i32, y: i32) -> (i32, i32) {
         (x + self.camera.0, y + self.camera.1)
     }
 
-    /// Returns the linear index of the pixel with (x, y) coordinates in the screen
+    /// Returns the linear index of the pixel with (x, y) coordinates in the screen,
+    /// unless it's outside the screen or the clipping rectangle.
     fn index(&self, x: i32, y: i32) -> Option<usize> {
-        let x_in_bounds = 0 <= x && x < WIDTH as i32;
-        let y_in_bounds = 0 <= y && y < WIDTH as i32;
-
-        if x_in_bounds && y_in_bounds {
-            Some(x as usize + y as usize * WIDTH)
+        // The clipping rectangle never extends past the screen.
+        if self.clip.contains(x, y) {
+            Some(x as usize + y as usize * self.width)
         } else {
             None
         }
@@ -136,6 +326,38 @@ impl DrawData {
     }
 }
 
+/// Rows of a filled ellipse: `(y, left, right)` for each row of the bounding rectangle
+/// that is in `rows`, so huge ellipses only cost what's on screen.
+///
+/// A pixel is in the ellipse if its center is, which keeps the result symmetric
+/// for both odd and even sizes. Rows always have at least their middle pixel(s),
+/// so thin rectangles draw lines.
+fn oval_spans(x0: i32, y0: i32, x1: i32, y1: i32, rows: Range<i32>) -> Vec<(i32, i32, i32)> {
+    let MinMax { min: x0, max: x1 } = min_max(x0, x1);
+    let MinMax { min: y0, max: y1 } = min_max(y0, y1);
+
+    // Measured in pixel edges: the rectangle covers the whole of its border pixels.
+    let (center_x, center_y) = ((x0 + x1 + 1) as f32 / 2.0, (y0 + y1 + 1) as f32 / 2.0);
+    let (radius_x, radius_y) = ((x1 - x0 + 1) as f32 / 2.0, (y1 - y0 + 1) as f32 / 2.0);
+
+    (y0.max(rows.start)..=y1.min(rows.end - 1))
+        .map(|y| {
+            let dy = (y as f32 + 0.5 - center_y) / radius_y;
+            let half_width = radius_x * (1.0 - dy * dy).max(0.0).sqrt();
+
+            let left = (center_x - half_width - 0.5).ceil() as i32;
+            let right = (center_x + half_width - 0.5).floor() as i32;
+
+            if left <= right {
+                (y, left, right)
+            } else {
+                let middle = center_x - 0.5;
+                (y, middle.floor() as i32, middle.ceil() as i32)
+            }
+        })
+        .collect()
+}
+
 // Functions which more directly implement pico8 functionality
 impl DrawData {
     pub(crate) fn pal(&mut self, c0: Color, c1: Color) {
@@ -148,14 +370,201 @@ impl DrawData {
         std::mem::replace(&mut self.camera, (x, y))
     }
 
+    /// Restricts drawing to the given screen rectangle (not affected by the camera).
+    /// If `intersect` is set, the new rectangle is clipped to the current one.
+    /// Returns the previous rectangle, so it can be restored afterwards.
+    pub(crate) fn clip(&mut self, rect: ClipRect, intersect: bool) -> ClipRect {
+        let bounds = if intersect {
+            self.clip
+        } else {
+            Self::screen(self.width, self.height)
+        };
+
+        std::mem::replace(&mut self.clip, rect.intersect(bounds))
+    }
+
+    /// Resets the clipping rectangle to the full screen. Returns the previous rectangle.
+    pub(crate) fn reset_clip(&mut self) -> ClipRect {
+        self.clip(Self::screen(self.width, self.height), false)
+    }
+
+    /// Resets the camera to (0, 0). Returns the previous value set.
+    pub(crate) fn reset_camera(&mut self) -> (i32, i32) {
+        self.camera(0, 0)
+    }
+
+    /// Runs `draw` with the default palettes, camera, clipping, fill pattern, cursor and font,
+    /// putting the game's back afterwards. For drawing things on top of the game, like menus.
+    pub(crate) fn with_default_state(&mut self, draw: impl FnOnce(&mut Self)) {
+        let saved = (
+            self.transparency,
+            self.draw_palette,
+            self.screen_palette,
+            self.camera,
+            self.clip,
+            self.fill_pattern,
+            self.fill_transparent,
+            self.cursor,
+            self.font.take(),
+        );
+        self.transparency = DEFAULT_TRANSPARENCY;
+        self.draw_palette = ORIGINAL_PALETTE;
+        self.screen_palette = ORIGINAL_PALETTE;
+        self.camera = (0, 0);
+        self.clip = Self::screen(self.width, self.height);
+        self.fill_pattern = 0;
+        self.fill_transparent = false;
+
+        draw(self);
+
+        (
+            self.transparency,
+            self.draw_palette,
+            self.screen_palette,
+            self.camera,
+            self.clip,
+            self.fill_pattern,
+            self.fill_transparent,
+            self.cursor,
+            self.font,
+        ) = saved;
+    }
+
+    /// A byte of the draw state's memory (`0x5f00..0x5f40`), `offset` bytes in,
+    /// or `None` if that byte isn't part of the state kept here.
+    /// <https://pico-8.fandom.com/wiki/Memory#Draw_state>
+    pub(crate) fn peek_draw_state(&self, offset: usize) -> Option<u8> {
+        let clip = self.clip;
+        let [camera_x, camera_y] = [self.camera.0, self.camera.1].map(|n| (n as i16).to_le_bytes());
+
+        Some(match offset {
+            // The draw palette, with bit 4 set for transparent colors.
+            0x00..=0x0f => {
+                self.draw_palette[offset] | (((self.transparency >> offset) & 1) as u8) << 4
+            }
+            0x10..=0x1f => self.screen_palette[offset - 0x10],
+            0x20 => clip.x as u8,
+            0x21 => clip.y as u8,
+            0x22 => clip.right() as u8,
+            0x23 => clip.bottom() as u8,
+            0x26 => self.cursor.0 as u8,
+            0x27 => self.cursor.1 as u8,
+            0x28 | 0x29 => camera_x[offset - 0x28],
+            0x2a | 0x2b => camera_y[offset - 0x2a],
+            0x31 | 0x32 => self.fill_pattern.to_le_bytes()[offset - 0x31],
+            0x33 => self.fill_transparent as u8,
+            _ => return None,
+        })
+    }
+
+    /// Writes a byte of the draw state's memory, see [`DrawData::peek_draw_state`].
+    /// Returns whether that byte is part of the state kept here.
+    pub(crate) fn poke_draw_state(&mut self, offset: usize, value: u8) -> bool {
+        let replace = |bytes: [u8; 2], index: usize| {
+            let mut bytes = bytes;
+            bytes[index] = value;
+            i16::from_le_bytes(bytes) as i32
+        };
+
+        match offset {
+            0x00..=0x0f => {
+                self.draw_palette[offset] = value & 0x0f;
+                self.set_transparent(offset as Color, value & 0x10 != 0);
+            }
+            0x10..=0x1f => self.screen_pal((offset - 0x10) as Color, value),
+            0x20..=0x23 => {
+                let mut corners = [0x20, 0x21, 0x22, 0x23]
+                    .map(|offset| self.peek_draw_state(offset).map_or(0, i32::from));
+                corners[offset - 0x20] = value.into();
+                let [x0, y0, x1, y1] = corners;
+
+                self.clip(
+                    ClipRect {
+                        x: x0,
+                        y: y0,
+                        w: x1 - x0,
+                        h: y1 - y0,
+                    },
+                    false,
+                );
+            }
+            0x26 => self.cursor.0 = value.into(),
+            0x27 => self.cursor.1 = value.into(),
+            0x28 | 0x29 => {
+                self.camera.0 = replace((self.camera.0 as i16).to_le_bytes(), offset - 0x28);
+            }
+            0x2a | 0x2b => {
+                self.camera.1 = replace((self.camera.1 as i16).to_le_bytes(), offset - 0x2a);
+            }
+            0x31 | 0x32 => {
+                let mut bytes = self.fill_pattern.to_le_bytes();
+                bytes[offset - 0x31] = value;
+                self.fill_pattern = u16::from_le_bytes(bytes);
+            }
+            0x33 => self.fill_transparent = value & 1 != 0,
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// A byte of screen memory (`0x6000..0x8000`), `offset` bytes in: two pixels of
+    /// the top left 128x128 pixels, with the left one in the low 4 bits.
+    pub(crate) fn peek_screen(&self, offset: usize) -> u8 {
+        let (x, y) = ((2 * offset % WIDTH) as i32, (2 * offset / WIDTH) as i32);
+
+        self.pget(x, y) | self.pget(x + 1, y) << 4
+    }
+
+    /// Writes a byte of screen memory, see [`DrawData::peek_screen`].
+    /// The draw palette doesn't apply, but the screen palette does.
+    pub(crate) fn poke_screen(&mut self, offset: usize, value: u8) {
+        let (x, y) = (2 * offset % WIDTH, 2 * offset / WIDTH);
+
+        for (x, color) in [(x, value & 0x0f), (x + 1, value >> 4)] {
+            if x < self.width && y < self.height {
+                let index = x + y * self.width;
+                self.pixels[index] = color;
+                self.write_rgb(index, color);
+            }
+        }
+    }
+
+    /// Sets the 4x4 fill pattern used by shapes, where bit 15 is the top left pixel.
+    /// Set bits are drawn with the secondary color (the high 4 bits of the color),
+    /// or not at all if `transparent` is set.
+    /// <https://pico-8.fandom.com/wiki/Fillp>
+    pub(crate) fn fillp(&mut self, pattern: u16, transparent: bool) {
+        self.fill_pattern = pattern;
+        self.fill_transparent = transparent;
+    }
+
     pub(crate) fn pset(&mut self, x: i32, y: i32, color: Color) {
         let (x, y) = self.apply_camera(x, y);
         if let Some(index) = self.index(x, y) {
-            self.set_pixel(index, color);
+            if let Some(color) = self.pattern_color(x, y, color) {
+                self.set_pixel(index, color);
+            }
+        }
+    }
+
+    /// The color at screen position (x, y), as stored in screen memory (after the draw palette).
+    /// 0 outside the screen.
+    pub(crate) fn pget(&self, x: i32, y: i32) -> Color {
+        if Self::screen(self.width, self.height).contains(x, y) {
+            self.pixels[x as usize + y as usize * self.width]
+        } else {
+            0
         }
     }
 
     pub(crate) fn rectfill(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        // Without a fill pattern every pixel gets the same color, so rows are filled at once.
+        if self.fill_pattern == 0 {
+            self.fill_rect(x0, y0, x1, y1, color & 0x0f);
+            return;
+        }
+
         let MinMax { min: y0, max: y1 } = min_max(y0, y1);
 
         for y in y0..=y1 {
@@ -176,14 +585,86 @@ impl DrawData {
         }
     }
 
+    /// Sets the pen color, returning the previous one.
+    /// Pico8's primitives also set it to the color they're drawn with.
+    pub(crate) fn color(&mut self, color: Color) -> Color {
+        std::mem::replace(&mut self.pen, color)
+    }
+
+    /// The color the last primitive was drawn with, or set with [`DrawData::color`].
+    pub(crate) fn pen(&self) -> Color {
+        self.pen
+    }
+
+    /// Where the next [`DrawData::line_to`] starts, or `None` for it to only set the end.
+    pub(crate) fn set_line_end(&mut self, end: Option<(i32, i32)>) {
+        self.line_end = end;
+    }
+
+    /// Continues the last line to (x1, y1), which becomes the new end.
+    pub(crate) fn line_to(&mut self, x1: i32, y1: i32, color: Color) {
+        if let Some((x0, y0)) = self.line_end {
+            self.line(x0, y0, x1, y1, color);
+        }
+        self.line_end = Some((x1, y1));
+    }
+
+    /// Makes `c0` show up as `c1` on screen, including what's already been drawn.
+    /// <https://pico-8.fandom.com/wiki/Pal>
+    /// `c1` can be `128..=143` for the extended colors, other bits are ignored like in pico8.
+    pub(crate) fn screen_pal(&mut self, c0: Color, c1: Color) {
+        let (c0, c1) = (c0 & 0x0f, c1 & 0x8f);
+
+        if self.screen_palette[c0 as usize] != c1 {
+            self.screen_palette[c0 as usize] = c1;
+            self.refresh_color(c0);
+        }
+    }
+
+    /// Converts the pixels of `color` to RGB again, after its screen palette entry changed.
+    fn refresh_color(&mut self, color: Color) {
+        for index in 0..self.pixels.len() {
+            if self.pixels[index] == color {
+                self.write_rgb(index, color);
+            }
+        }
+    }
+
+    /// Converts the whole screen to RGB again, for changes that can affect every pixel.
+    fn refresh_buffer(&mut self) {
+        for index in 0..self.pixels.len() {
+            self.write_rgb(index, self.pixels[index]);
+        }
+    }
+
+    /// Resets both the draw and the screen palette.
     pub(crate) fn reset_pal(&mut self) {
         self.draw_palette = ORIGINAL_PALETTE;
+        if self.screen_palette != ORIGINAL_PALETTE {
+            self.screen_palette = ORIGINAL_PALETTE;
+            self.refresh_buffer();
+        }
         // pal() resets transparency to default
-        self.palt(Some(0));
+        self.reset_palt();
     }
 
+    /// Makes `transparent_color` the only transparent color, or none at all.
     pub(crate) fn palt(&mut self, transparent_color: Option<Color>) {
-        self.transparent_color = transparent_color
+        self.transparency = transparent_color.map_or(0, |color| 1 << color);
+    }
+
+    /// Sets whether `color` is transparent, leaving the other colors as they are.
+    pub(crate) fn set_transparent(&mut self, color: Color, transparent: bool) {
+        if transparent {
+            self.transparency |= 1 << color;
+        } else {
+            self.transparency &= !(1 << color);
+        }
+    }
+
+    /// Only color 0 is transparent.
+    pub(crate) fn reset_palt(&mut self) {
+        self.transparency = DEFAULT_TRANSPARENCY;
     }
 
     // Taken from Pemsa, a C++ implementation of pico8.
@@ -227,16 +708,189 @@ impl DrawData {
         self.quarter_bresenham(cx, cy, radius, color, plot);
     }
 
-    pub(crate) fn print(&mut self, str: &str, x: i32, y: i32, color: Color) {
-        for (pos, char) in str.chars().enumerate() {
-            let index = char as u8 - font::FIRST_CHAR as u8;
+    /// The outline of the ellipse that fits in the (x0, y0), (x1, y1) rectangle.
+    pub(crate) fn oval(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        let visible = self.visible_rect();
+        // With the rows around the visible ones, to tell which visible pixels are on the edge.
+        let spans = oval_spans(
+            x0,
+            y0,
+            x1,
+            y1,
+            visible.y.saturating_sub(1)..visible.bottom().saturating_add(1),
+        );
+        let inside = |index: Option<usize>, x: i32| {
+            index
+                .and_then(|index| spans.get(index))
+                .map_or(false, |&(_, left, right)| (left..=right).contains(&x))
+        };
+
+        // Every pixel of the filled oval that touches its outside.
+        for (index, &(y, left, right)) in spans.iter().enumerate() {
+            if !(visible.y..visible.bottom()).contains(&y) {
+                continue;
+            }
+
+            for x in left.max(visible.x)..=right.min(visible.right() - 1) {
+                let edge = x == left
+                    || x == right
+                    || !inside(index.checked_sub(1), x)
+                    || !inside(Some(index + 1), x);
+
+                if edge {
+                    self.pset(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// The ellipse that fits in the (x0, y0), (x1, y1) rectangle.
+    pub(crate) fn ovalfill(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        let visible = self.visible_rect();
+
+        for (y, left, right) in oval_spans(x0, y0, x1, y1, visible.y..visible.bottom()) {
+            let (left, right) = (left.max(visible.x), right.min(visible.right() - 1));
+            if left <= right {
+                self.line(left, y, right, y, color);
+            }
+        }
+    }
+
+    /// Prints `str` with its top left corner at (x, y), each `\n` starting a new line below.
+    /// Pico8's control codes are supported, see [`text::parse`].
+    /// The cursor moves to the line after the text.
+    ///
+    /// Returns the x coordinate right after the end of the text.
+    pub(crate) fn print(&mut self, str: &str, x: i32, y: i32, color: Color) -> i32 {
+        let (end_x, line_y) = self.print_text(str, x, y, color, false);
+
+        self.cursor = (x, line_y + self.line_height());
+        end_x
+    }
+
+    /// Prints `str` at the cursor, like [`DrawData::print`].
+    /// Lines that would go past the bottom of the screen scroll it up first.
+    pub(crate) fn print_at_cursor(&mut self, str: &str, color: Color) -> i32 {
+        let (x, y) = self.cursor;
+        let (end_x, line_y) = self.print_text(str, x, y, color, true);
+
+        self.cursor = (x, line_y + self.line_height());
+        end_x
+    }
+
+    /// Sets where [`DrawData::print_at_cursor`] prints next. Returns the previous position.
+    pub(crate) fn cursor(&mut self, x: i32, y: i32) -> (i32, i32) {
+        std::mem::replace(&mut self.cursor, (x, y))
+    }
+
+    /// Prints with `font` instead of the built-in font, or goes back to the built-in one with `None`.
+    pub(crate) fn set_font(&mut self, font: Option<CustomFont>) {
+        self.font = font.map(Box::new);
+    }
+
+    fn char_width(&self) -> i32 {
+        self.font
+            .as_ref()
+            .map_or(font::CHAR_WIDTH, |font| font.char_width)
+    }
+
+    /// How far printing `char` moves the cursor.
+    fn advance(&self, char: char) -> i32 {
+        let custom = self
+            .font
+            .as_ref()
+            .map_or(false, |font| font.glyph(char).is_some());
 
-            self.print_char(index as usize, x + (pos * 4) as i32, y, color);
+        if !custom && font::wide_glyph(char).is_some() {
+            font::WIDE_CHAR_WIDTH
+        } else {
+            self.char_width()
+        }
+    }
+
+    fn line_height(&self) -> i32 {
+        self.font
+            .as_ref()
+            .map_or(font::LINE_HEIGHT, |font| font.line_height)
+    }
+
+    /// Returns where the text ended: the x coordinate after it, and the y of its last line.
+    fn print_text(&mut self, str: &str, x: i32, y: i32, color: Color, scroll: bool) -> (i32, i32) {
+        let mut home = (x, y);
+        let (mut x, mut y) = home;
+        let mut color = color;
+        let mut background = None;
+        // Makes room for the line starting at `y`, returning where it ends up.
+        let fit_line = |this: &mut Self, y: i32| {
+            let overflow = this.apply_camera(0, y + this.line_height()).1 - this.height as i32;
+            if scroll && overflow > 0 {
+                this.scroll_up(overflow as usize);
+                y - overflow
+            } else {
+                y
+            }
+        };
+
+        y = fit_line(self, y);
+        for op in text::parse(str) {
+            match op {
+                TextOp::Char(char) => {
+                    let width = self.advance(char);
+
+                    if let Some(background) = background {
+                        for offset_y in 0..self.line_height() {
+                            for offset_x in 0..width {
+                                self.plot(x + offset_x, y + offset_y, background);
+                            }
+                        }
+                    }
+
+                    self.print_char(char, x, y, color);
+                    x += width;
+                }
+                TextOp::Newline => {
+                    x = home.0;
+                    y = fit_line(self, y + self.line_height());
+                }
+                TextOp::CarriageReturn => x = home.0,
+                TextOp::Tab => {
+                    // Fonts without a width have nowhere to tab to.
+                    let tab_width = 4 * self.char_width();
+                    if tab_width > 0 {
+                        x = home.0 + ((x - home.0).div_euclid(tab_width) + 1) * tab_width;
+                    }
+                }
+                TextOp::Backspace => x -= self.char_width(),
+                TextOp::Color(new_color) => color = new_color,
+                TextOp::Background(new_background) => background = Some(new_background),
+                TextOp::Move(dx, dy) => {
+                    x += dx;
+                    y += dy;
+                }
+                TextOp::Home => (x, y) = home,
+                TextOp::SetHome => home = (x, y),
+                TextOp::Clear(color) => {
+                    self.cls_color(color);
+                    home = (0, 0);
+                    (x, y) = home;
+                }
+            }
         }
+
+        (x, y)
+    }
+
+    /// Moves the whole screen up by `rows`, clearing the rows it uncovers.
+    fn scroll_up(&mut self, rows: usize) {
+        let rows = rows.min(self.height);
+
+        self.pixels.copy_within(rows * self.width.., 0);
+        self.pixels[(self.height - rows) * self.width..].fill(0);
+        self.refresh_buffer();
     }
 
+    /// Draws a single sprite: `Pico8::spr_` handles `w` and `h`, as it needs the whole sprite sheet.
     #[allow(clippy::too_many_arguments)]
-    // TODO: Implement w and h params functionality
     pub fn spr_(
         &mut self,
         sprite: &Sprite,
@@ -247,6 +901,55 @@ impl DrawData {
         flip_x: bool,
         flip_y: bool,
     ) {
+        if !flip_x && !flip_y && self.blit_opaque(sprite, x, y) {
+            return;
+        }
+
+        self.blit(sprite, x, y, flip_x, flip_y);
+    }
+
+    /// Draws `sprite` a row at a time, if it has no transparent pixels and the draw palette
+    /// isn't changed: then each row is copied as is, instead of checking every pixel.
+    /// Returns whether it did.
+    fn blit_opaque(&mut self, sprite: &Sprite, x: i32, y: i32) -> bool {
+        let pixels = &sprite.sprite;
+        let opaque = pixels
+            .iter()
+            .all(|&color| self.transparency & (1 << color) == 0);
+
+        if !opaque || self.draw_palette != ORIGINAL_PALETTE {
+            return false;
+        }
+
+        // The part of the sprite that's inside the clipping rectangle, in screen coordinates.
+        let (x, y) = self.apply_camera(x, y);
+        let drawn = ClipRect {
+            x,
+            y,
+            w: Sprite::WIDTH as i32,
+            h: Sprite::HEIGHT as i32,
+        }
+        .intersect(self.clip);
+        if drawn.w == 0 {
+            return true;
+        }
+
+        for screen_y in drawn.y..drawn.bottom() {
+            let row = (screen_y - y) as usize * Sprite::WIDTH + (drawn.x - x) as usize;
+            let source = &pixels[row..row + drawn.w as usize];
+            let start = drawn.x as usize + screen_y as usize * self.width;
+
+            self.pixels[start..start + source.len()].copy_from_slice(source);
+            for (index, &color) in (start..).zip(source) {
+                self.write_rgb(index, color);
+            }
+        }
+
+        true
+    }
+
+    /// Draws `sprite` pixel by pixel, skipping transparent ones.
+    fn blit(&mut self, sprite: &Sprite, x: i32, y: i32, flip_x: bool, flip_y: bool) {
         let buffer = &sprite.sprite;
 
         for i in 0..8 {
@@ -266,14 +969,137 @@ impl DrawData {
         self.spr_(sprite, x, y, 1.0, 1.0, false, false)
     }
 
+    /// Stretches the `sw`x`sh` rectangle of the sprite sheet at (`sx`, `sy`)
+    /// to the `dw`x`dh` rectangle at (`dx`, `dy`), sampling the nearest pixel.
+    /// A negative destination size flips the sprite, pixels outside the sheet are 0.
+    ///
+    /// <https://pico-8.fandom.com/wiki/Sspr>
+    pub(crate) fn sspr(
+        &mut self,
+        sprite_sheet: &SpriteSheet,
+        (sx, sy, sw, sh): (i32, i32, i32, i32),
+        (dx, dy, dw, dh): (i32, i32, i32, i32),
+        flip_x: bool,
+        flip_y: bool,
+    ) {
+        let flip_x = flip_x != (dw < 0);
+        let flip_y = flip_y != (dh < 0);
+        let (dw, dh) = (dw.abs(), dh.abs());
+
+        let sample = |x: i32, y: i32| -> Color {
+            let in_bounds =
+                (0..SPRITE_SHEET_WIDTH).contains(&x) && (0..SPRITE_SHEET_WIDTH).contains(&y);
+
+            if in_bounds {
+                sprite_sheet.get(x as usize, y as usize)
+            } else {
+                0
+            }
+        };
+
+        for i in 0..dw {
+            for j in 0..dh {
+                let src_i = if flip_x { dw - 1 - i } else { i };
+                let src_j = if flip_y { dh - 1 - j } else { j };
+                let color = sample(sx + src_i * sw / dw, sy + src_j * sh / dh);
+
+                let (x, y) = self.apply_camera(dx + i, dy + j);
+                if let Some(index) = self.index(x, y) {
+                    self.set_pixel_with_transparency(index, color);
+                }
+            }
+        }
+    }
+
+    /// Draws a line textured with the map: each pixel samples the map at (`mx`, `my`),
+    /// in cells, which then moves by (`mdx`, `mdy`) for the next pixel.
+    /// Transparent colors and transparent fill pattern bits are skipped.
+    ///
+    /// <https://pico-8.fandom.com/wiki/Tline>
+    pub(crate) fn tline(
+        &mut self,
+        (x0, y0, x1, y1): (i32, i32, i32, i32),
+        (mx, my): (f32, f32),
+        (mdx, mdy): (f32, f32),
+        map: &Map,
+        sprite_sheet: &SpriteSheet,
+    ) {
+        for (step, (x, y)) in draw::line(x0, y0, x1, y1).enumerate() {
+            let mx = mx + mdx * step as f32;
+            let my = my + mdy * step as f32;
+            let sprite = map.mget(mx.floor() as i32, my.floor() as i32);
+
+            // Tiny negative fractions round up to a whole cell in f32, hence the `min`.
+            let pixel_x =
+                (((mx - mx.floor()) * Sprite::WIDTH as f32) as usize).min(Sprite::WIDTH - 1);
+            let pixel_y =
+                (((my - my.floor()) * Sprite::HEIGHT as f32) as usize).min(Sprite::HEIGHT - 1);
+            let color = sprite_sheet.get_sprite(sprite as usize).sprite[pixel_x + pixel_y * 8];
+
+            let (x, y) = self.apply_camera(x, y);
+            if let Some(index) = self.index(x, y) {
+                if self.pattern_color(x, y, color).is_some() {
+                    self.set_pixel_with_transparency(index, color);
+                }
+            }
+        }
+    }
+
+    /// Clears the whole screen, which also resets the clipping rectangle and the cursor.
     pub(crate) fn cls_color(&mut self, color: Color) {
+        self.reset_clip();
+        self.cursor = (0, 0);
         let (start_x, start_y) = self.unapply_camera(0, 0);
-        let (end_x, end_y) = self.unapply_camera(127, 127);
+        let (end_x, end_y) = self.unapply_camera(self.width as i32 - 1, self.height as i32 - 1);
+
+        // Not using `rectfill`, which would apply the fill pattern.
+        self.fill_rect(start_x, start_y, end_x, end_y, color);
+    }
+
+    /// Fills the (x0, y0), (x1, y1) rectangle with `color`, through the draw palette,
+    /// a row at a time.
+    fn fill_rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        let MinMax { min: x0, max: x1 } = min_max(x0, x1);
+        let MinMax { min: y0, max: y1 } = min_max(y0, y1);
+        let (x, y) = self.apply_camera(x0, y0);
+        let rect = ClipRect {
+            x,
+            y,
+            w: x1.saturating_sub(x0).saturating_add(1),
+            h: y1.saturating_sub(y0).saturating_add(1),
+        }
+        .intersect(self.clip);
+        if rect.w == 0 || rect.h == 0 {
+            return;
+        }
+
+        let color = self.draw_palette[color as usize];
+        let rgb = rgb(&self.screen_palette, color);
+        let row_start = |y: i32| NUM_COMPONENTS * (rect.x as usize + y as usize * self.width);
+        let row_len = NUM_COMPONENTS * rect.w as usize;
+
+        // Three bytes at a time don't vectorize, so only the first RGB row is written that way
+        // and then copied to the others (`fill` for the indices becomes a memset).
+        let first_row = row_start(rect.y);
+        for pixel in self.buffer[first_row..first_row + row_len].chunks_exact_mut(NUM_COMPONENTS) {
+            pixel.copy_from_slice(&rgb);
+        }
 
-        self.rectfill(start_x, start_y, end_x, end_y, color);
+        for y in rect.y..rect.bottom() {
+            let start = rect.x as usize + y as usize * self.width;
+            self.pixels[start..start + rect.w as usize].fill(color);
+
+            if y > rect.y {
+                self.buffer
+                    .copy_within(first_row..first_row + row_len, row_start(y));
+            }
+        }
     }
 
     /// <https://pico-8.fandom.com/wiki/Map>
+    ///
+    /// Draws `cell_w`x`cell_h` cells of the map. Empty cells (sprite 0) are skipped,
+    /// and so are sprites that don't have all the flags set in `layer`.
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn map(
         &mut self,
@@ -283,16 +1109,53 @@ impl DrawData {
         screen_y: i32,
         cell_w: i32,
         cell_h: i32,
-        // TODO: Use layer
         layer: u8,
         map: &Map,
         flags: &Flags,
         sprite_sheet: &SpriteSheet,
     ) {
-        // TODO: Handle like pico8
-        for (i_x, map_x) in (cell_x..=(cell_x + cell_w)).enumerate() {
-            for (i_y, map_y) in (cell_y..=(cell_y + cell_h)).enumerate() {
+        #[cfg(all(feature = "parallel-map", not(target_arch = "wasm32")))]
+        if cell_w.saturating_mul(cell_h) >= PARALLEL_MAP_CELLS {
+            self.map_parallel(
+                (cell_x, cell_y),
+                (screen_x, screen_y),
+                (cell_w, cell_h),
+                layer,
+                map,
+                flags,
+                sprite_sheet,
+            );
+            return;
+        }
+
+        self.map_serial(
+            (cell_x, cell_y),
+            (screen_x, screen_y),
+            (cell_w, cell_h),
+            layer,
+            map,
+            flags,
+            sprite_sheet,
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn map_serial(
+        &mut self,
+        (cell_x, cell_y): (i32, i32),
+        (screen_x, screen_y): (i32, i32),
+        (cell_w, cell_h): (i32, i32),
+        layer: u8,
+        map: &Map,
+        flags: &Flags,
+        sprite_sheet: &SpriteSheet,
+    ) {
+        for (i_x, map_x) in (cell_x..(cell_x + cell_w)).enumerate() {
+            for (i_y, map_y) in (cell_y..(cell_y + cell_h)).enumerate() {
                 let spr = map.mget(map_x, map_y);
+                if spr == 0 {
+                    continue;
+                }
 
                 let flags = flags.get(spr.into()).unwrap();
 
@@ -306,20 +1169,127 @@ impl DrawData {
             }
         }
     }
-}
-
-impl Default for DrawData {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-// Pico8 api
 
+    /// Like [`DrawData::map_serial`], with the screen split in bands of rows
+    /// that are drawn on the threads of [`thread_pool`].
+    ///
+    /// Tiles never overlap, so the order they're drawn in doesn't matter.
+    #[cfg(all(feature = "parallel-map", not(target_arch = "wasm32")))]
+    #[allow(clippy::too_many_arguments)]
+    fn map_parallel(
+        &mut self,
+        (cell_x, cell_y): (i32, i32),
+        (screen_x, screen_y): (i32, i32),
+        (cell_w, cell_h): (i32, i32),
+        layer: u8,
+        map: &Map,
+        flags: &Flags,
+        sprite_sheet: &SpriteSheet,
+    ) {
+        let threads = std::thread::available_parallelism().map_or(1, usize::from);
+        let band_height = (self.height + threads - 1) / threads;
+        let (screen_x, screen_y) = self.apply_camera(screen_x, screen_y);
+        let (width, clip, transparency) = (self.width, self.clip, self.transparency);
+        let (draw_palette, screen_palette) = (self.draw_palette, self.screen_palette);
+
+        let draw_band = |band_y: i32, pixels: &mut [Color], buffer: &mut [u8]| {
+            let band_end = band_y + (pixels.len() / width) as i32;
+            // What can be drawn to: the band's rows, inside the clipping rectangle.
+            let (top, bottom) = (band_y.max(clip.y), band_end.min(clip.bottom()));
+            let (left, right) = (clip.x, clip.right());
+
+            for i_y in 0..cell_h {
+                let y = screen_y + 8 * i_y;
+                let rows = y.max(top)..(y + 8).min(bottom);
+                if rows.is_empty() {
+                    continue;
+                }
+
+                for i_x in 0..cell_w {
+                    let x = screen_x + 8 * i_x;
+                    let columns = x.max(left)..(x + 8).min(right);
+                    if columns.is_empty() {
+                        continue;
+                    }
+
+                    let spr = map.mget(cell_x + i_x, cell_y + i_y);
+                    if spr == 0 || flags.get(spr.into()).unwrap() & layer != layer {
+                        continue;
+                    }
+                    let sprite = &sprite_sheet.get_sprite(spr as usize).sprite;
+
+                    for pixel_y in rows.clone() {
+                        for pixel_x in columns.clone() {
+                            let i = (pixel_x - x) as usize;
+                            let j = (pixel_y - y) as usize;
+                            let color = sprite[i + j * Sprite::WIDTH];
+                            if transparency & (1 << color) != 0 {
+                                continue;
+                            }
+
+                            let color = draw_palette[color as usize];
+                            let index = pixel_x as usize + (pixel_y - band_y) as usize * width;
+                            pixels[index] = color;
+                            write_rgb(buffer, &screen_palette, index, color);
+                        }
+                    }
+                }
+            }
+        };
+
+        let pixel_bands = self.pixels.chunks_mut(band_height * width);
+        let buffer_bands = self.buffer.chunks_mut(band_height * width * NUM_COMPONENTS);
+
+        let draw_band = &draw_band;
+        let tasks = pixel_bands
+            .zip(buffer_bands)
+            .enumerate()
+            .map(|(band, (pixels, buffer))| {
+                Box::new(move || draw_band((band * band_height) as i32, pixels, buffer))
+                    as thread_pool::Task<'_>
+            })
+            .collect();
+        thread_pool::run(tasks);
+    }
+}
+
+/// How many cells `map` has to draw to be split across threads, see [`DrawData::map_parallel`].
+#[cfg(all(feature = "parallel-map", not(target_arch = "wasm32")))]
+const PARALLEL_MAP_CELLS: i32 = 256;
+
+impl Default for DrawData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes the RGB bytes of the pixel at `index` in `buffer`, through `screen_palette`.
+fn write_rgb(buffer: &mut [u8], screen_palette: &[Color; 16], index: usize, color: Color) {
+    buffer[NUM_COMPONENTS * index..NUM_COMPONENTS * (index + 1)]
+        .copy_from_slice(&rgb(screen_palette, color));
+}
+
+/// The RGB bytes `color` shows up as, through `screen_palette`.
+fn rgb(screen_palette: &[Color; 16], color: Color) -> [u8; NUM_COMPONENTS] {
+    let [_, r, g, b] = get_color(screen_palette[color as usize]).to_be_bytes();
+
+    [r, g, b]
+}
+
+// Pico8 api
+
+/// The `0xRRGGBB` value of a screen palette entry: bit 7 picks [`EXTENDED_COLORS`] over [`COLORS`].
 fn get_color(index: Color) -> u32 {
-    COLORS[index as usize]
+    let colors = if index & 0x80 == 0 {
+        &COLORS
+    } else {
+        &EXTENDED_COLORS
+    };
+
+    colors[(index & 0x0f) as usize]
 }
 
+/// `0xRRGGBB` values of the colors in the Pico8 palette.
 // Add _FF at the end for alpha
 pub const COLORS: [u32; 16] = [
     0x000000, // Black
@@ -340,6 +1310,28 @@ pub const COLORS: [u32; 16] = [
     0xFFCCAA, // Light peach
 ];
 
+/// `0xRRGGBB` values of Pico8's secret palette, colors `128..=143`.
+///
+/// They can only be shown through the screen palette, e.g. `screen_pal(c, 128 + n)`.
+pub const EXTENDED_COLORS: [u32; 16] = [
+    0x291814, // Brownish black
+    0x111D35, // Darker blue
+    0x422136, // Darker purple
+    0x125359, // Blue green
+    0x742F29, // Dark brown
+    0x49333B, // Darker grey
+    0xA28879, // Medium grey
+    0xF3EF7D, // Light yellow
+    0xBE1250, // Dark red
+    0xFF6C24, // Dark orange
+    0xA8E72E, // Lime green
+    0x00B543, // Medium green
+    0x065AB5, // True blue
+    0x754665, // Mauve
+    0xFF6E59, // Dark peach
+    0xFF9D81, // Peach
+];
+
 /// Named constants for the colors in the Pico8 palette.
 pub mod colors {
     use crate::Color;
@@ -360,6 +1352,86 @@ pub mod colors {
     pub const LAVENDER: Color = 13;
     pub const PINK: Color = 14;
     pub const LIGHT_PEACH: Color = 15;
+
+    /// The colors of the Pico8 palette by name, for when raw indices get confusing.
+    ///
+    /// The draw API takes [`Color`] indices, which these convert to:
+    ///
+    /// ```
+    /// use runty8_core::colors::{self, PaletteColor};
+    ///
+    /// let color: runty8_core::Color = PaletteColor::Red.into();
+    /// assert_eq!(color, colors::RED);
+    /// assert_eq!(PaletteColor::try_from(8), Ok(PaletteColor::Red));
+    /// assert_eq!(PaletteColor::Red.rgb(), (0xff, 0x00, 0x4d));
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[repr(u8)]
+    pub enum PaletteColor {
+        Black = BLACK,
+        DarkBlue = DARK_BLUE,
+        DarkPurple = DARK_PURPLE,
+        DarkGreen = DARK_GREEN,
+        Brown = BROWN,
+        DarkGrey = DARK_GREY,
+        LightGrey = LIGHT_GREY,
+        White = WHITE,
+        Red = RED,
+        Orange = ORANGE,
+        Yellow = YELLOW,
+        Green = GREEN,
+        Blue = BLUE,
+        Lavender = LAVENDER,
+        Pink = PINK,
+        LightPeach = LIGHT_PEACH,
+    }
+
+    impl PaletteColor {
+        /// All 16 colors, in palette order.
+        pub const ALL: [Self; 16] = [
+            Self::Black,
+            Self::DarkBlue,
+            Self::DarkPurple,
+            Self::DarkGreen,
+            Self::Brown,
+            Self::DarkGrey,
+            Self::LightGrey,
+            Self::White,
+            Self::Red,
+            Self::Orange,
+            Self::Yellow,
+            Self::Green,
+            Self::Blue,
+            Self::Lavender,
+            Self::Pink,
+            Self::LightPeach,
+        ];
+
+        /// The color's red, green and blue components in the original palette (see [`crate::COLORS`]).
+        pub fn rgb(self) -> (u8, u8, u8) {
+            let [_, r, g, b] = crate::COLORS[self as usize].to_be_bytes();
+
+            (r, g, b)
+        }
+    }
+
+    impl From<PaletteColor> for Color {
+        fn from(color: PaletteColor) -> Self {
+            color as Color
+        }
+    }
+
+    impl TryFrom<Color> for PaletteColor {
+        type Error = String;
+
+        /// Fails for indices past 15.
+        fn try_from(index: Color) -> Result<Self, String> {
+            Self::ALL
+                .get(index as usize)
+                .copied()
+                .ok_or_else(|| format!("Invalid color: {index}, expected 0..=15"))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -368,10 +1440,59 @@ mod tests {
 
     use crate::{
         colors,
-        draw_data::{get_color, Buffer, NUM_COMPONENTS},
+        draw_data::{get_color, NUM_COMPONENTS},
     };
 
-    use super::DrawData;
+    use super::{ClipRect, DrawData};
+    use crate::font::CustomFont;
+    use crate::Map;
+
+    #[test]
+    fn screen_palette_selects_extended_colors() {
+        let mut draw_data = DrawData::new();
+        draw_data.pset(0, 0, colors::RED);
+        draw_data.pset(1, 0, colors::BLUE);
+
+        draw_data.screen_pal(colors::RED, 128 + 8);
+        assert_eq!(pixel(&draw_data, 0, 0), 0xBE1250);
+        assert_eq!(pixel(&draw_data, 1, 0), get_color(colors::BLUE));
+        assert_eq!(draw_data.peek_draw_state(0x10 + 8), Some(0x88));
+
+        // Bits 4 to 6 don't matter.
+        draw_data.screen_pal(colors::BLUE, 0xf0 | 12);
+        assert_eq!(pixel(&draw_data, 1, 0), 0x065AB5);
+    }
+
+    #[test]
+    fn palette_colors_have_pico8_rgb_values() {
+        use crate::colors::PaletteColor;
+
+        let expected = [
+            (PaletteColor::Black, (0x00, 0x00, 0x00)),
+            (PaletteColor::DarkBlue, (0x1d, 0x2b, 0x53)),
+            (PaletteColor::DarkPurple, (0x7e, 0x25, 0x53)),
+            (PaletteColor::DarkGreen, (0x00, 0x87, 0x51)),
+            (PaletteColor::Brown, (0xab, 0x52, 0x36)),
+            (PaletteColor::DarkGrey, (0x5f, 0x57, 0x4f)),
+            (PaletteColor::LightGrey, (0xc2, 0xc3, 0xc7)),
+            (PaletteColor::White, (0xff, 0xf1, 0xe8)),
+            (PaletteColor::Red, (0xff, 0x00, 0x4d)),
+            (PaletteColor::Orange, (0xff, 0xa3, 0x00)),
+            (PaletteColor::Yellow, (0xff, 0xec, 0x27)),
+            (PaletteColor::Green, (0x00, 0xe4, 0x36)),
+            (PaletteColor::Blue, (0x29, 0xad, 0xff)),
+            (PaletteColor::Lavender, (0x83, 0x76, 0x9c)),
+            (PaletteColor::Pink, (0xff, 0x77, 0xa8)),
+            (PaletteColor::LightPeach, (0xff, 0xcc, 0xaa)),
+        ];
+
+        for (index, (color, rgb)) in expected.into_iter().enumerate() {
+            assert_eq!(color.rgb(), rgb, "{color:?}");
+            assert_eq!(u8::from(color), index as u8);
+            assert_eq!(PaletteColor::try_from(index as u8), Ok(color));
+        }
+        assert!(PaletteColor::try_from(16).is_err());
+    }
 
     #[derive(Clone)]
     enum IterBothNextYield {
@@ -417,10 +1538,10 @@ mod tests {
 
     #[test]
     fn rectfill_works_with_unordered_arguments() {
-        fn red_pixels_count(buf: &Buffer) -> usize {
+        fn red_pixels_count(buf: &[u8]) -> usize {
             fn is_red(chunk: &[u8]) -> bool {
                 let chunk: Vec<u32> = chunk
-                    .into_iter()
+                    .iter()
                     .copied()
                     .map(|component| component as u32)
                     .collect();
@@ -507,4 +1628,1125 @@ mod tests {
         assert_eq!(draw_data.camera(42, 42), (5, 25));
         assert_eq!(draw_data.camera(0, 0), (42, 42));
     }
+
+    fn pixel(draw_data: &DrawData, x: usize, y: usize) -> u32 {
+        let index = NUM_COMPONENTS * (x + y * 128);
+        let rgb = &draw_data.buffer()[index..index + 3];
+
+        (rgb[0] as u32) << 16 | (rgb[1] as u32) << 8 | rgb[2] as u32
+    }
+
+    #[test]
+    fn pset_is_offset_by_camera() {
+        let mut draw_data = DrawData::new();
+
+        draw_data.camera(5, 5);
+        draw_data.pset(10, 10, colors::RED);
+
+        assert_eq!(pixel(&draw_data, 5, 5), get_color(colors::RED));
+        assert_eq!(pixel(&draw_data, 10, 10), get_color(colors::BLACK));
+    }
+
+    #[test]
+    fn primitives_are_offset_by_camera() {
+        let mut draw_data = DrawData::new();
+        draw_data.camera(-20, 10);
+
+        draw_data.line(0, 10, 3, 10, colors::RED);
+        draw_data.rect(0, 20, 2, 22, colors::GREEN);
+        draw_data.circfill(0, 40, 0, colors::BLUE);
+        draw_data.print("A", 0, 50, colors::WHITE);
+
+        assert_eq!(pixel(&draw_data, 23, 0), get_color(colors::RED));
+        assert_eq!(pixel(&draw_data, 20, 10), get_color(colors::GREEN));
+        assert_eq!(pixel(&draw_data, 22, 12), get_color(colors::GREEN));
+        assert_eq!(pixel(&draw_data, 20, 30), get_color(colors::BLUE));
+        // The top row of "A" is lit.
+        assert_eq!(pixel(&draw_data, 21, 40), get_color(colors::WHITE));
+    }
+
+    #[test]
+    fn spr_is_offset_by_camera() {
+        let mut sprite_sheet = crate::SpriteSheet::new();
+        for x in 0..8 {
+            for y in 0..8 {
+                sprite_sheet.set(8 + x, y, colors::YELLOW);
+            }
+        }
+        let mut draw_data = DrawData::new();
+
+        draw_data.camera(8, 8);
+        draw_data.spr(sprite_sheet.get_sprite(1), 8, 8);
+
+        assert_eq!(pixel(&draw_data, 0, 0), get_color(colors::YELLOW));
+        assert_eq!(pixel(&draw_data, 7, 7), get_color(colors::YELLOW));
+        assert_eq!(pixel(&draw_data, 8, 8), get_color(colors::BLACK));
+    }
+
+    #[test]
+    fn reset_camera_returns_old_camera() {
+        let mut draw_data = DrawData::new();
+        draw_data.camera(5, 5);
+
+        assert_eq!(draw_data.reset_camera(), (5, 5));
+        draw_data.pset(10, 10, colors::RED);
+        assert_eq!(pixel(&draw_data, 10, 10), get_color(colors::RED));
+    }
+
+    fn lit_pixels(draw_data: &DrawData) -> Vec<(usize, usize)> {
+        (0..128)
+            .flat_map(|y| (0..128).map(move |x| (x, y)))
+            .filter(|&(x, y)| pixel(draw_data, x, y) != get_color(colors::BLACK))
+            .collect()
+    }
+
+    // The top left `width` by `height` pixels, `#` for lit ones.
+    fn picture(draw_data: &DrawData, width: usize, height: usize) -> Vec<String> {
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        if pixel(draw_data, x, y) == get_color(colors::BLACK) {
+                            '.'
+                        } else {
+                            '#'
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn print_returns_end_of_last_line() {
+        let mut draw_data = DrawData::new();
+
+        assert_eq!(draw_data.print("AB", 10, 0, colors::WHITE), 18);
+        assert_eq!(draw_data.print("ABC\nD", 10, 20, colors::WHITE), 14);
+
+        // The second line starts back at x = 10, 6 pixels down.
+        let lit = lit_pixels(&draw_data);
+        assert!(lit
+            .iter()
+            .any(|&(x, y)| (10..14).contains(&x) && (26..32).contains(&y)));
+        assert!(!lit.iter().any(|&(x, y)| x >= 14 && (26..32).contains(&y)));
+    }
+
+    #[test]
+    fn print_at_cursor_advances_lines() {
+        let mut draw_data = DrawData::new();
+
+        draw_data.print("A", 5, 10, colors::WHITE);
+        assert_eq!(draw_data.cursor(0, 0), (5, 16));
+
+        draw_data.print_at_cursor("A\nB", colors::WHITE);
+        draw_data.print_at_cursor("C", colors::WHITE);
+        assert_eq!(draw_data.cursor(0, 0), (0, 18));
+    }
+
+    #[test]
+    fn print_at_cursor_scrolls_at_the_bottom() {
+        let mut draw_data = DrawData::new();
+
+        draw_data.pset(127, 127, colors::RED);
+        draw_data.pset(127, 2, colors::RED);
+        draw_data.cursor(0, 125);
+        draw_data.print_at_cursor("A", colors::WHITE);
+
+        assert_eq!(draw_data.cursor(0, 0), (0, 128));
+        // Scrolled up by 3 rows, so that the line fits.
+        assert_eq!(draw_data.pget(127, 124), colors::RED);
+        assert_eq!(draw_data.pget(127, 127), 0);
+        assert!(!lit_pixels(&draw_data).contains(&(127, 0)));
+        assert!(lit_pixels(&draw_data).iter().any(|&(_, y)| y >= 122));
+    }
+
+    #[test]
+    fn print_switches_colors_mid_string() {
+        let mut draw_data = DrawData::new();
+
+        // "A" in white, then "B" in red on a blue background.
+        let end = draw_data.print("A\x0c8\x02cB", 0, 0, colors::WHITE);
+
+        assert_eq!(end, 8);
+        let colors_in = |x0: usize| {
+            (0..6)
+                .flat_map(|y| (x0..x0 + 4).map(move |x| (x, y)))
+                .map(|(x, y)| draw_data.pget(x as i32, y))
+                .collect::<std::collections::HashSet<_>>()
+        };
+        assert_eq!(colors_in(0), [colors::BLACK, colors::WHITE].into());
+        assert_eq!(colors_in(4), [colors::BLUE, colors::RED].into());
+    }
+
+    #[test]
+    fn print_wraps_colors_past_15() {
+        let mut draw_data = DrawData::new();
+
+        // `g` is 16 and `v` is 31, which wrap around to black and light peach.
+        draw_data.print("\x0cgA\x02v\x0c8B", 0, 0, colors::RED);
+
+        assert!(lit_pixels(&draw_data).iter().all(|&(x, _)| x >= 4));
+        let background = (4..8).flat_map(|x| (0..6).map(move |y| (x, y)));
+        assert!(background
+            .map(|(x, y)| draw_data.pget(x, y))
+            .all(|color| color == colors::LIGHT_PEACH || color == colors::RED));
+    }
+
+    #[test]
+    fn print_tabs_and_goes_home() {
+        let mut draw_data = DrawData::new();
+
+        assert_eq!(draw_data.print("A\tB", 10, 0, colors::WHITE), 30);
+        assert_eq!(draw_data.print("AB\x06gC", 10, 10, colors::WHITE), 14);
+        assert_eq!(draw_data.print("AB\rC", 10, 20, colors::WHITE), 14);
+    }
+
+    #[test]
+    fn custom_font_replaces_glyphs() {
+        let mut draw_data = DrawData::new();
+        let mut font = CustomFont::new(6, 9);
+        // A diagonal line, from the top left corner.
+        font.glyphs[usize::from(b'A' - b' ')] = [1, 2, 4, 8, 16, 32, 64, 128];
+
+        draw_data.set_font(Some(font));
+        assert_eq!(draw_data.print("AA\nA", 0, 0, colors::WHITE), 6);
+
+        let diagonal = |x0: usize, y0: usize| (0..8).map(move |i| (x0 + i, y0 + i));
+        let mut expected: Vec<_> = diagonal(0, 0)
+            .chain(diagonal(6, 0))
+            .chain(diagonal(0, 9))
+            .collect();
+        expected.sort_by_key(|&(x, y)| (y, x));
+        expected.dedup();
+        assert_eq!(lit_pixels(&draw_data), expected);
+
+        // Characters the font doesn't have fall back to the built-in ones.
+        draw_data.cls_color(colors::BLACK);
+        draw_data.print("\u{2190}", 0, 0, colors::WHITE);
+        assert!(!lit_pixels(&draw_data).is_empty());
+
+        draw_data.set_font(None);
+        assert_eq!(draw_data.print("A", 0, 0, colors::WHITE), 4);
+    }
+
+    #[test]
+    fn tabs_stay_put_with_zero_width_fonts() {
+        let mut draw_data = DrawData::new();
+        draw_data.set_font(Some(CustomFont::new(0, 6)));
+
+        assert_eq!(draw_data.print("\t\t", 10, 0, colors::WHITE), 10);
+    }
+
+    #[test]
+    fn custom_font_reads_pico8_memory() {
+        let mut memory = vec![0; 0x400];
+        memory[0] = 5;
+        memory[2] = 7;
+        memory[8 * 0x41] = 0xff;
+
+        let font = CustomFont::from_pico8_memory(&memory).unwrap();
+
+        assert_eq!((font.char_width, font.line_height), (5, 7));
+        assert_eq!(font.glyph('A'), Some(&[0xff, 0, 0, 0, 0, 0, 0, 0]));
+        assert!(CustomFont::from_pico8_memory(&memory[..0x3ff]).is_err());
+    }
+
+    #[test]
+    fn prints_wide_characters() {
+        let mut draw_data = DrawData::new();
+
+        // The heart, both as its P8SCII code and as its Unicode character.
+        assert_eq!(draw_data.print("\u{87}A", 0, 0, colors::WHITE), 12);
+        assert_eq!(draw_data.print("♥", 0, 10, colors::WHITE), 8);
+
+        let heart = [
+            ".##.##.", //
+            "#######", //
+            "#######", //
+            ".#####.", //
+            "...#...", //
+        ];
+        assert_eq!(&picture(&draw_data, 7, 5)[..], heart);
+        let second: Vec<String> = picture(&draw_data, 7, 15)[10..].to_vec();
+        assert_eq!(second, heart);
+
+        // Emoji variation selectors don't take any space.
+        assert_eq!(
+            draw_data.print("⬅\u{fe0f}➡\u{fe0f}", 0, 20, colors::WHITE),
+            16
+        );
+    }
+
+    #[test]
+    fn print_draws_unknown_characters_as_missing() {
+        let mut draw_data = DrawData::new();
+
+        assert_eq!(draw_data.print("\u{7f}é", 0, 0, colors::WHITE), 8);
+        assert!(!lit_pixels(&draw_data).is_empty());
+    }
+
+    // The shapes in these tests are what this rasterizer (pemsa's port of pico8's) draws,
+    // pinned to catch changes. They weren't captured from pico8.
+    #[test]
+    fn circ_keeps_its_shape() {
+        let mut draw_data = DrawData::new();
+        draw_data.circ(4, 4, 4, colors::RED);
+        draw_data.circ(8, 18, 8, colors::RED);
+
+        let small = [
+            "...###...",
+            ".##...##.",
+            ".#.....#.",
+            "#.......#",
+            "#.......#",
+            "#.......#",
+            ".#.....#.",
+            ".##...##.",
+            "...###...",
+        ];
+        let big = [
+            "......#####......",
+            "....##.....##....",
+            "...#.........#...",
+            "..#...........#..",
+            ".#.............#.",
+            ".#.............#.",
+            "#...............#",
+            "#...............#",
+            "#...............#",
+            "#...............#",
+            "#...............#",
+            ".#.............#.",
+            ".#.............#.",
+            "..#...........#..",
+            "...#.........#...",
+            "....##.....##....",
+            "......#####......",
+        ];
+        assert_eq!(&picture(&draw_data, 9, 9)[..], small);
+        assert_eq!(&picture(&draw_data, 17, 27)[10..], big);
+    }
+
+    #[test]
+    fn circfill_keeps_its_shape() {
+        let mut draw_data = DrawData::new();
+        draw_data.circfill(4, 4, 4, colors::RED);
+        draw_data.circfill(8, 18, 8, colors::RED);
+
+        let small = [
+            "...###...",
+            ".#######.",
+            ".#######.",
+            "#########",
+            "#########",
+            "#########",
+            ".#######.",
+            ".#######.",
+            "...###...",
+        ];
+        let big = [
+            "......#####......",
+            "....#########....",
+            "...###########...",
+            "..#############..",
+            ".###############.",
+            ".###############.",
+            "#################",
+            "#################",
+            "#################",
+            "#################",
+            "#################",
+            ".###############.",
+            ".###############.",
+            "..#############..",
+            "...###########...",
+            "....#########....",
+            "......#####......",
+        ];
+        assert_eq!(&picture(&draw_data, 9, 9)[..], small);
+        assert_eq!(&picture(&draw_data, 17, 27)[10..], big);
+    }
+
+    #[test]
+    fn circfill_follows_camera_clip_and_fillp() {
+        let mut draw_data = DrawData::new();
+        draw_data.camera(-1, 0);
+        draw_data.clip(
+            ClipRect {
+                x: 0,
+                y: 0,
+                w: 9,
+                h: 4,
+            },
+            false,
+        );
+        // Every other column, transparent.
+        draw_data.fillp(0b0101_0101_0101_0101, true);
+        draw_data.circfill(3, 4, 4, colors::RED);
+
+        assert_eq!(
+            picture(&draw_data, 11, 5),
+            [
+                "....#......",
+                "..#.#.#....",
+                "..#.#.#....",
+                "#.#.#.#.#..",
+                "...........",
+            ]
+        );
+    }
+
+    // Like the circles above, pinned from this implementation rather than captured from pico8.
+    #[test]
+    fn oval_keeps_its_shape() {
+        let mut draw_data = DrawData::new();
+        draw_data.oval(0, 0, 6, 4, colors::RED);
+
+        assert_eq!(
+            picture(&draw_data, 8, 6),
+            [".#####..", "#.....#.", "#.....#.", "#.....#.", ".#####..", "........",]
+        );
+    }
+
+    #[test]
+    fn ovalfill_keeps_its_shape() {
+        let mut draw_data = DrawData::new();
+        // Corners in any order.
+        draw_data.ovalfill(5, 3, 0, 0, colors::RED);
+
+        assert_eq!(
+            picture(&draw_data, 7, 5),
+            [".####..", "######.", "######.", ".####..", "......."]
+        );
+    }
+
+    #[test]
+    fn degenerate_ovals_are_lines() {
+        let mut draw_data = DrawData::new();
+        draw_data.oval(2, 0, 2, 3, colors::RED);
+        draw_data.ovalfill(0, 5, 4, 5, colors::RED);
+
+        assert_eq!(
+            lit_pixels(&draw_data),
+            [
+                (2, 0),
+                (2, 1),
+                (2, 2),
+                (2, 3),
+                (0, 5),
+                (1, 5),
+                (2, 5),
+                (3, 5),
+                (4, 5)
+            ]
+        );
+    }
+
+    #[test]
+    fn oval_honors_camera_and_clip() {
+        let mut draw_data = DrawData::new();
+        draw_data.camera(-10, -10);
+        draw_data.clip(
+            ClipRect {
+                x: 0,
+                y: 0,
+                w: 13,
+                h: 128,
+            },
+            false,
+        );
+        draw_data.ovalfill(0, 0, 4, 4, colors::RED);
+
+        let lit = lit_pixels(&draw_data);
+        assert!(lit
+            .iter()
+            .all(|&(x, y)| (10..13).contains(&x) && (10..15).contains(&y)));
+        assert_eq!(lit.len(), 2 + 3 + 3 + 3 + 2);
+    }
+
+    #[test]
+    fn huge_ovals_only_draw_what_is_visible() {
+        let mut draw_data = DrawData::new();
+        let far = 1 << 28;
+
+        draw_data.oval(-far, -far, far, far, colors::RED);
+        assert!(lit_pixels(&draw_data).is_empty());
+
+        draw_data.ovalfill(-far, -far, far, far, colors::RED);
+        assert_eq!(lit_pixels(&draw_data).len(), 128 * 128);
+    }
+
+    #[test]
+    fn rectfill_is_cropped_to_clip() {
+        let mut draw_data = DrawData::new();
+
+        draw_data.clip(
+            ClipRect {
+                x: 10,
+                y: 20,
+                w: 5,
+                h: 3,
+            },
+            false,
+        );
+        draw_data.rectfill(0, 0, 127, 127, colors::RED);
+
+        let lit = lit_pixels(&draw_data);
+        assert_eq!(lit.len(), 5 * 3);
+        assert_eq!(lit.first(), Some(&(10, 20)));
+        assert_eq!(lit.last(), Some(&(14, 22)));
+    }
+
+    #[test]
+    fn clip_ignores_camera() {
+        let mut draw_data = DrawData::new();
+
+        draw_data.camera(10, 10);
+        draw_data.clip(
+            ClipRect {
+                x: 0,
+                y: 0,
+                w: 1,
+                h: 1,
+            },
+            false,
+        );
+        draw_data.rectfill(0, 0, 127, 127, colors::RED);
+
+        assert_eq!(lit_pixels(&draw_data), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn nested_clips_can_be_restored() {
+        let mut draw_data = DrawData::new();
+        let outer = ClipRect {
+            x: 0,
+            y: 0,
+            w: 64,
+            h: 64,
+        };
+
+        assert_eq!(draw_data.clip(outer, false), DrawData::screen(128, 128));
+        let previous = draw_data.clip(
+            ClipRect {
+                x: 60,
+                y: 60,
+                w: 10,
+                h: 10,
+            },
+            true,
+        );
+        assert_eq!(previous, outer);
+        draw_data.rectfill(0, 0, 127, 127, colors::RED);
+        assert_eq!(lit_pixels(&draw_data).len(), 4 * 4);
+
+        draw_data.clip(previous, false);
+        draw_data.rectfill(0, 0, 127, 127, colors::RED);
+        assert_eq!(lit_pixels(&draw_data).len(), 64 * 64);
+
+        assert_eq!(draw_data.reset_clip(), outer);
+    }
+
+    #[test]
+    fn clip_is_limited_to_the_screen() {
+        let mut draw_data = DrawData::new();
+
+        draw_data.clip(
+            ClipRect {
+                x: -10,
+                y: 120,
+                w: 20,
+                h: 20,
+            },
+            false,
+        );
+
+        assert_eq!(
+            draw_data.reset_clip(),
+            ClipRect {
+                x: 0,
+                y: 120,
+                w: 10,
+                h: 8
+            }
+        );
+    }
+
+    #[test]
+    fn huge_clip_rects_dont_overflow() {
+        let mut draw_data = DrawData::new();
+
+        draw_data.clip(
+            ClipRect {
+                x: i32::MAX - 1,
+                y: -5,
+                w: i32::MAX,
+                h: i32::MAX,
+            },
+            false,
+        );
+        // Past the right of the screen, so empty.
+        let previous = draw_data.clip(
+            ClipRect {
+                x: 4,
+                y: 5,
+                w: i32::MAX,
+                h: i32::MAX,
+            },
+            false,
+        );
+        assert_eq!(previous.w, 0);
+
+        draw_data.rectfill(0, 0, 127, 127, colors::RED);
+        assert_eq!(lit_pixels(&draw_data).len(), 124 * 123);
+        assert!(draw_data.clip.contains(127, 127));
+    }
+
+    #[test]
+    fn cls_resets_clip() {
+        let mut draw_data = DrawData::new();
+
+        draw_data.clip(
+            ClipRect {
+                x: 0,
+                y: 0,
+                w: 1,
+                h: 1,
+            },
+            false,
+        );
+        draw_data.cls_color(colors::RED);
+
+        assert_eq!(lit_pixels(&draw_data).len(), 128 * 128);
+    }
+
+    fn yellow_sprite_sheet() -> crate::SpriteSheet {
+        let mut sprite_sheet = crate::SpriteSheet::new();
+        for x in 0..8 {
+            for y in 0..8 {
+                sprite_sheet.set(x, y, colors::YELLOW);
+            }
+        }
+
+        sprite_sheet
+    }
+
+    #[test]
+    fn pal_remaps_drawn_sprites() {
+        let sprite_sheet = yellow_sprite_sheet();
+        let mut draw_data = DrawData::new();
+
+        draw_data.pal(colors::YELLOW, colors::RED);
+        draw_data.spr(sprite_sheet.get_sprite(0), 0, 0);
+        // Only affects what's drawn afterwards.
+        draw_data.pal(colors::YELLOW, colors::BLUE);
+
+        assert_eq!(draw_data.buffer()[..3], [0xFF, 0x00, 0x4D]);
+        assert_eq!(pixel(&draw_data, 7, 7), get_color(colors::RED));
+    }
+
+    #[test]
+    fn opaque_sprites_are_copied_row_by_row() {
+        let mut sprite_sheet = crate::SpriteSheet::new();
+        for (i, color) in sprite_sheet.sprite_sheet.iter_mut().enumerate() {
+            *color = (i % 15) as u8 + 1;
+        }
+        // Sprite 1 has a transparent pixel.
+        sprite_sheet.set(8 + 3, 4, 0);
+
+        let draw = |draw_data: &mut DrawData, use_fast_path: bool| {
+            let mut copied = 0;
+            draw_data.camera(-3, 5);
+            draw_data.clip(
+                ClipRect {
+                    x: 2,
+                    y: 10,
+                    w: 100,
+                    h: 90,
+                },
+                false,
+            );
+
+            for i in 0..1000 {
+                let sprite = sprite_sheet.get_sprite(i % 2);
+                // Partly outside of the clipping rectangle, often.
+                let (x, y) = ((i * 7 % 140) as i32 - 10, (i * 13 % 140) as i32 - 10);
+
+                if use_fast_path && draw_data.blit_opaque(sprite, x, y) {
+                    copied += 1;
+                } else {
+                    draw_data.blit(sprite, x, y, false, false);
+                }
+            }
+
+            copied
+        };
+
+        let mut fast = DrawData::new();
+        let mut slow = DrawData::new();
+        assert_eq!(draw(&mut fast, true), 500);
+        assert_eq!(draw(&mut slow, false), 0);
+        assert_eq!(fast.index_buffer(), slow.index_buffer());
+        assert_eq!(fast.buffer(), slow.buffer());
+
+        // Remapped colors take the general path.
+        fast.pal(1, 2);
+        assert!(!fast.blit_opaque(sprite_sheet.get_sprite(0), 0, 0));
+    }
+
+    #[test]
+    fn index_buffer_has_the_drawn_colors() {
+        let mut draw_data = DrawData::new();
+
+        draw_data.pal(colors::YELLOW, colors::RED);
+        draw_data.screen_pal(colors::RED, colors::GREEN);
+        draw_data.pset(3, 2, colors::YELLOW);
+        draw_data.pset(5, 0, colors::BLUE);
+
+        let indices = draw_data.index_buffer();
+        assert_eq!(indices[3 + 2 * 128], colors::RED);
+        assert_eq!(indices[5], colors::BLUE);
+        assert_eq!(indices[0], colors::BLACK);
+        assert_eq!(pixel(&draw_data, 3, 2), get_color(colors::GREEN));
+    }
+
+    #[test]
+    fn other_screen_sizes_clip_to_their_edges() {
+        let mut draw_data = DrawData::with_size(256, 64);
+
+        draw_data.cls_color(colors::BLUE);
+        draw_data.pset(200, 50, colors::RED);
+        draw_data.pset(10, 70, colors::RED);
+
+        assert_eq!(draw_data.buffer().len(), 3 * 256 * 64);
+        assert_eq!(draw_data.pget(200, 50), colors::RED);
+        assert_eq!(draw_data.pget(255, 63), colors::BLUE);
+        assert_eq!(draw_data.pget(10, 70), 0);
+        assert_eq!(
+            draw_data
+                .index_buffer()
+                .iter()
+                .filter(|&&color| color == colors::RED)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn frame_hash_follows_the_indices() {
+        let mut draw_data = DrawData::new();
+        assert_eq!(draw_data.frame_hash(), 0x9c1b_da7f_8c87_2325);
+
+        draw_data.pset(3, 2, colors::YELLOW);
+        let drawn = draw_data.frame_hash();
+        assert_ne!(drawn, DrawData::new().frame_hash());
+
+        draw_data.screen_pal(colors::YELLOW, colors::RED);
+        assert_eq!(draw_data.frame_hash(), drawn);
+    }
+
+    #[test]
+    fn screen_pal_remaps_whole_screen() {
+        let sprite_sheet = yellow_sprite_sheet();
+        let mut draw_data = DrawData::new();
+
+        draw_data.spr(sprite_sheet.get_sprite(0), 0, 0);
+        draw_data.screen_pal(colors::YELLOW, colors::GREEN);
+        draw_data.spr(sprite_sheet.get_sprite(0), 8, 0);
+
+        assert_eq!(draw_data.buffer()[..3], [0x00, 0xE4, 0x36]);
+        assert_eq!(pixel(&draw_data, 15, 7), get_color(colors::GREEN));
+    }
+
+    #[test]
+    fn rectfill_fills_like_pset() {
+        let mut filled = DrawData::new();
+        let mut plotted = DrawData::new();
+        for draw_data in [&mut filled, &mut plotted] {
+            draw_data.camera(-4, 9);
+            draw_data.clip(
+                ClipRect {
+                    x: 1,
+                    y: 2,
+                    w: 100,
+                    h: 90,
+                },
+                false,
+            );
+            draw_data.pal(colors::PINK, colors::GREEN);
+        }
+
+        let rects = [
+            (-10, -10, 20, 30),
+            (120, 5, 90, 140),
+            (50, 50, 50, 50),
+            (300, 0, 400, 9),
+        ];
+        for (i, &(x0, y0, x1, y1)) in rects.iter().enumerate() {
+            let color = colors::PINK + 16 * i as u8;
+            filled.rectfill(x0, y0, x1, y1, color);
+
+            for x in x0.min(x1)..=x0.max(x1) {
+                for y in y0..=y1 {
+                    plotted.pset(x, y, color);
+                }
+            }
+        }
+
+        assert_eq!(filled.index_buffer(), plotted.index_buffer());
+        assert_eq!(filled.buffer(), plotted.buffer());
+    }
+
+    #[test]
+    fn screen_pal_only_converts_the_remapped_color() {
+        let mut draw_data = DrawData::new();
+        for x in 0..16 {
+            draw_data.rectfill(x * 8, 0, x * 8 + 7, 127, x as u8);
+        }
+
+        draw_data.screen_pal(colors::YELLOW, colors::GREEN);
+        draw_data.screen_pal(colors::RED, 128 + colors::RED);
+        let remapped = draw_data.buffer().to_vec();
+
+        draw_data.refresh_buffer();
+        assert_eq!(draw_data.buffer(), remapped);
+        assert_eq!(pixel(&draw_data, 8 * 10, 5), get_color(colors::GREEN));
+        assert_eq!(pixel(&draw_data, 8 * 8, 5), get_color(128 + colors::RED));
+        assert_eq!(pixel(&draw_data, 8 * 9, 5), get_color(9));
+    }
+
+    #[test]
+    fn reset_pal_resets_both_palettes() {
+        let sprite_sheet = yellow_sprite_sheet();
+        let mut draw_data = DrawData::new();
+
+        draw_data.pal(colors::YELLOW, colors::RED);
+        draw_data.screen_pal(colors::RED, colors::GREEN);
+        draw_data.spr(sprite_sheet.get_sprite(0), 0, 0);
+        assert_eq!(pixel(&draw_data, 0, 0), get_color(colors::GREEN));
+
+        draw_data.reset_pal();
+        // The drawn pixel is red, and the screen no longer shows it as green.
+        assert_eq!(pixel(&draw_data, 0, 0), get_color(colors::RED));
+        draw_data.spr(sprite_sheet.get_sprite(0), 8, 0);
+        assert_eq!(pixel(&draw_data, 8, 0), get_color(colors::YELLOW));
+    }
+
+    #[test]
+    fn opaque_black_is_drawn() {
+        let mut sprite_sheet = crate::SpriteSheet::new();
+        sprite_sheet.set(1, 0, colors::YELLOW);
+        let mut draw_data = DrawData::new();
+        draw_data.cls_color(colors::RED);
+
+        draw_data.set_transparent(colors::BLACK, false);
+        draw_data.spr(sprite_sheet.get_sprite(0), 0, 0);
+
+        assert_eq!(pixel(&draw_data, 0, 0), get_color(colors::BLACK));
+        assert_eq!(pixel(&draw_data, 1, 0), get_color(colors::YELLOW));
+    }
+
+    #[test]
+    fn many_colors_can_be_transparent() {
+        let mut sprite_sheet = crate::SpriteSheet::new();
+        sprite_sheet.set(1, 0, colors::YELLOW);
+        sprite_sheet.set(2, 0, colors::BLUE);
+        let mut draw_data = DrawData::new();
+        draw_data.cls_color(colors::RED);
+
+        draw_data.set_transparent(colors::YELLOW, true);
+        draw_data.spr(sprite_sheet.get_sprite(0), 0, 0);
+
+        assert_eq!(pixel(&draw_data, 0, 0), get_color(colors::RED));
+        assert_eq!(pixel(&draw_data, 1, 0), get_color(colors::RED));
+        assert_eq!(pixel(&draw_data, 2, 0), get_color(colors::BLUE));
+
+        draw_data.reset_palt();
+        draw_data.spr(sprite_sheet.get_sprite(0), 0, 0);
+        assert_eq!(pixel(&draw_data, 0, 0), get_color(colors::RED));
+        assert_eq!(pixel(&draw_data, 1, 0), get_color(colors::YELLOW));
+    }
+
+    #[test]
+    fn fillp_draws_checkerboard() {
+        let mut draw_data = DrawData::new();
+
+        draw_data.fillp(0b1010_0101_1010_0101, false);
+        draw_data.rectfill(0, 0, 7, 7, colors::RED | colors::BLUE << 4);
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let expected = if (x + y) % 2 == 0 {
+                    colors::BLUE
+                } else {
+                    colors::RED
+                };
+
+                assert_eq!(pixel(&draw_data, x, y), get_color(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn transparent_fillp_skips_set_bits() {
+        let mut draw_data = DrawData::new();
+
+        draw_data.fillp(0b1010_0101_1010_0101, true);
+        draw_data.rectfill(0, 0, 7, 7, colors::RED);
+
+        assert_eq!(lit_pixels(&draw_data).len(), 32);
+        assert_eq!(pixel(&draw_data, 0, 0), get_color(colors::BLACK));
+        assert_eq!(pixel(&draw_data, 1, 0), get_color(colors::RED));
+    }
+
+    #[test]
+    fn fillp_doesnt_affect_cls_and_print() {
+        let mut draw_data = DrawData::new();
+
+        draw_data.fillp(0xFFFF, true);
+        draw_data.cls_color(colors::RED);
+        draw_data.print("A", 0, 0, colors::WHITE);
+        draw_data.rectfill(0, 0, 127, 127, colors::BLUE);
+
+        assert_eq!(pixel(&draw_data, 1, 0), get_color(colors::WHITE));
+        assert_eq!(pixel(&draw_data, 127, 127), get_color(colors::RED));
+    }
+
+    fn corners_sprite_sheet() -> crate::SpriteSheet {
+        let mut sprite_sheet = crate::SpriteSheet::new();
+        sprite_sheet.set(0, 0, colors::RED);
+        sprite_sheet.set(7, 0, colors::GREEN);
+        sprite_sheet.set(0, 7, colors::BLUE);
+        sprite_sheet.set(7, 7, colors::YELLOW);
+
+        sprite_sheet
+    }
+
+    #[test]
+    fn sspr_scales_sprite_up() {
+        let sprite_sheet = corners_sprite_sheet();
+        let mut draw_data = DrawData::new();
+
+        draw_data.sspr(&sprite_sheet, (0, 0, 8, 8), (10, 10, 16, 16), false, false);
+
+        for (x, y, color) in [
+            (10, 10, colors::RED),
+            (11, 11, colors::RED),
+            (25, 10, colors::GREEN),
+            (10, 25, colors::BLUE),
+            (25, 25, colors::YELLOW),
+            (24, 24, colors::YELLOW),
+        ] {
+            assert_eq!(pixel(&draw_data, x, y), get_color(color));
+        }
+        assert_eq!(lit_pixels(&draw_data).len(), 4 * 4);
+    }
+
+    #[test]
+    fn sspr_flips() {
+        let sprite_sheet = corners_sprite_sheet();
+        let mut draw_data = DrawData::new();
+
+        draw_data.sspr(&sprite_sheet, (0, 0, 8, 8), (0, 0, 8, 8), true, false);
+        // Negative sizes flip too, so this flips back.
+        draw_data.sspr(&sprite_sheet, (0, 0, 8, 8), (8, 0, -8, 8), true, false);
+
+        assert_eq!(pixel(&draw_data, 0, 0), get_color(colors::GREEN));
+        assert_eq!(pixel(&draw_data, 7, 7), get_color(colors::BLUE));
+        assert_eq!(pixel(&draw_data, 8, 0), get_color(colors::RED));
+    }
+
+    #[test]
+    fn sspr_with_empty_destination_draws_nothing() {
+        let sprite_sheet = corners_sprite_sheet();
+        let mut draw_data = DrawData::new();
+
+        draw_data.sspr(&sprite_sheet, (0, 0, 8, 8), (0, 0, 0, 8), false, false);
+        draw_data.sspr(
+            &sprite_sheet,
+            (-8, -8, 200, 200),
+            (0, 0, 4, 4),
+            false,
+            false,
+        );
+
+        assert_eq!(lit_pixels(&draw_data).len(), 0);
+    }
+
+    #[test]
+    fn tline_samples_map_across_cells() {
+        let mut sprite_sheet = crate::SpriteSheet::new();
+        for x in 0..8 {
+            // Sprite 1 has a gradient on its 3rd row, sprite 2 is solid.
+            sprite_sheet.set(8 + x, 2, x as u8 + 1);
+            for y in 0..8 {
+                sprite_sheet.set(16 + x, y, colors::WHITE);
+            }
+        }
+        let mut map = Map::new();
+        map.mset(3, 5, 1);
+        map.mset(4, 5, 2);
+        let mut draw_data = DrawData::new();
+
+        draw_data.tline(
+            (0, 10, 15, 10),
+            (3.0, 5.0 + 2.0 / 8.0),
+            (1.0 / 8.0, 0.0),
+            &map,
+            &sprite_sheet,
+        );
+
+        for x in 0..8 {
+            assert_eq!(pixel(&draw_data, x, 10), get_color(x as u8 + 1));
+            assert_eq!(pixel(&draw_data, x + 8, 10), get_color(colors::WHITE));
+        }
+        assert_eq!(lit_pixels(&draw_data).len(), 16);
+    }
+
+    #[test]
+    fn tline_stays_in_the_sprite_just_below_cell_edges() {
+        let mut sprite_sheet = crate::SpriteSheet::new();
+        for x in 0..8 {
+            sprite_sheet.set(x, 7, colors::WHITE);
+        }
+        let mut draw_data = DrawData::new();
+
+        // Right above row 0, walking left across column 0.
+        draw_data.tline(
+            (0, 0, 11, 0),
+            (0.5, -1e-8),
+            (-1.0 / 8.0, 0.0),
+            &Map::new(),
+            &sprite_sheet,
+        );
+
+        assert_eq!(lit_pixels(&draw_data).len(), 12);
+    }
+
+    #[test]
+    fn tline_honors_pal() {
+        let mut sprite_sheet = crate::SpriteSheet::new();
+        sprite_sheet.set(8, 0, colors::WHITE);
+        let mut map = Map::new();
+        map.mset(0, 0, 1);
+        let mut draw_data = DrawData::new();
+
+        draw_data.pal(colors::WHITE, colors::RED);
+        draw_data.tline(
+            (0, 0, 7, 0),
+            (0.0, 0.0),
+            (1.0 / 8.0, 0.0),
+            &map,
+            &sprite_sheet,
+        );
+
+        assert_eq!(pixel(&draw_data, 0, 0), get_color(colors::RED));
+        // Transparent black isn't drawn.
+        assert_eq!(lit_pixels(&draw_data).len(), 1);
+    }
+
+    #[test]
+    fn map_only_draws_sprites_in_layer() {
+        let mut sprite_sheet = crate::SpriteSheet::new();
+        sprite_sheet.set(8, 0, colors::RED);
+        sprite_sheet.set(16, 0, colors::BLUE);
+        let mut flags = crate::Flags::new();
+        flags.fset(1, 0, true);
+        flags.fset(2, 1, true);
+        let mut map = Map::with_size(4, 4);
+        map.mset(0, 0, 1);
+        map.mset(1, 0, 2);
+        let mut draw_data = DrawData::new();
+
+        draw_data.map(0, 0, 0, 0, 4, 4, 0b10, &map, &flags, &sprite_sheet);
+
+        assert_eq!(pixel(&draw_data, 0, 0), get_color(colors::BLACK));
+        assert_eq!(pixel(&draw_data, 8, 0), get_color(colors::BLUE));
+
+        draw_data.map(0, 0, 0, 0, 4, 4, 0, &map, &flags, &sprite_sheet);
+        assert_eq!(pixel(&draw_data, 0, 0), get_color(colors::RED));
+    }
+
+    #[cfg(all(feature = "parallel-map", not(target_arch = "wasm32")))]
+    #[test]
+    fn map_parallel_draws_like_map_serial() {
+        let mut sprite_sheet = crate::SpriteSheet::new();
+        for (i, color) in sprite_sheet.sprite_sheet.iter_mut().enumerate() {
+            *color = (i % 7) as u8;
+        }
+        let mut flags = crate::Flags::new();
+        flags.fset(3, 0, true);
+        let mut map = Map::new();
+        for x in 0..128 {
+            for y in 0..64 {
+                map.mset(x, y, ((x * 3 + y) % 6) as u8);
+            }
+        }
+
+        let draw = |parallel: bool| {
+            let mut draw_data = DrawData::new();
+            draw_data.camera(5, -3);
+            draw_data.clip(
+                ClipRect {
+                    x: 3,
+                    y: 7,
+                    w: 110,
+                    h: 97,
+                },
+                false,
+            );
+            draw_data.pal(2, 9);
+
+            let (cell, screen, size) = ((10, 4), (-20, 1), (20, 20));
+            for layer in [0, 1] {
+                if parallel {
+                    draw_data.map_parallel(cell, screen, size, layer, &map, &flags, &sprite_sheet);
+                } else {
+                    draw_data.map_serial(cell, screen, size, layer, &map, &flags, &sprite_sheet);
+                }
+            }
+
+            draw_data
+        };
+
+        let (serial, parallel) = (draw(false), draw(true));
+        assert_ne!(serial.frame_hash(), DrawData::new().frame_hash());
+        assert_eq!(serial.index_buffer(), parallel.index_buffer());
+        assert_eq!(serial.buffer(), parallel.buffer());
+    }
+
+    #[test]
+    fn map_skips_empty_cells_and_stops_at_its_size() {
+        let mut sprite_sheet = crate::SpriteSheet::new();
+        for x in 0..8 {
+            sprite_sheet.set(x, 0, colors::RED);
+            sprite_sheet.set(8 + x, 0, colors::BLUE);
+        }
+        let mut map = Map::with_size(4, 4);
+        map.mset(0, 0, 1);
+        map.mset(1, 0, 1);
+        map.mset(2, 0, 1);
+        let mut draw_data = DrawData::new();
+        draw_data.palt(None);
+
+        draw_data.camera(-8, 0);
+        draw_data.map(
+            0,
+            0,
+            0,
+            0,
+            2,
+            2,
+            0,
+            &map,
+            &crate::Flags::new(),
+            &sprite_sheet,
+        );
+
+        // Two cells drawn, offset by the camera, and sprite 0 isn't drawn even when opaque.
+        assert_eq!(lit_pixels(&draw_data).len(), 16);
+        assert_eq!(pixel(&draw_data, 8, 0), get_color(colors::BLUE));
+        assert_eq!(pixel(&draw_data, 23, 0), get_color(colors::BLUE));
+    }
 }
@@ -40,19 +40,22 @@ impl Flags {
         Self { flags }
     }
 
-    fn len(&self) -> usize {
-        self.flags.len()
-    }
-
+    /// Does nothing if `index` isn't a sprite.
     fn set(&mut self, index: usize, value: u8) {
-        self.flags[index] = value;
+        if let Some(flags) = self.flags.get_mut(index) {
+            *flags = value;
+        }
     }
 
     pub fn get(&self, index: usize) -> Option<u8> {
-        // TODO: Check what pico8 does in cases when the index is out of bounds
         self.flags.get(index).copied()
     }
 
+    // Pico8's fget(n). Sprites outside the sheet have no flags set.
+    pub fn fget(&self, sprite: usize) -> u8 {
+        self.get(sprite).unwrap_or(0)
+    }
+
     // Pico8's fset(n, v)
     pub fn fset_all(&mut self, sprite: usize, flags: u8) -> u8 {
         self.set(sprite, flags);
@@ -60,40 +63,33 @@ impl Flags {
         flags
     }
 
-    // Pico8's fset(n, f, v)
+    // Pico8's fset(n, f, v). Returns the sprite's new flags, flags past 7 are ignored.
     pub fn fset(&mut self, sprite: usize, flag: usize, value: bool) -> u8 {
-        // TODO: Check what pico8 does in these cases:
-        assert!(flag <= 7);
+        let flags = self.fget(sprite);
+        if flag > 7 {
+            return flags;
+        }
 
         let value = value as u8;
-        let mut flags = self.get(sprite).unwrap();
-        flags = (flags & !(1u8 << flag)) | (value << flag);
+        self.set(sprite, (flags & !(1u8 << flag)) | (value << flag));
 
-        self.set(sprite, flags);
-
-        flags
+        self.fget(sprite)
     }
 
+    // Pico8's fget(n, f)
     pub fn fget_n(&self, sprite: usize, flag: u8) -> bool {
-        // TODO: Check what pico8 does in these cases:
-        assert!(sprite < self.len());
-        assert!(flag <= 7);
-
-        let res = (self.get(sprite).unwrap() & (1 << flag)) >> flag;
-        assert!(res == 0 || res == 1);
-
-        res != 0
+        flag <= 7 && self.fget(sprite) & (1 << flag) != 0
     }
 
+    /// Parses the format of [`Serialize::serialize`]: each sprite's flags in binary, one per line.
     pub fn deserialize(file_contents: &str) -> Result<Self, String> {
-        // let flags_vec: Result<Vec<u8>, String> = file_contents
         let flags_vec: Result<Vec<u8>, String> = file_contents
             .lines()
-            .map(
-                |line| u8::from_str_radix(line, 2), //  line.parse::<u8>().map_err(|e| format!("{:?}", e))
-            )
-            .collect::<Result<Vec<u8>, _>>()
-            .map_err(|err| format!("{err:?}"));
+            .map(|line| {
+                u8::from_str_radix(line.trim(), 2)
+                    .map_err(|err| format!("Invalid flags {line:?}: {err}"))
+            })
+            .collect();
 
         let flags_array: [u8; SpriteSheet::SPRITE_COUNT] =
             flags_vec?.try_into().map_err(|v: Vec<u8>| {
@@ -122,3 +118,73 @@ impl Default for Flags {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Flags;
+
+    #[test]
+    fn flags_round_trip() {
+        let mut flags = Flags::new();
+
+        for flag in 0..8 {
+            assert_eq!(flags.fset(3, flag, true), 1 << flag);
+            assert!(flags.fget_n(3, flag as u8));
+            assert_eq!(flags.fget(3), 1 << flag);
+
+            assert_eq!(flags.fset(3, flag, false), 0);
+            assert!(!flags.fget_n(3, flag as u8));
+        }
+    }
+
+    #[test]
+    fn random_flags_serialize_round_trip() {
+        use crate::serialize::Serialize;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(8);
+        for _ in 0..20 {
+            let mut flags = Flags::new();
+            rng.fill(&mut flags.flags[..]);
+
+            let deserialized = Flags::deserialize(&flags.serialize()).unwrap();
+
+            assert_eq!(deserialized.flags, flags.flags);
+        }
+    }
+
+    #[test]
+    fn deserialize_rejects_bad_flags() {
+        let flags = vec!["00000001"; 256].join("\r\n");
+        assert_eq!(Flags::deserialize(&flags).unwrap().fget(255), 1);
+
+        assert!(Flags::deserialize(&flags.replacen('1', "2", 1)).is_err());
+        assert!(Flags::deserialize(&flags.replacen("00000001", "100000000", 1)).is_err());
+        assert!(Flags::deserialize(&flags[10..]).is_err());
+    }
+
+    #[test]
+    fn fset_all_overwrites_every_flag() {
+        let mut flags = Flags::new();
+        flags.fset(7, 0, true);
+
+        flags.fset_all(7, 0b1010_0000);
+
+        assert_eq!(flags.fget(7), 0b1010_0000);
+        assert!(flags.fget_n(7, 7));
+        assert!(!flags.fget_n(7, 0));
+    }
+
+    #[test]
+    fn out_of_bounds_is_ignored() {
+        let mut flags = Flags::new();
+
+        assert_eq!(flags.fset(1000, 0, true), 0);
+        flags.fset_all(1000, 0xFF);
+        assert_eq!(flags.fget(1000), 0);
+        assert!(!flags.fget_n(1000, 0));
+
+        assert_eq!(flags.fset(0, 8, true), 0);
+        assert!(!flags.fget_n(0, 8));
+    }
+}
@@ -496,6 +496,88 @@ const LETTERS: [Character; 26] = [
 ];
 
 pub const FIRST_CHAR: char = ' ';
+/// Horizontal distance between characters, in pixels.
+pub const CHAR_WIDTH: i32 = 4;
+/// Vertical distance between lines of text, in pixels.
+pub const LINE_HEIGHT: i32 = 6;
+
+/// Width of pico8's wide characters (P8SCII `0x80` to `0x99`), in pixels.
+pub const WIDE_CHAR_WIDTH: i32 = 8;
+const FIRST_WIDE_CHAR: u32 = 0x80;
+
+/// The Unicode characters pico8 uses for its wide characters, in P8SCII order.
+const WIDE_CHAR_SYMBOLS: [char; 26] = [
+    '█', '▒', '🐱', '⬇', '░', '✽', '●', '♥', '☉', '웃', '⌂', '⬅', '😐', '♪', '🅾', '◆', '…', '➡',
+    '★', '⧗', '⬆', 'ˇ', '∧', '❎', '▤', '▥',
+];
+
+/// 7x5 bitmaps of the wide characters, a byte per row with the leftmost pixel in bit 6.
+const WIDE_CHARS: [[u8; 5]; 26] = [
+    // solid block
+    [0b1111111, 0b1111111, 0b1111111, 0b1111111, 0b1111111],
+    // checkerboard
+    [0b1010101, 0b0101010, 0b1010101, 0b0101010, 0b1010101],
+    // cat
+    [0b1000001, 0b1111111, 0b1011101, 0b1111111, 0b0111110],
+    // down arrow
+    [0b0011100, 0b0011100, 0b1111111, 0b0111110, 0b0001000],
+    // dots
+    [0b1000100, 0b0010001, 0b1000100, 0b0010001, 0b1000100],
+    // burst
+    [0b1001001, 0b0101010, 0b0011100, 0b0101010, 0b1001001],
+    // ball
+    [0b0111110, 0b1111111, 0b1111111, 0b1111111, 0b0111110],
+    // heart
+    [0b0110110, 0b1111111, 0b1111111, 0b0111110, 0b0001000],
+    // eye
+    [0b0111110, 0b1100011, 0b1101011, 0b1100011, 0b0111110],
+    // person
+    [0b0011100, 0b0011100, 0b1111111, 0b0011100, 0b0110110],
+    // house
+    [0b0001000, 0b0111110, 0b1111111, 0b0110110, 0b0110110],
+    // left arrow
+    [0b0010000, 0b0110000, 0b1111111, 0b0110000, 0b0010000],
+    // face
+    [0b1111111, 0b1011101, 0b1111111, 0b1000001, 0b1111111],
+    // note
+    [0b0001111, 0b0001001, 0b0001001, 0b0111011, 0b0111000],
+    // O button
+    [0b0111110, 0b1100011, 0b1100011, 0b1100011, 0b0111110],
+    // diamond
+    [0b0001000, 0b0011100, 0b0111110, 0b0011100, 0b0001000],
+    // ellipsis
+    [0b0000000, 0b0000000, 0b0000000, 0b0000000, 0b1001001],
+    // right arrow
+    [0b0000100, 0b0000110, 0b1111111, 0b0000110, 0b0000100],
+    // star
+    [0b0001000, 0b0011100, 0b1111111, 0b0011100, 0b0110110],
+    // hourglass
+    [0b1111111, 0b0111110, 0b0011100, 0b0111110, 0b1111111],
+    // up arrow
+    [0b0001000, 0b0111110, 0b1111111, 0b0011100, 0b0011100],
+    // down chevron
+    [0b0000000, 0b1000001, 0b0100010, 0b0010100, 0b0001000],
+    // up chevron
+    [0b0001000, 0b0010100, 0b0100010, 0b1000001, 0b0000000],
+    // X button
+    [0b0111110, 0b1101011, 0b1110111, 0b1101011, 0b0111110],
+    // horizontal stripes
+    [0b1111111, 0b0000000, 0b1111111, 0b0000000, 0b1111111],
+    // vertical stripes
+    [0b1010101, 0b1010101, 0b1010101, 0b1010101, 0b1010101],
+];
+
+/// The bitmap of one of pico8's wide characters, given either as its P8SCII code
+/// (`'\u{80}'` to `'\u{99}'`) or as the Unicode character pico8 shows for it (e.g. `'♥'`).
+pub(crate) fn wide_glyph(char: char) -> Option<&'static [u8; 5]> {
+    let index = (char as u32)
+        .checked_sub(FIRST_WIDE_CHAR)
+        .map(|index| index as usize)
+        .filter(|&index| index < WIDE_CHARS.len())
+        .or_else(|| WIDE_CHAR_SYMBOLS.iter().position(|&symbol| symbol == char))?;
+
+    WIDE_CHARS.get(index)
+}
 
 pub const FONT: [Character; SYMBOLS1.len() + NUMBERS.len() + SYMBOLS2.len() + LETTERS.len()] = {
     let arr = [[0; CHAR_SIZE]; SYMBOLS1.len() + NUMBERS.len() + SYMBOLS2.len() + LETTERS.len()];
@@ -520,3 +602,58 @@ const fn copy<const N: usize>(mut arr: [Character; N], arrays: &[&[Character]])
 
     arr
 }
+
+/// A font to print with instead of the built-in one, like pico8's custom font
+/// (see [`crate::Pico8::set_font`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CustomFont {
+    /// Horizontal distance between characters, in pixels.
+    pub char_width: i32,
+    /// Vertical distance between lines of text, in pixels.
+    pub line_height: i32,
+    /// The 8x8 bitmap of each character from `' '` to `'\x7f'`:
+    /// a byte per row, the lowest bit being the leftmost pixel.
+    pub glyphs: [[u8; 8]; CustomFont::CHAR_COUNT],
+}
+
+impl CustomFont {
+    pub const CHAR_COUNT: usize = 96;
+
+    /// A font with blank characters.
+    pub fn new(char_width: i32, line_height: i32) -> Self {
+        Self {
+            char_width,
+            line_height,
+            glyphs: [[0; 8]; Self::CHAR_COUNT],
+        }
+    }
+
+    /// Reads a font laid out like pico8's custom font memory (what carts `poke` at 0x5600):
+    /// the character width at byte 0, the line height at byte 2,
+    /// and then the 8 bytes of character `c` at `8 * c`.
+    pub fn from_pico8_memory(memory: &[u8]) -> Result<Self, String> {
+        let end = 8 * (FIRST_CHAR as usize + Self::CHAR_COUNT);
+        if memory.len() < end {
+            return Err(format!(
+                "Custom font is too small: expected at least {end} bytes, got {}",
+                memory.len()
+            ));
+        }
+
+        let mut font = Self::new(memory[0].into(), memory[2].into());
+        for (glyph, bytes) in font
+            .glyphs
+            .iter_mut()
+            .zip(memory[8 * FIRST_CHAR as usize..end].chunks_exact(8))
+        {
+            glyph.copy_from_slice(bytes);
+        }
+
+        Ok(font)
+    }
+
+    pub(crate) fn glyph(&self, char: char) -> Option<&[u8; 8]> {
+        self.glyphs
+            .get((char as usize).checked_sub(FIRST_CHAR as usize)?)
+    }
+}
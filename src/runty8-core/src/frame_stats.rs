@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+
+use crate::draw_data::{colors, DrawData};
+use crate::font;
+
+/// How many frames [`FrameStats`] averages over.
+const SAMPLES: usize = 30;
+
+/// Rolling averages of how long the last frames took, for `RunConfig::fps_overlay`.
+#[derive(Debug, Clone, Default)]
+pub struct FrameStats {
+    // Frame time and draw time of each frame, in milliseconds, oldest first.
+    samples: VecDeque<(f64, f64)>,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a frame that took `frame_millis` since the previous one,
+    /// `draw_millis` of which went into drawing it.
+    pub fn record(&mut self, frame_millis: f64, draw_millis: f64) {
+        if self.samples.len() == SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((frame_millis, draw_millis));
+    }
+
+    /// Average time between frames, in milliseconds.
+    pub fn frame_millis(&self) -> f64 {
+        self.average(|(frame, _)| frame)
+    }
+
+    /// Average time spent drawing a frame, in milliseconds.
+    pub fn draw_millis(&self) -> f64 {
+        self.average(|(_, draw)| draw)
+    }
+
+    /// Frames per second, from [`FrameStats::frame_millis`]. `0.0` before any frame.
+    pub fn fps(&self) -> f64 {
+        let frame_millis = self.frame_millis();
+
+        if frame_millis > 0.0 {
+            1000.0 / frame_millis
+        } else {
+            0.0
+        }
+    }
+
+    fn average(&self, value: impl Fn((f64, f64)) -> f64) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+
+        self.samples.iter().copied().map(value).sum::<f64>() / self.samples.len() as f64
+    }
+
+    /// Prints the averages in the top left corner, over a black box.
+    /// The cart's camera, clip, palettes and cursor are left as they were.
+    pub(crate) fn draw(&self, draw_data: &mut DrawData) {
+        let lines = [
+            format!("fps {:.0}", self.fps()),
+            format!("frame {:.1}ms", self.frame_millis()),
+            format!("draw {:.1}ms", self.draw_millis()),
+        ];
+        let width = lines.iter().map(|line| line.len()).max().unwrap_or(0) as i32 * 4;
+
+        draw_data.with_default_state(|draw_data| {
+            let height = lines.len() as i32 * font::LINE_HEIGHT;
+            draw_data.rectfill(0, 0, width, height, colors::BLACK);
+
+            for (y, line) in (0..).step_by(font::LINE_HEIGHT as usize).zip(&lines) {
+                draw_data.print(line, 1, y + 1, colors::WHITE);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrameStats, SAMPLES};
+
+    #[test]
+    fn averages_the_last_frames() {
+        let mut stats = FrameStats::new();
+        assert_eq!(stats.fps(), 0.0);
+
+        for _ in 0..SAMPLES {
+            stats.record(100.0, 50.0);
+        }
+        for _ in 0..SAMPLES {
+            stats.record(20.0, 5.0);
+        }
+        assert_eq!(stats.frame_millis(), 20.0);
+        assert_eq!(stats.draw_millis(), 5.0);
+        assert_eq!(stats.fps(), 50.0);
+
+        stats.record(50.0, 5.0);
+        assert_eq!(stats.frame_millis(), 21.0);
+    }
+}
@@ -1,80 +1,248 @@
-use crate::{Button, InputEvent, Key, KeyState, KeyboardEvent, MouseButton, MouseEvent};
+use std::collections::HashSet;
+
+use crate::{
+    Button, ButtonMap, GamepadAxis, GamepadEvent, InputEvent, KeyState, KeyboardEvent, MouseButton,
+    MouseEvent, PhysicalInput,
+};
+
+/// Stick deflection below which the stick is considered centered.
+pub const DEFAULT_GAMEPAD_DEADZONE: f32 = 0.25;
 
 #[derive(Debug)]
 pub struct Input {
-    pub(crate) left: Option<bool>,
-    pub(crate) right: Option<bool>,
-    pub(crate) up: Option<bool>,
-    pub(crate) down: Option<bool>,
-    pub(crate) x: Option<bool>,
-    pub(crate) c: Option<bool>,
+    // Physical inputs are tracked (rather than Pico8 buttons) so that
+    // releasing one binding doesn't release a button held through another.
+    pressed: HashSet<PhysicalInput>,
+    received_button_event: bool,
     pub mouse: Option<bool>,
+    // Like `stat(34)`: 1 for the left button, 2 for the right one and 4 for the middle one.
+    pub(crate) mouse_buttons: u8,
     pub mouse_x: i32,
     pub mouse_y: i32,
+    stick_x: f32,
+    stick_y: f32,
+    gamepad_deadzone: f32,
+    // Set by the first gamepad event: there are no connection events.
+    pub(crate) gamepad_used: bool,
+    button_map: ButtonMap,
 }
 
 #[allow(clippy::new_without_default)]
 impl Input {
     pub fn new() -> Self {
         Self {
-            left: None,
-            right: None,
-            up: None,
-            down: None,
-            x: None,
-            c: None,
+            pressed: HashSet::new(),
+            received_button_event: false,
             mouse: None,
+            mouse_buttons: 0,
             // TODO: Initialize mouse properly
             mouse_x: 64,
             mouse_y: 64,
+            stick_x: 0.0,
+            stick_y: 0.0,
+            gamepad_deadzone: DEFAULT_GAMEPAD_DEADZONE,
+            gamepad_used: false,
+            button_map: ButtonMap::default(),
         }
     }
 
+    /// Sets how far the stick has to move before it presses a direction.
+    pub fn set_gamepad_deadzone(&mut self, deadzone: f32) {
+        self.gamepad_deadzone = deadzone;
+    }
+
+    /// Sets which physical inputs press which buttons.
+    pub fn set_button_map(&mut self, button_map: ButtonMap) {
+        self.button_map = button_map;
+    }
+
     pub fn on_event(&mut self, event: InputEvent) {
         match event {
             InputEvent::Keyboard(KeyboardEvent { key, state }) => {
-                if let Some(button) = key_to_button(key) {
-                    let key_ref = self.button_to_ref(button);
-                    *key_ref = Some(state == KeyState::Down);
-                }
+                self.on_physical_input(key.into(), state);
             }
-            InputEvent::Mouse(MouseEvent::Button {
-                button: MouseButton::Left,
-                state,
-            }) => {
-                self.mouse = Some(state == KeyState::Down);
+            InputEvent::Mouse(MouseEvent::Button { button, state }) => {
+                let bit = match button {
+                    MouseButton::Left => 1,
+                    MouseButton::Right => 2,
+                    MouseButton::Middle => 4,
+                };
+                match state {
+                    KeyState::Down => self.mouse_buttons |= bit,
+                    KeyState::Up => self.mouse_buttons &= !bit,
+                }
+
+                if let MouseButton::Left = button {
+                    self.mouse = Some(state == KeyState::Down);
+                }
             }
             InputEvent::Mouse(MouseEvent::Move { x, y }) => {
                 self.mouse_x = x;
                 self.mouse_y = y;
             }
-            InputEvent::Mouse(MouseEvent::Button { .. }) => {
-                // Runty8 games currently can't access other mouse buttons
+            InputEvent::Mouse(MouseEvent::Wheel { .. }) => {}
+            InputEvent::Gamepad(GamepadEvent::Button { button, state }) => {
+                self.gamepad_used = true;
+                self.on_physical_input(button.into(), state);
+            }
+            InputEvent::Gamepad(GamepadEvent::Axis { axis, value }) => {
+                self.received_button_event = true;
+                self.gamepad_used = true;
+                match axis {
+                    GamepadAxis::LeftStickX => self.stick_x = value,
+                    GamepadAxis::LeftStickY => self.stick_y = value,
+                }
             }
         }
     }
 
-    fn button_to_ref(&mut self, button: Button) -> &mut Option<bool> {
-        match button {
-            Button::Cross => &mut self.x,
-            Button::Circle => &mut self.c,
-            Button::Left => &mut self.left,
-            Button::Right => &mut self.right,
-            Button::Up => &mut self.up,
-            Button::Down => &mut self.down,
-            Button::Mouse => &mut self.mouse,
+    fn on_physical_input(&mut self, input: PhysicalInput, state: KeyState) {
+        self.received_button_event = true;
+        match state {
+            KeyState::Down => self.pressed.insert(input),
+            KeyState::Up => self.pressed.remove(&input),
+        };
+    }
+
+    /// Current state of a button, combining all of its bindings.
+    ///
+    /// `None` if no button event has ever been received.
+    pub(crate) fn button(&self, button: Button) -> Option<bool> {
+        if button == Button::Mouse {
+            return self.mouse;
+        }
+        if !self.received_button_event {
+            return None;
         }
+
+        let bound = self
+            .button_map
+            .inputs(button)
+            .any(|input| self.pressed.contains(&input));
+
+        let deadzone = self.gamepad_deadzone;
+        let stick = match button {
+            Button::Left => self.stick_x < -deadzone,
+            Button::Right => self.stick_x > deadzone,
+            Button::Up => self.stick_y < -deadzone,
+            Button::Down => self.stick_y > deadzone,
+            Button::Cross | Button::Circle | Button::Mouse | Button::Pause => false,
+        };
+
+        Some(bound || stick)
     }
 }
 
-fn key_to_button(key: Key) -> Option<Button> {
-    match key {
-        Key::X | Key::Escape => Some(Button::Cross),
-        Key::C | Key::Enter => Some(Button::Circle),
-        Key::LeftArrow => Some(Button::Left),
-        Key::RightArrow => Some(Button::Right),
-        Key::UpArrow => Some(Button::Up),
-        Key::DownArrow => Some(Button::Down),
-        _ => None,
+#[cfg(test)]
+mod tests {
+    use super::Input;
+    use crate::{
+        Button, ButtonMap, GamepadAxis, GamepadButton, GamepadEvent, InputEvent, Key, KeyState,
+        KeyboardEvent, MouseButton, MouseEvent,
+    };
+
+    fn key(key: Key, state: KeyState) -> InputEvent {
+        InputEvent::Keyboard(KeyboardEvent { key, state })
+    }
+
+    fn gamepad_button(button: GamepadButton, state: KeyState) -> InputEvent {
+        InputEvent::Gamepad(GamepadEvent::Button { button, state })
+    }
+
+    fn stick_x(value: f32) -> InputEvent {
+        InputEvent::Gamepad(GamepadEvent::Axis {
+            axis: GamepadAxis::LeftStickX,
+            value,
+        })
+    }
+
+    #[test]
+    fn face_buttons_map_to_circle_and_cross() {
+        let mut input = Input::new();
+        assert_eq!(input.button(Button::Circle), None);
+
+        input.on_event(gamepad_button(GamepadButton::South, KeyState::Down));
+        input.on_event(gamepad_button(GamepadButton::East, KeyState::Down));
+
+        assert_eq!(input.button(Button::Circle), Some(true));
+        assert_eq!(input.button(Button::Cross), Some(true));
+    }
+
+    #[test]
+    fn stick_respects_deadzone() {
+        let mut input = Input::new();
+        input.set_gamepad_deadzone(0.5);
+
+        input.on_event(stick_x(-0.4));
+        assert_eq!(input.button(Button::Left), Some(false));
+
+        input.on_event(stick_x(-0.6));
+        assert_eq!(input.button(Button::Left), Some(true));
+        assert_eq!(input.button(Button::Right), Some(false));
+    }
+
+    #[test]
+    fn dpad_and_stick_both_drive_directions() {
+        let mut input = Input::new();
+
+        input.on_event(gamepad_button(GamepadButton::DPadRight, KeyState::Down));
+        input.on_event(stick_x(0.0));
+        assert_eq!(input.button(Button::Right), Some(true));
+
+        input.on_event(gamepad_button(GamepadButton::DPadRight, KeyState::Up));
+        input.on_event(stick_x(1.0));
+        assert_eq!(input.button(Button::Right), Some(true));
+    }
+
+    #[test]
+    fn releasing_gamepad_keeps_keyboard_held() {
+        let mut input = Input::new();
+
+        input.on_event(key(Key::LeftArrow, KeyState::Down));
+        input.on_event(gamepad_button(GamepadButton::DPadLeft, KeyState::Down));
+        input.on_event(gamepad_button(GamepadButton::DPadLeft, KeyState::Up));
+
+        assert_eq!(input.button(Button::Left), Some(true));
+    }
+
+    #[test]
+    fn button_map_replaces_default_keys() {
+        let mut input = Input::new();
+        input.set_button_map(ButtonMap::empty().bind(Key::Z, Button::Circle));
+
+        input.on_event(key(Key::C, KeyState::Down));
+        assert_eq!(input.button(Button::Circle), Some(false));
+
+        input.on_event(key(Key::Z, KeyState::Down));
+        assert_eq!(input.button(Button::Circle), Some(true));
+    }
+
+    #[test]
+    fn button_stays_pressed_until_every_binding_is_released() {
+        let mut input = Input::new();
+
+        input.on_event(key(Key::X, KeyState::Down));
+        input.on_event(key(Key::Escape, KeyState::Down));
+        input.on_event(key(Key::X, KeyState::Up));
+        assert_eq!(input.button(Button::Cross), Some(true));
+
+        input.on_event(key(Key::Escape, KeyState::Up));
+        assert_eq!(input.button(Button::Cross), Some(false));
+    }
+
+    #[test]
+    fn mouse_buttons_are_a_bitmask() {
+        let mut input = Input::new();
+        let mouse = |button, state| InputEvent::Mouse(MouseEvent::Button { button, state });
+
+        input.on_event(mouse(MouseButton::Left, KeyState::Down));
+        input.on_event(mouse(MouseButton::Middle, KeyState::Down));
+        assert_eq!(input.mouse_buttons, 0b101);
+        assert_eq!(input.button(Button::Mouse), Some(true));
+
+        input.on_event(mouse(MouseButton::Left, KeyState::Up));
+        input.on_event(mouse(MouseButton::Right, KeyState::Down));
+        assert_eq!(input.mouse_buttons, 0b110);
+        assert_eq!(input.button(Button::Mouse), Some(false));
     }
 }
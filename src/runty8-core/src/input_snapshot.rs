@@ -0,0 +1,57 @@
+use crate::draw_data::{colors, DrawData};
+use crate::Buttons;
+
+/// What the player is doing with the controls, for debugging input mappings,
+/// see [`crate::Pico8::input_snapshot`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InputSnapshot {
+    /// The buttons `btn` is true for.
+    pub held: Buttons,
+    /// The buttons `btnp` is true for.
+    pub pressed: Buttons,
+    /// The mouse position, in screen pixels.
+    pub mouse: (i32, i32),
+    /// Whether any gamepad event has been received, which is the closest thing
+    /// to a connection status that's available.
+    pub gamepad: bool,
+}
+
+impl InputSnapshot {
+    /// Draws a square per button in the bottom left corner (in `btn()` bit order, then pause
+    /// and the mouse), white while held and red when `btnp` fires, then a green square
+    /// if there's a gamepad. A pink cross marks the mouse.
+    pub(crate) fn draw(&self, draw_data: &mut DrawData) {
+        let (_, height) = draw_data.size();
+        let y = height as i32 - 5;
+        let squares = Buttons::ORDER.len() as i32 + 1;
+
+        draw_data.with_default_state(|draw_data| {
+            draw_data.rectfill(0, y - 1, 4 * squares, y + 3, colors::BLACK);
+
+            let colors = Buttons::ORDER
+                .iter()
+                .map(|&button| {
+                    if self.pressed.held(button) {
+                        colors::RED
+                    } else if self.held.held(button) {
+                        colors::WHITE
+                    } else {
+                        colors::DARK_GREY
+                    }
+                })
+                .chain([if self.gamepad {
+                    colors::GREEN
+                } else {
+                    colors::DARK_GREY
+                }]);
+            for (index, color) in (0..).zip(colors) {
+                let x = 1 + 4 * index;
+                draw_data.rectfill(x, y, x + 2, y + 2, color);
+            }
+
+            let (x, y) = self.mouse;
+            draw_data.line(x - 1, y, x + 1, y, colors::PINK);
+            draw_data.line(x, y - 1, x, y + 1, colors::PINK);
+        });
+    }
+}
@@ -2,33 +2,70 @@
 
 //! Types and functions required to run a Runty8 game.
 
+mod audio;
+mod button_map;
+mod cart_data;
+mod clock;
 mod draw_data;
 mod flags;
+mod frame_stats;
 mod input;
+mod input_snapshot;
 mod map;
+mod memory;
+mod music;
+mod num;
+mod p8;
+mod pause_menu;
 mod pico8;
+mod prng;
+mod replay;
+mod resource_loader;
+mod run_config;
 pub mod serialize;
+mod sfx;
 mod sprite_sheet;
 mod state;
+mod text;
+#[cfg(all(feature = "parallel-map", not(target_arch = "wasm32")))]
+mod thread_pool;
+mod timestep;
 pub(crate) mod util;
-pub use draw_data::colors;
+pub use draw_data::{colors, COLORS, EXTENDED_COLORS};
 
 pub mod draw;
 pub mod font;
 
+pub use audio::{Audio, CHANNELS, SAMPLE_RATE};
+pub use button_map::{ButtonMap, PhysicalInput};
 pub use draw_data::DrawData;
 pub use flags::Flags;
-pub use input::Input;
+pub use frame_stats::FrameStats;
+pub use input::{Input, DEFAULT_GAMEPAD_DEADZONE};
+pub use input_snapshot::InputSnapshot;
 pub use map::Map;
+pub use music::{Music, Pattern};
+pub use num::P8Num;
 pub use pico8::*;
+pub use replay::{Buttons, Replay};
+pub use resource_loader::{BytesLoader, FileLoader, ResourceLoader};
+pub use run_config::{
+    DisplayFilter, FramePacing, RunConfig, ScalingMode, ScreenshotScale, WindowIcon, WindowMode,
+};
 use serialize::{Serialize, Serialized};
+pub use sfx::{Effect, Instrument, Note, Sfx, Sound};
 pub use sprite_sheet::{Sprite, SpriteSheet};
+pub use timestep::{Steps, Timestep};
 
 /// A regular pico8 app.
 pub trait App {
     fn init(pico8: &mut Pico8) -> Self;
     fn update(&mut self, pico8: &mut Pico8);
     fn draw(&mut self, pico8: &mut Pico8);
+
+    /// Called once when the game is closing, before the window goes away.
+    /// E.g. for saving progress that isn't saved as it happens.
+    fn on_exit(&mut self, _pico8: &mut Pico8) {}
 }
 
 /// A pico8 color.
@@ -37,7 +74,7 @@ pub trait App {
 pub type Color = u8; // Actually a u4
 
 /// Pico8's supported input buttons.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Button {
     /// Left arrow.
     Left,
@@ -53,9 +90,11 @@ pub enum Button {
     Circle,
     /// Left mouse button.
     Mouse,
+    /// Opens the pause menu (P, or Start on gamepads).
+    Pause,
 }
 
-/// Game assets: sprite sheet, map, flags.
+/// Game assets: sprite sheet, map, flags, sound effects and music.
 // TODO: Rename to assets?
 #[derive(Debug)]
 pub struct Resources {
@@ -63,9 +102,98 @@ pub struct Resources {
     pub sprite_sheet: SpriteSheet,
     pub sprite_flags: Flags,
     pub map: Map,
+    pub sfx: Sfx,
+    pub music: Music,
 }
 
 impl Resources {
+    /// Loads the sprite sheet, flags, map, sound effects and music from a pico8 `.p8` cart.
+    ///
+    /// Assets will be saved next to the cart.
+    pub fn from_p8(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| format!("Couldn't read cart {path}: {error}"))?;
+
+        Self::from_p8_contents(parent_dir(path), &contents)
+    }
+
+    /// Like [`Resources::from_p8`], for a cart that's already in memory.
+    pub fn from_p8_contents(assets_path: String, contents: &str) -> Result<Self, String> {
+        Ok(Self::from_cart(assets_path, p8::parse(contents)?))
+    }
+
+    /// Loads the sprite sheet, flags, map, sound effects and music from a pico8 `.p8.png` cart
+    /// (the shareable image kind).
+    ///
+    /// Assets will be saved next to the cart.
+    pub fn from_p8_png(path: &str) -> Result<Self, String> {
+        let contents =
+            std::fs::read(path).map_err(|error| format!("Couldn't read cart {path}: {error}"))?;
+
+        Ok(Self::from_cart(parent_dir(path), p8::parse_png(&contents)?))
+    }
+
+    /// Loads the sprite sheet, flags, map, sound effects and music from the files in `assets_path`
+    /// while the game runs, unlike [`load_assets!`] which embeds them in the binary.
+    /// On the web they're loaded from local storage instead.
+    ///
+    /// Missing files get blank assets, malformed ones an error naming the file.
+    pub fn from_dir(assets_path: &str) -> Result<Self, String> {
+        Self::from_loader(assets_path, &FileLoader::new(assets_path))
+    }
+
+    /// Loads a cart from `path`, in whichever format it's in: a `.p8` or `.p8.png` cart,
+    /// or else a directory of asset files (see [`Resources::from_dir`]).
+    pub fn from_path(path: &str) -> Result<Self, String> {
+        if path.ends_with(".p8.png") {
+            Self::from_p8_png(path)
+        } else if path.ends_with(".p8") {
+            Self::from_p8(path)
+        } else {
+            Self::from_dir(path)
+        }
+    }
+
+    /// An empty sprite sheet, flags, map, sound effects and music, saved to `assets_path`.
+    pub fn blank(assets_path: &str) -> Self {
+        Self {
+            assets_path: assets_path.to_owned(),
+            sprite_sheet: SpriteSheet::new(),
+            sprite_flags: Flags::new(),
+            map: Map::new(),
+            sfx: Sfx::new(),
+            music: Music::new(),
+        }
+    }
+
+    fn from_cart(assets_path: String, cart: p8::Cart) -> Self {
+        Self {
+            assets_path,
+            sprite_sheet: cart.sprite_sheet,
+            sprite_flags: cart.flags,
+            map: cart.map,
+            sfx: cart.sfx,
+            music: cart.music,
+        }
+    }
+
+    /// Saves the sprite sheet, flags, map, sound effects and music as a pico8 `.p8` cart.
+    pub fn to_p8(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_p8_contents())
+            .map_err(|error| format!("Couldn't write cart {path}: {error}"))
+    }
+
+    /// Like [`Resources::to_p8`], returning the cart's contents instead of writing them.
+    pub fn to_p8_contents(&self) -> String {
+        p8::serialize(
+            &self.sprite_sheet,
+            &self.sprite_flags,
+            &self.map,
+            &self.sfx,
+            &self.music,
+        )
+    }
+
     pub fn serialize(&self) -> Vec<Serialized> {
         vec![
             Serialized {
@@ -80,10 +208,29 @@ impl Resources {
                 file_name: Flags::file_name(),
                 serialized: self.sprite_flags.serialize(),
             },
+            Serialized {
+                file_name: Sfx::file_name(),
+                serialized: self.sfx.serialize(),
+            },
+            Serialized {
+                file_name: Music::file_name(),
+                serialized: self.music.serialize(),
+            },
         ]
     }
 }
 
+/// The directory `path` is in, `"."` for a bare file name.
+fn parent_dir(path: &str) -> String {
+    std::path::Path::new(path)
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .map_or_else(
+            || ".".to_owned(),
+            |parent| parent.to_string_lossy().into_owned(),
+        )
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 /// Key state: up or down.
 pub enum KeyState {
@@ -93,76 +240,124 @@ pub enum KeyState {
 /// Keyboard keys.
 #[derive(Clone, Copy, Debug, PartialEq, Hash, Eq)]
 pub enum Key {
-    ///
+    /// A key.
     A,
-    ///
+    /// B key.
     B,
-    ///
+    /// C key.
     C,
-    ///
+    /// D key.
     D,
-    ///
+    /// E key.
     E,
-    ///
+    /// F key.
     F,
-    ///
+    /// G key.
     G,
-    ///
+    /// H key.
     H,
-    ///
+    /// I key.
     I,
-    ///
+    /// J key.
     J,
-    ///
+    /// K key.
     K,
-    ///
+    /// L key.
     L,
-    ///
+    /// M key.
     M,
-    ///
+    /// N key.
     N,
-    ///
+    /// O key.
     O,
-    ///
+    /// P key.
     P,
-    ///
+    /// Q key.
     Q,
-    ///
+    /// R key.
     R,
-    ///
+    /// S key.
     S,
-    ///
+    /// T key.
     T,
-    ///
+    /// U key.
     U,
-    ///
+    /// V key.
     V,
-    ///
+    /// W key.
     W,
-    ///
+    /// X key.
     X,
-    ///
+    /// Y key.
     Y,
-    ///
+    /// Z key.
     Z,
-    ///
+    /// 0 key.
+    Digit0,
+    /// 1 key.
+    Digit1,
+    /// 2 key.
+    Digit2,
+    /// 3 key.
+    Digit3,
+    /// 4 key.
+    Digit4,
+    /// 5 key.
+    Digit5,
+    /// 6 key.
+    Digit6,
+    /// 7 key.
+    Digit7,
+    /// 8 key.
+    Digit8,
+    /// 9 key.
+    Digit9,
+    /// Left control key.
     Control,
-    ///
+    /// Left arrow key.
     LeftArrow,
-    ///
+    /// Right arrow key.
     RightArrow,
-    ///
+    /// Up arrow key.
     UpArrow,
-    ///
+    /// Down arrow key.
     DownArrow,
-    ///
+    /// Escape key.
     Escape,
-    ///
+    /// Left alt key.
     Alt,
-    ///
+    /// Space key.
     Space,
-    ///
+    /// Enter key.
     Enter,
+    /// Backspace key.
+    Backspace,
+    /// Tab key.
+    Tab,
+    /// F1 key.
+    F1,
+    /// F2 key.
+    F2,
+    /// F3 key.
+    F3,
+    /// F4 key.
+    F4,
+    /// F5 key.
+    F5,
+    /// F6 key.
+    F6,
+    /// F7 key.
+    F7,
+    /// F8 key.
+    F8,
+    /// F9 key.
+    F9,
+    /// F10 key.
+    F10,
+    /// F11 key.
+    F11,
+    /// F12 key.
+    F12,
 }
 
 /// Keyboard event (key up/down).
@@ -174,13 +369,72 @@ pub struct KeyboardEvent {
     pub state: KeyState,
 }
 
-/// Input events (mouse/keyboard).
+/// Input events (mouse/keyboard/gamepad).
 #[derive(Clone, Copy, Debug)]
 pub enum InputEvent {
     /// Keyboard event
     Keyboard(KeyboardEvent),
     /// Mouse event
     Mouse(MouseEvent),
+    /// Gamepad event
+    Gamepad(GamepadEvent),
+}
+
+/// Gamepad buttons, named after their position on the controller.
+///
+/// By default, [`GamepadButton::South`] (A on the Steam Deck and Xbox controllers)
+/// maps to [`Button::Circle`] (O), and [`GamepadButton::East`] (B) maps to [`Button::Cross`] (X).
+/// See [`ButtonMap`] to change this.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    /// Bottom face button (A).
+    South,
+    /// Right face button (B).
+    East,
+    /// Left face button (X).
+    West,
+    /// Top face button (Y).
+    North,
+    /// D-pad left.
+    DPadLeft,
+    /// D-pad right.
+    DPadRight,
+    /// D-pad up.
+    DPadUp,
+    /// D-pad down.
+    DPadDown,
+    /// Start (or menu) button.
+    Start,
+    /// Select (or view) button.
+    Select,
+}
+
+/// Gamepad axes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    /// Left stick, horizontal. Negative is left.
+    LeftStickX,
+    /// Left stick, vertical. Negative is up.
+    LeftStickY,
+}
+
+/// Gamepad events (button presses, stick movement).
+#[derive(Clone, Copy, Debug)]
+pub enum GamepadEvent {
+    /// Gamepad button pressed/released.
+    Button {
+        /// Gamepad button that was pressed or released.
+        button: GamepadButton,
+        /// Whether the button was pressed or released.
+        state: KeyState,
+    },
+    /// Gamepad axis moved.
+    Axis {
+        /// Axis that moved.
+        axis: GamepadAxis,
+        /// New position of the axis, in the range `-1.0..=1.0`.
+        value: f32,
+    },
 }
 
 /// Mouse buttons.
@@ -200,9 +454,9 @@ pub enum MouseEvent {
     /// Mouse move event.
     // Contains the current position of the mouse.
     Move {
-        ///
+        /// Horizontal position, in screen pixels.
         x: i32,
-        ///
+        /// Vertical position, in screen pixels.
         y: i32,
     },
     /// Mouse button pressed/released.
@@ -212,19 +466,25 @@ pub enum MouseEvent {
         /// Whether the button was pressed or released.
         state: KeyState,
     },
+    /// Mouse wheel scrolled.
+    Wheel {
+        /// Lines scrolled: positive is up (away from the user), negative is down.
+        y: i32,
+    },
 }
 
 /// Runty8 events (input, tick, etc).
 #[derive(Clone, Copy, Debug)]
 pub enum Event {
-    ///
+    /// Input event (keyboard, mouse).
     Input(InputEvent),
-    ///
+    /// A frame has passed.
     Tick {
         /// How much time passed since the last [`Event::Tick`], in milliseconds.
         delta_millis: f64,
     },
-    // TODO: Remove this
+    /// The window is closing, or the event loop is ending for another reason
+    /// (like losing the graphics context). Can arrive more than once.
     WindowClosed,
 }
 
@@ -252,8 +512,7 @@ macro_rules! include_assets {
 pub use include_dir;
 #[doc(hidden)]
 pub use paste::paste;
-/// Embed game assets in your binary
-
+/// Deserialize an asset from its file contents, or create a blank one if the file is missing.
 pub fn create_asset<T: Default>(
     deserialize: fn(&str) -> Result<T, String>,
     asset_name: &str,
@@ -333,6 +592,9 @@ macro_rules! load_assets {
                 $crate::load_file(&DIR, &assets_path, &$crate::Flags::file_name())?;
             let sprite_sheet_contents =
                 $crate::load_file(&DIR, &assets_path, &$crate::SpriteSheet::file_name())?;
+            let sfx_contents = $crate::load_file(&DIR, &assets_path, &$crate::Sfx::file_name())?;
+            let music_contents =
+                $crate::load_file(&DIR, &assets_path, &$crate::Music::file_name())?;
 
             let map =
                 $crate::create_asset($crate::Map::deserialize, "map", map_contents.as_deref())?;
@@ -349,10 +611,21 @@ macro_rules! load_assets {
                 sprite_sheet_contents.as_deref(),
             )?;
 
+            let sfx =
+                $crate::create_asset($crate::Sfx::deserialize, "sfx", sfx_contents.as_deref())?;
+
+            let music = $crate::create_asset(
+                $crate::Music::deserialize,
+                "music",
+                music_contents.as_deref(),
+            )?;
+
             Ok::<$crate::Resources, String>($crate::Resources {
                 map,
                 sprite_flags,
                 sprite_sheet,
+                sfx,
+                music,
                 assets_path,
             })
         })()
@@ -381,3 +654,62 @@ pub mod wasm {
         console_log::init_with_level(log::Level::Debug).unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Map, Resources};
+    use crate::serialize::Serialize;
+    use std::path::PathBuf;
+
+    fn empty_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn from_dir_reads_saved_assets() {
+        let dir = empty_dir("runty8-from-dir-test");
+        let mut map = Map::new();
+        map.mset(4, 5, 6);
+        std::fs::write(dir.join(Map::file_name()), map.serialize()).unwrap();
+
+        let resources = Resources::from_dir(dir.to_str().unwrap()).unwrap();
+        assert_eq!(resources.map.mget(4, 5), 6);
+        // Missing files get blank assets.
+        assert_eq!(resources.sprite_flags.fget(0), 0);
+    }
+
+    #[test]
+    fn from_path_picks_the_format() {
+        let dir = empty_dir("runty8-from-path-test");
+        let mut resources = Resources::blank(dir.to_str().unwrap());
+        resources.map.mset(1, 2, 3);
+        let cart = dir.join("cart.p8");
+        resources.to_p8(cart.to_str().unwrap()).unwrap();
+
+        let loaded = Resources::from_path(cart.to_str().unwrap()).unwrap();
+        assert_eq!(loaded.map.mget(1, 2), 3);
+        // The cart's directory has no asset files.
+        let loaded = Resources::from_path(dir.to_str().unwrap()).unwrap();
+        assert_eq!(loaded.map.mget(1, 2), 0);
+    }
+
+    #[test]
+    fn bare_file_names_are_in_the_current_directory() {
+        assert_eq!(super::parent_dir("game.p8"), ".");
+        assert_eq!(super::parent_dir("carts/game.p8"), "carts");
+        assert_eq!(super::parent_dir("/game.p8"), "/");
+    }
+
+    #[test]
+    fn from_dir_rejects_malformed_files() {
+        let dir = empty_dir("runty8-from-dir-malformed-test");
+        std::fs::write(dir.join(Map::file_name()), "not a map").unwrap();
+
+        let error = Resources::from_dir(dir.to_str().unwrap()).unwrap_err();
+        assert!(error.contains(&Map::file_name()), "{error}");
+    }
+}
@@ -6,10 +6,27 @@ use itertools::Itertools;
 type SpriteId = u8;
 
 /// A pico8 game's sprite map.
+///
+/// Maps can be built or modified before handing them to [`crate::Resources`],
+/// e.g. to generate levels procedurally:
+///
+/// ```
+/// use runty8_core::Map;
+///
+/// let mut map = Map::new();
+/// for x in 0..Map::WIDTH_SPRITES as i32 {
+///     map.mset(x, 10, 3);
+/// }
+///
+/// assert_eq!(map.mget(5, 10), 3);
+/// // Like in pico8, reading outside the map returns 0.
+/// assert_eq!(map.mget(-1, 10), 0);
+/// ```
 #[derive(Debug, Clone)]
 pub struct Map {
-    // Don't really want the size to change
-    pub(crate) map: [SpriteId; Self::MAP_SIZE],
+    pub(crate) map: Vec<SpriteId>,
+    width_sprites: usize,
+    height_sprites: usize,
 }
 
 impl Map {
@@ -26,42 +43,103 @@ impl Map {
     const SPRITES_PER_SCREEN_ROW: usize = Self::SCREEN_SIZE_PIXELS / Sprite::WIDTH;
     pub const WIDTH_SPRITES: usize = Self::SCREENS_WIDTH * Self::SPRITES_PER_SCREEN_ROW;
     pub const HEIGHT_SPRITES: usize = Self::SCREENS_HEIGHT * Self::SPRITES_PER_SCREEN_ROW;
-    const MAP_SIZE: usize = Self::WIDTH_SPRITES * Self::HEIGHT_SPRITES;
 
     // TODO: Make pub(crate)
     pub fn new() -> Self {
-        let mut map = [0; Self::MAP_SIZE];
+        let mut map = Self::with_size(Self::WIDTH_SPRITES, Self::HEIGHT_SPRITES);
 
-        map[0] = 1;
-        map[1] = 1;
-        map[2] = 1;
+        map.map[0] = 1;
+        map.map[1] = 1;
+        map.map[2] = 1;
 
-        Map { map }
+        map
     }
 
+    /// An empty map of `width_sprites` by `height_sprites` cells.
+    ///
+    /// Pico8's own maps are [`Map::WIDTH_SPRITES`] by [`Map::HEIGHT_SPRITES`],
+    /// but bigger games can use bigger maps.
+    pub fn with_size(width_sprites: usize, height_sprites: usize) -> Self {
+        Self {
+            map: vec![0; width_sprites * height_sprites],
+            width_sprites,
+            height_sprites,
+        }
+    }
+
+    /// Width of the map, in cells.
+    pub fn width(&self) -> usize {
+        self.width_sprites
+    }
+
+    /// Height of the map, in cells.
+    pub fn height(&self) -> usize {
+        self.height_sprites
+    }
+
+    /// Every cell in the map as `(cel_x, cel_y, sprite)`, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, u8)> + '_ {
+        let width = self.width_sprites;
+
+        self.map
+            .iter()
+            .enumerate()
+            .map(move |(index, &sprite)| (index % width, index / width, sprite))
+    }
+
+    /// The cells of the `w` by `h` rectangle at (`cel_x`, `cel_y`) as `(cel_x, cel_y, sprite)`,
+    /// row by row, e.g. for the cells a [`crate::Pico8::map`] call draws.
+    ///
+    /// The parts of the rectangle outside the map are left out, and so are empty cells
+    /// (sprite 0) with `skip_empty`.
+    pub fn region(
+        &self,
+        cel_x: i32,
+        cel_y: i32,
+        w: i32,
+        h: i32,
+        skip_empty: bool,
+    ) -> impl Iterator<Item = (usize, usize, u8)> + '_ {
+        // The part of `start..start + len` that's in `0..size`.
+        let overlap = |start: i32, len: i32, size: usize| {
+            let size = i32::try_from(size).unwrap_or(i32::MAX);
+            let end = start.saturating_add(len.max(0)).clamp(0, size);
+
+            (start.clamp(0, end) as usize)..(end as usize)
+        };
+        let columns = overlap(cel_x, w, self.width_sprites);
+        let rows = overlap(cel_y, h, self.height_sprites);
+
+        rows.flat_map(move |y| columns.clone().map(move |x| (x, y)))
+            .map(|(x, y)| (x, y, self.map[x + y * self.width_sprites]))
+            .filter(move |&(_, _, sprite)| !skip_empty || sprite != 0)
+    }
+
+    /// Sprite at the given cell.
+    ///
+    /// Like pico8, returns 0 outside of the map.
     pub fn mget(&self, cel_x: i32, cel_y: i32) -> u8 {
-        let index = Self::index(cel_x, cel_y);
+        let index = self.index(cel_x, cel_y);
 
-        // TODO: Handle like pico8
-        // TODO2: I think it returns 0 if outside bounds?
         index.map(|index| self.map[index]).unwrap_or(0)
     }
 
-    pub fn mset(&mut self, cel_x: usize, cel_y: usize, sprite: u8) {
-        let index = cel_x + cel_y * Map::WIDTH_SPRITES;
-        // TODO: Handle like pico8
-        assert!(index <= self.map.len());
-
-        self.map[index] = sprite;
+    /// Sets the sprite at the given cell.
+    ///
+    /// Like pico8, does nothing outside of the map.
+    pub fn mset(&mut self, cel_x: i32, cel_y: i32, sprite: u8) {
+        if let Some(index) = self.index(cel_x, cel_y) {
+            self.map[index] = sprite;
+        }
     }
 
-    fn index(x: i32, y: i32) -> Option<usize> {
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
         if x >= 0
-            && (x as usize) < Map::WIDTH_SPRITES
+            && (x as usize) < self.width_sprites
             && y >= 0
-            && (y as usize) < Map::HEIGHT_SPRITES
+            && (y as usize) < self.height_sprites
         {
-            Some(x as usize + y as usize * Map::WIDTH_SPRITES)
+            Some(x as usize + y as usize * self.width_sprites)
         } else {
             None
         }
@@ -69,25 +147,46 @@ impl Map {
 }
 
 impl Map {
-    // TODO: Make sure this works
+    /// Reads a map serialized with [`Serialize::serialize`]: one line per row of the map.
     pub fn deserialize(str: &str) -> Result<Self, String> {
-        let map: [SpriteId; Self::MAP_SIZE] = str
-            .split_ascii_whitespace()
-            .map(|num| u8::from_str_radix(num, 16).unwrap())
-            .collect::<Vec<_>>()
-            .try_into()
-            .map_err(|error: Vec<u8>| format!("Error deserializing map {}", error.len()))?;
+        let rows = str
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.split_ascii_whitespace()
+                    .map(|num| {
+                        u8::from_str_radix(num, 16)
+                            .map_err(|error| format!("Error deserializing map: {error}"))
+                    })
+                    .collect::<Result<Vec<SpriteId>, String>>()
+            })
+            .collect::<Result<Vec<_>, String>>()?;
 
-        Ok(Self { map })
+        let width_sprites = rows.first().map(Vec::len).unwrap_or(0);
+        let height_sprites = rows.len();
+        if width_sprites == 0 {
+            return Err("Error deserializing map: the map is empty".to_owned());
+        }
+        if let Some(row) = rows.iter().find(|row| row.len() != width_sprites) {
+            return Err(format!(
+                "Error deserializing map: expected rows of {width_sprites} sprites, found {}",
+                row.len()
+            ));
+        }
+
+        Ok(Self {
+            map: rows.concat(),
+            width_sprites,
+            height_sprites,
+        })
     }
 }
 
 impl Serialize for Map {
-    // TODO: Make sure this works
     fn serialize(&self) -> String {
         self.map
             .iter()
-            .chunks(Map::WIDTH_SPRITES)
+            .chunks(self.width_sprites.max(1))
             .into_iter()
             .map(|chunk| chunk.map(|n| format!("{n:0>2X}")).join(" "))
             .join("\n")
@@ -99,3 +198,151 @@ impl Default for Map {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Map;
+    use crate::serialize::Serialize;
+
+    #[test]
+    fn mget_outside_the_map_is_zero() {
+        let map = Map::new();
+        let (width, height) = (Map::WIDTH_SPRITES as i32, Map::HEIGHT_SPRITES as i32);
+
+        assert_eq!(map.mget(0, 0), 1);
+        for (x, y) in [
+            (-1, 0),
+            (0, -1),
+            (width, 0),
+            (0, height),
+            (i32::MIN, i32::MAX),
+        ] {
+            assert_eq!(map.mget(x, y), 0);
+        }
+    }
+
+    #[test]
+    fn mset_outside_the_map_is_ignored() {
+        let mut map = Map::new();
+        let before = map.map.clone();
+        let (width, height) = (Map::WIDTH_SPRITES as i32, Map::HEIGHT_SPRITES as i32);
+
+        for (x, y) in [(-1, 0), (width, 0), (0, height), (width - 1, height)] {
+            map.mset(x, y, 42);
+        }
+
+        assert_eq!(map.map, before);
+    }
+
+    #[test]
+    fn mset_last_cell() {
+        let mut map = Map::new();
+        let (width, height) = (Map::WIDTH_SPRITES as i32, Map::HEIGHT_SPRITES as i32);
+
+        map.mset(width - 1, height - 1, 42);
+
+        assert_eq!(map.mget(width - 1, height - 1), 42);
+        assert_eq!(map.map[map.map.len() - 1], 42);
+    }
+
+    #[test]
+    fn with_size_respects_dimensions() {
+        let mut map = Map::with_size(300, 10);
+
+        map.mset(299, 9, 7);
+        map.mset(300, 0, 7);
+
+        assert_eq!(map.mget(299, 9), 7);
+        assert_eq!(map.mget(300, 0), 0);
+        assert_eq!(map.iter().filter(|&(_, _, sprite)| sprite == 7).count(), 1);
+        assert_eq!(map.iter().last(), Some((299, 9, 7)));
+    }
+
+    #[test]
+    fn serialization_round_trips_custom_sizes() {
+        let mut map = Map::with_size(3, 2);
+        map.mset(2, 1, 0xAB);
+
+        let serialized = map.serialize();
+        assert_eq!(serialized, "00 00 00\n00 00 AB");
+
+        let deserialized = Map::deserialize(&serialized).unwrap();
+        assert_eq!((deserialized.width(), deserialized.height()), (3, 2));
+        assert_eq!(deserialized.map, map.map);
+    }
+
+    #[test]
+    fn every_sprite_id_round_trips() {
+        let mut map = Map::with_size(16, 16);
+        for (index, cell) in map.map.iter_mut().enumerate() {
+            *cell = index as u8;
+        }
+
+        let deserialized = Map::deserialize(&map.serialize()).unwrap();
+
+        assert_eq!(deserialized.map, map.map);
+    }
+
+    #[test]
+    fn random_maps_round_trip() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(8);
+        for _ in 0..20 {
+            let mut map = Map::new();
+            rng.fill(&mut map.map[..]);
+
+            let deserialized = Map::deserialize(&map.serialize()).unwrap();
+
+            assert_eq!(deserialized.map, map.map);
+            assert_eq!(
+                (deserialized.width(), deserialized.height()),
+                (Map::WIDTH_SPRITES, Map::HEIGHT_SPRITES)
+            );
+        }
+    }
+
+    #[test]
+    fn region_yields_the_cells_in_a_rectangle() {
+        let mut map = Map::with_size(8, 6);
+        map.mset(2, 1, 4);
+        map.mset(3, 2, 5);
+        map.mset(7, 5, 6);
+
+        let cells: Vec<_> = map.region(2, 1, 2, 2, true).collect();
+        assert_eq!(cells, [(2, 1, 4), (3, 2, 5)]);
+        assert_eq!(
+            map.region(2, 1, 2, 2, false).collect::<Vec<_>>(),
+            [(2, 1, 4), (3, 1, 0), (2, 2, 0), (3, 2, 5)]
+        );
+
+        // Clipped to the map.
+        let cells: Vec<_> = map.region(6, 4, 10, 10, false).collect();
+        assert_eq!(cells, [(6, 4, 0), (7, 4, 0), (6, 5, 0), (7, 5, 6)]);
+        assert_eq!(map.region(-3, -3, 4, 3, false).count(), 0);
+        assert_eq!(map.region(-3, 0, 4, 1, false).count(), 1);
+        assert_eq!(map.region(0, 0, -2, 3, false).count(), 0);
+    }
+
+    #[test]
+    fn deserialize_rejects_ragged_rows() {
+        assert!(Map::deserialize("00 00\n00").is_err());
+    }
+
+    #[test]
+    fn deserialize_rejects_empty_maps() {
+        assert!(Map::deserialize("").is_err());
+        assert!(Map::deserialize("\n  \n").is_err());
+
+        // Maps without cells serialize to nothing, rather than panicking.
+        assert_eq!(Map::with_size(0, 3).serialize(), "");
+    }
+
+    #[test]
+    fn default_map_keeps_pico8_size() {
+        let map = Map::default();
+
+        assert_eq!(map.width(), Map::WIDTH_SPRITES);
+        assert_eq!(map.height(), Map::HEIGHT_SPRITES);
+    }
+}
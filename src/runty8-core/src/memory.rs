@@ -0,0 +1,406 @@
+//! Pico8's [memory](<https://pico-8.fandom.com/wiki/Memory>), for `peek` and `poke`.
+//!
+//! The parts pico8 keeps its state in (sprites, map, flags, draw state and screen)
+//! are views of the game's [`Resources`] and [`DrawData`], so writes show up there.
+//! The rest (music, sound effects, persistent data and general use memory) are plain bytes,
+//! which sounds don't read from yet.
+//!
+//! The bottom halves of the sprite sheet and the map are the same memory (`0x1000..0x2000`),
+//! so they're made to agree when a cart is loaded and kept that way by `mset`, `sset` and `poke`.
+//!
+//! `0x0000..0x4300` is also kept as it was when the cart was loaded, for `reload` and `cstore`.
+
+use crate::draw_data::DrawData;
+use crate::serialize::{self, Serialize};
+use crate::{Color, Flags, Map, Resources, SpriteSheet};
+
+/// `0x0000..0x8000`: the base memory, without pico8's newer upper memory.
+pub(crate) const SIZE: usize = 0x8000;
+/// `0x0000..0x4300`: the part of memory that comes from the cart.
+pub(crate) const CART_SIZE: usize = 0x4300;
+
+const SPRITE_SHEET: usize = 0x0000;
+// The bottom half of the sprite sheet is also the bottom half of the map.
+const SHARED_MAP: usize = 0x1000;
+const MAP: usize = 0x2000;
+const FLAGS: usize = 0x3000;
+const DRAW_STATE: usize = 0x5f00;
+const SCREEN: usize = 0x6000;
+
+// 128 bytes (or cells) per row of the sprite sheet and the map.
+const ROW_LENGTH: usize = 128;
+
+#[derive(Debug)]
+pub(crate) struct Memory {
+    // Bytes that aren't views of other state.
+    bytes: Vec<u8>,
+    // The cart's part of memory, as loaded or last `cstore`d.
+    cart: Vec<u8>,
+}
+
+impl Memory {
+    pub(crate) fn new(resources: &mut Resources) -> Self {
+        share_bottom_half(resources);
+
+        let bytes = vec![0; SIZE];
+        let cart = (0..CART_SIZE)
+            .map(|address| peek_assets(resources, address).unwrap_or(bytes[address]))
+            .collect();
+
+        Self { bytes, cart }
+    }
+
+    /// The byte at `address`, 0 past the end of memory.
+    pub(crate) fn peek(&self, resources: &Resources, draw_data: &DrawData, address: usize) -> u8 {
+        if let Some(value) = peek_assets(resources, address) {
+            return value;
+        }
+
+        match address {
+            DRAW_STATE..=0x5f3f => draw_data
+                .peek_draw_state(address - DRAW_STATE)
+                .unwrap_or(self.bytes[address]),
+            SCREEN..=0x7fff => draw_data.peek_screen(address - SCREEN),
+            _ => self.bytes.get(address).copied().unwrap_or(0),
+        }
+    }
+
+    /// Writes `value` at `address`, ignored past the end of memory.
+    pub(crate) fn poke(
+        &mut self,
+        resources: &mut Resources,
+        draw_data: &mut DrawData,
+        address: usize,
+        value: u8,
+    ) {
+        if poke_assets(resources, address, value) {
+            return;
+        }
+
+        match address {
+            DRAW_STATE..=0x5f3f => {
+                if !draw_data.poke_draw_state(address - DRAW_STATE, value) {
+                    self.bytes[address] = value;
+                }
+            }
+            SCREEN..=0x7fff => draw_data.poke_screen(address - SCREEN, value),
+            _ => {
+                if let Some(byte) = self.bytes.get_mut(address) {
+                    *byte = value;
+                }
+            }
+        }
+    }
+
+    /// The byte at `address` when the cart was loaded (or last stored to), 0 outside of it.
+    pub(crate) fn cart_byte(&self, address: usize) -> u8 {
+        self.cart.get(address).copied().unwrap_or(0)
+    }
+
+    /// Changes the byte at `address` of the cart's data, ignored outside of it.
+    /// Call [`Memory::save_cart`] to write the changes to disk.
+    pub(crate) fn store_cart_byte(&mut self, address: usize, value: u8) {
+        if let Some(byte) = self.cart.get_mut(address) {
+            *byte = value;
+        }
+    }
+
+    /// Saves the cart's sprite sheet, map and flags to the assets path of `resources`.
+    ///
+    /// Cells of bigger maps that are past what memory holds are saved as they are in `resources`.
+    pub(crate) fn save_cart(&self, resources: &Resources) {
+        let assets_path = &resources.assets_path;
+        let mut cart = Resources {
+            map: resources.map.clone(),
+            ..Resources::blank(assets_path)
+        };
+        for (address, &value) in self.cart.iter().enumerate() {
+            poke_assets(&mut cart, address, value);
+        }
+
+        let to_serialize: &[(&str, &dyn Serialize)] = &[
+            (&SpriteSheet::file_name(), &cart.sprite_sheet),
+            (&Map::file_name(), &cart.map),
+            (&Flags::file_name(), &cart.sprite_flags),
+        ];
+        for (name, serializable) in to_serialize {
+            serialize::serialize(assets_path, name, serializable);
+        }
+    }
+}
+
+/// Makes the bottom halves of the sprite sheet and of the map hold the same bytes.
+/// Like `.p8` exports, the sprite sheet's win, unless they're blank.
+fn share_bottom_half(resources: &mut Resources) {
+    let sprites_blank = (SHARED_MAP..MAP).all(|address| peek_sprites(resources, address) == 0);
+
+    for address in SHARED_MAP..MAP {
+        let value = if sprites_blank {
+            let (x, y) = map_cell(address - SHARED_MAP);
+            resources.map.mget(x, y + 32)
+        } else {
+            peek_sprites(resources, address)
+        };
+        poke_assets(resources, address, value);
+    }
+}
+
+/// How many of the `length` bytes from `address` on are below `size`, so copies can stop
+/// there without overflowing: addresses past it read as 0 and ignore writes anyway.
+pub(crate) fn length_within(address: usize, length: usize, size: usize) -> usize {
+    length.min(size.saturating_sub(address))
+}
+
+/// The address of the byte map cell (`x`, `y`) shares with the sprite sheet, if it does.
+pub(crate) fn shared_map_address(x: i32, y: i32) -> Option<usize> {
+    let x = usize::try_from(x).ok().filter(|&x| x < ROW_LENGTH)?;
+    let y = usize::try_from(y).ok().filter(|y| (32..64).contains(y))?;
+
+    Some(SHARED_MAP + (y - 32) * ROW_LENGTH + x)
+}
+
+/// The address of the byte holding sprite sheet pixel (`x`, `y`), if the map shares it.
+pub(crate) fn shared_sprite_address(x: i32, y: i32) -> Option<usize> {
+    let x = usize::try_from(x).ok().filter(|&x| x < ROW_LENGTH)?;
+    let y = usize::try_from(y).ok().filter(|y| (64..128).contains(y))?;
+
+    Some((y * ROW_LENGTH + x) / 2)
+}
+
+fn peek_sprites(resources: &Resources, address: usize) -> u8 {
+    let (x, y) = (2 * address % ROW_LENGTH, 2 * address / ROW_LENGTH);
+    let sprite_sheet = &resources.sprite_sheet;
+
+    sprite_sheet.get(x, y) | sprite_sheet.get(x + 1, y) << 4
+}
+
+/// The byte at `address` if it's part of the sprite sheet, map or flags.
+fn peek_assets(resources: &Resources, address: usize) -> Option<u8> {
+    Some(match address {
+        SPRITE_SHEET..=0x1fff => peek_sprites(resources, address),
+        MAP..=0x2fff => {
+            let (x, y) = map_cell(address - MAP);
+            resources.map.mget(x, y)
+        }
+        FLAGS..=0x30ff => resources.sprite_flags.fget(address - FLAGS),
+        _ => return None,
+    })
+}
+
+/// Writes the byte at `address` if it's part of the sprite sheet, map or flags,
+/// returning whether it was.
+fn poke_assets(resources: &mut Resources, address: usize, value: u8) -> bool {
+    match address {
+        SPRITE_SHEET..=0x1fff => {
+            let (x, y) = (2 * address % ROW_LENGTH, 2 * address / ROW_LENGTH);
+            let colors: [Color; 2] = [value & 0x0f, value >> 4];
+
+            resources.sprite_sheet.set(x, y, colors[0]);
+            resources.sprite_sheet.set(x + 1, y, colors[1]);
+
+            if address >= SHARED_MAP {
+                let (x, y) = map_cell(address - SHARED_MAP);
+                resources.map.mset(x, y + 32, value);
+            }
+        }
+        MAP..=0x2fff => {
+            let (x, y) = map_cell(address - MAP);
+            resources.map.mset(x, y, value);
+        }
+        FLAGS..=0x30ff => {
+            resources.sprite_flags.fset_all(address - FLAGS, value);
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+fn map_cell(offset: usize) -> (i32, i32) {
+    ((offset % ROW_LENGTH) as i32, (offset / ROW_LENGTH) as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{colors, Map, Pico8, Resources};
+
+    fn pico8() -> Pico8 {
+        Pico8::new(Resources::blank("."))
+    }
+
+    #[test]
+    fn sprites_map_and_flags_are_memory_mapped() {
+        let mut pico8 = pico8();
+
+        pico8.sset(2, 1, colors::RED);
+        pico8.sset(3, 1, colors::BLUE);
+        assert_eq!(pico8.peek(0x40 + 1), 0xc8);
+
+        pico8.poke(0x2000 + 128 + 5, 17);
+        assert_eq!(pico8.mget(5, 1), 17);
+
+        pico8.poke(0x1000, 9);
+        assert_eq!(pico8.mget(0, 32), 9);
+        assert_eq!(pico8.resources.sprite_sheet.get(0, 64), 9);
+
+        pico8.poke(0x3000 + 3, 0b101);
+        assert_eq!(pico8.fget(3), 0b101);
+    }
+
+    #[test]
+    fn the_shared_half_is_the_same_bytes() {
+        let mut pico8 = pico8();
+
+        pico8.mset(3, 40, 0x5a);
+        // A row of the map takes as many bytes as two rows of the sprite sheet.
+        let address = 0x1000 + 8 * 128 + 3;
+        assert_eq!(pico8.peek(address), 0x5a);
+        assert_eq!(pico8.sget(6, 64 + 16), 0x0a);
+        assert_eq!(pico8.sget(7, 64 + 16), 0x05);
+
+        pico8.sset(7, 64 + 16, 0x0c);
+        assert_eq!(pico8.mget(3, 40), 0xca);
+    }
+
+    #[test]
+    fn loading_shares_the_map_when_the_sprites_are_blank() {
+        let mut map_only = Resources::blank(".");
+        map_only.map.mset(0, 40, 5);
+        let mut pico8 = Pico8::new(map_only);
+
+        pico8.mset(0, 40, 6);
+        pico8.reload();
+        assert_eq!(pico8.mget(0, 40), 5);
+        assert_eq!(pico8.peek(0x1000 + 8 * 128), 5);
+
+        // Otherwise the sprite sheet's half wins, as in `.p8` exports.
+        let mut shared = Resources::blank(".");
+        shared.map.mset(0, 40, 5);
+        shared.sprite_sheet.set(2, 64 + 16, 1);
+        let pico8 = Pico8::new(shared);
+        assert_eq!(pico8.mget(0, 40), 0);
+        assert_eq!(pico8.mget(1, 40), 1);
+    }
+
+    #[test]
+    fn palette_pokes_change_the_draw_state() {
+        let mut pico8 = pico8();
+
+        // Color 8 drawn as 12, and made transparent.
+        pico8.poke(0x5f08, 0x10 | 12);
+        assert_eq!(pico8.peek(0x5f08), 0x1c);
+
+        pico8.palt_(8, false);
+        pico8.pset(0, 0, 8);
+        assert_eq!(pico8.pget(0, 0), 12);
+
+        pico8.camera(-3, 2);
+        assert_eq!(pico8.peek(0x5f28), 0xfd);
+        assert_eq!(pico8.peek(0x5f29), 0xff);
+        assert_eq!(pico8.peek(0x5f2a), 2);
+    }
+
+    #[test]
+    fn screen_memory_holds_two_pixels_per_byte() {
+        let mut pico8 = pico8();
+
+        pico8.poke(0x6000 + 64 + 1, 0x7a);
+        assert_eq!(pico8.pget(2, 1), 10);
+        assert_eq!(pico8.pget(3, 1), 7);
+
+        pico8.memset(0x6000, 0x11, 0x2000);
+        assert_eq!(pico8.pget(127, 127), 1);
+    }
+
+    #[test]
+    fn memcpy_copies_between_regions() {
+        let mut pico8 = pico8();
+        pico8.sset(0, 0, colors::PINK);
+
+        // The first row of sprite 0 to the top of the screen.
+        pico8.memcpy(0x6000, 0x0000, 4);
+        assert_eq!(pico8.pget(0, 0), colors::PINK);
+
+        pico8.poke(0x4300, 42);
+        pico8.memcpy(0x4301, 0x4300, 2);
+        assert_eq!([pico8.peek(0x4301), pico8.peek(0x4302)], [42, 0]);
+        assert_eq!(pico8.peek(0x8000), 0);
+
+        // Lengths past the end of memory stop there.
+        pico8.memcpy(0x6000, 0x4300, usize::MAX / 2);
+        assert_eq!(pico8.peek(0x6000), 42);
+    }
+
+    #[test]
+    fn addresses_near_the_end_of_usize_dont_overflow() {
+        let mut pico8 = pico8();
+        pico8.poke(0x4300, 42);
+
+        pico8.memcpy(usize::MAX - 1, 0x4300, 10);
+        pico8.memset(usize::MAX - 1, 7, 10);
+        pico8.reload_(usize::MAX - 1, 0, 10);
+
+        // Reading from there gives 0s.
+        pico8.memcpy(0x4300, usize::MAX - 1, 10);
+        pico8.reload_(0x4300, usize::MAX - 1, 10);
+        assert_eq!(pico8.peek(0x4300), 0);
+    }
+
+    #[test]
+    fn reload_undoes_changes_to_the_cart_data() {
+        let mut resources = Resources::blank(".");
+        resources.map.mset(1, 1, 5);
+        let mut pico8 = Pico8::new(resources);
+
+        pico8.mset(1, 1, 6);
+        pico8.mset(2, 1, 7);
+        pico8.reload_(0x2081, 0x2081, 1);
+        assert_eq!([pico8.mget(1, 1), pico8.mget(2, 1)], [5, 7]);
+
+        // Copied elsewhere, like pico8 does.
+        pico8.reload_(0x4300, 0x2081, 1);
+        assert_eq!(pico8.peek(0x4300), 5);
+
+        pico8.reload();
+        assert_eq!(pico8.mget(2, 1), 0);
+    }
+
+    #[test]
+    fn cstore_saves_the_cart_data() {
+        let assets_path = std::env::temp_dir().join("runty8-cstore-test");
+        std::fs::create_dir_all(&assets_path).unwrap();
+        let assets_path = assets_path.to_str().unwrap().to_owned();
+        let mut pico8 = Pico8::new(Resources::blank(&assets_path));
+
+        pico8.mset(3, 4, 9);
+        pico8.fset(2, 1, true);
+        pico8.cstore_(0x2000, 0x2000, 0x1000);
+
+        // Only the map was stored, so the flag change isn't kept.
+        pico8.reload();
+        assert_eq!(pico8.mget(3, 4), 9);
+        assert!(!pico8.fget_n(2, 1));
+
+        let map = std::fs::read_to_string(format!("{assets_path}/{}", Map::file_name())).unwrap();
+        assert_eq!(Map::deserialize(&map).unwrap().mget(3, 4), 9);
+    }
+
+    #[test]
+    fn cstore_keeps_the_bottom_half_of_the_map() {
+        let assets_path = std::env::temp_dir().join("runty8-cstore-shared-test");
+        std::fs::create_dir_all(&assets_path).unwrap();
+        let assets_path = assets_path.to_str().unwrap().to_owned();
+        let mut cart = Resources::blank(&assets_path);
+        cart.map.mset(1, 33, 4);
+        let mut pico8 = Pico8::new(cart);
+
+        pico8.mset(5, 50, 7);
+        pico8.mset(127, 63, 8);
+        pico8.cstore();
+
+        let saved = Pico8::new(Resources::from_dir(&assets_path).unwrap());
+        assert_eq!(saved.mget(1, 33), 4);
+        assert_eq!(saved.mget(5, 50), 7);
+        assert_eq!(saved.mget(127, 63), 8);
+    }
+}
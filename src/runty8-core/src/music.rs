@@ -0,0 +1,188 @@
+use crate::serialize::Serialize;
+use crate::CHANNELS;
+use itertools::Itertools;
+
+/// A step of a pico8 song: up to 4 sound effects played together.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pattern {
+    /// The sound effect played on each channel, if any.
+    pub channels: [Option<u8>; CHANNELS],
+    /// Where `loop_back` patterns go back to.
+    pub loop_start: bool,
+    /// Goes back to the closest previous `loop_start` pattern once done.
+    pub loop_back: bool,
+    /// Stops the music once done.
+    pub stop: bool,
+}
+
+impl Pattern {
+    // Set on a `__music__` channel byte when the channel has no sound.
+    pub(crate) const DISABLED: u8 = 0x40;
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.iter().all(Option::is_none)
+    }
+
+    /// Parses a line of a cart's `__music__` section, e.g. `01 0a424344`:
+    /// a byte of flags (loop start, loop back and stop),
+    /// and then the sound effect of each channel (`0x40` and up meaning no sound).
+    pub(crate) fn from_hex(line: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid music pattern: {line:?}");
+        let byte = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| invalid());
+
+        let (flags, channels) = line.split_once(' ').ok_or_else(invalid)?;
+        if channels.len() != 2 * CHANNELS || !channels.is_ascii() {
+            return Err(invalid());
+        }
+
+        let flags = byte(flags)?;
+        let mut pattern = Self {
+            loop_start: flags & 0b001 != 0,
+            loop_back: flags & 0b010 != 0,
+            stop: flags & 0b100 != 0,
+            ..Self::default()
+        };
+        for (index, channel) in pattern.channels.iter_mut().enumerate() {
+            let sound = byte(&channels[2 * index..2 * index + 2])?;
+
+            *channel = (sound & Self::DISABLED == 0).then_some(sound);
+        }
+
+        Ok(pattern)
+    }
+
+    /// The inverse of [`Pattern::from_hex`].
+    pub(crate) fn to_hex(&self) -> String {
+        let flags = self.loop_start as u8 | (self.loop_back as u8) << 1 | (self.stop as u8) << 2;
+        let channels: String = self
+            .channels
+            .iter()
+            .zip(1..)
+            // Empty channels are written like in new pico8 carts: 0x41 to 0x44.
+            .map(|(sound, index)| format!("{:02x}", sound.unwrap_or(Self::DISABLED | index)))
+            .collect();
+
+        format!("{flags:02x} {channels}")
+    }
+}
+
+/// A pico8 game's music: 64 patterns, played one after the other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Music {
+    patterns: Vec<Pattern>,
+}
+
+impl Music {
+    pub const COUNT: usize = 64;
+
+    pub fn file_name() -> String {
+        "music.txt".to_owned()
+    }
+
+    pub fn new() -> Self {
+        Self {
+            patterns: vec![Pattern::default(); Self::COUNT],
+        }
+    }
+
+    pub fn get(&self, pattern: usize) -> Option<&Pattern> {
+        self.patterns.get(pattern)
+    }
+
+    pub fn get_mut(&mut self, pattern: usize) -> Option<&mut Pattern> {
+        self.patterns.get_mut(pattern)
+    }
+
+    /// Reads music in the format of a cart's `__music__` section:
+    /// one line per pattern (see [`Pattern::from_hex`]), blank and missing ones being left empty.
+    pub fn deserialize(file_contents: &str) -> Result<Self, String> {
+        let lines: Vec<&str> = file_contents.lines().map(str::trim).collect();
+        // Blank lines keep their place, except for the ones ending the file.
+        let count = lines
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(0, |last| last + 1);
+        let lines = &lines[..count];
+
+        if lines.len() > Self::COUNT {
+            return Err(format!(
+                "Too many music patterns: expected at most {}, got {}",
+                Self::COUNT,
+                lines.len()
+            ));
+        }
+
+        let mut music = Self::new();
+        for (pattern, line) in music.patterns.iter_mut().zip(lines) {
+            if !line.is_empty() {
+                *pattern = Pattern::from_hex(line)?;
+            }
+        }
+
+        Ok(music)
+    }
+}
+
+impl Serialize for Music {
+    fn serialize(&self) -> String {
+        self.patterns.iter().map(Pattern::to_hex).join("\n")
+    }
+}
+
+impl Default for Music {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Music, Pattern};
+    use crate::serialize::Serialize;
+
+    #[test]
+    fn parses_patterns() {
+        let pattern = Pattern::from_hex("05 0a424344").unwrap();
+
+        assert_eq!(pattern.channels, [Some(10), None, None, None]);
+        assert!(pattern.loop_start);
+        assert!(!pattern.loop_back);
+        assert!(pattern.stop);
+    }
+
+    #[test]
+    fn patterns_round_trip() {
+        for line in ["00 41424344", "02 00010203", "07 3f424344"] {
+            assert_eq!(Pattern::from_hex(line).unwrap().to_hex(), line);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(Pattern::from_hex("00").is_err());
+        assert!(Pattern::from_hex("00 414243").is_err());
+        assert!(Pattern::from_hex("zz 41424344").is_err());
+    }
+
+    #[test]
+    fn music_files_round_trip() {
+        let mut music = Music::new();
+        *music.get_mut(10).unwrap() = Pattern::from_hex("05 0a424344").unwrap();
+
+        let deserialized = Music::deserialize(&music.serialize()).unwrap();
+
+        assert_eq!(deserialized, music);
+        assert_eq!(
+            Music::deserialize("05 0a424344").unwrap().get(0),
+            music.get(10)
+        );
+    }
+
+    #[test]
+    fn blank_lines_keep_the_pattern_numbers() {
+        let music = Music::deserialize("05 0a424344\n\n05 0a424344\n\n").unwrap();
+
+        assert_eq!(music.get(1), Some(&Pattern::default()));
+        assert_eq!(music.get(2), music.get(0));
+    }
+}
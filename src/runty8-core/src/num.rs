@@ -0,0 +1,382 @@
+use std::fmt::Display;
+use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Neg, Not, Rem, Shl, Shr, Sub};
+
+/// A number the way pico8 stores it: 16.16 signed fixed point.
+///
+/// Arithmetic wraps around like in pico8, e.g. `32767 + 1` is `-32768`,
+/// so game logic ported from a cart can rely on it. Pico8's `shl` and `shr` are `<<` and `>>`:
+///
+/// ```
+/// use runty8_core::P8Num;
+///
+/// let max = P8Num::from(32767);
+/// assert_eq!(max + P8Num::from(1), P8Num::from(-32768));
+/// assert_eq!(P8Num::from(1) / P8Num::from(3), P8Num::from_bits(0x5555));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct P8Num(i32);
+
+impl P8Num {
+    pub const ZERO: Self = Self(0);
+    pub const ONE: Self = Self(0x1_0000);
+    /// `0x7fff.ffff`, just under 32768.
+    pub const MAX: Self = Self(i32::MAX);
+    /// `-0x8000`, that is -32768.
+    pub const MIN: Self = Self(i32::MIN);
+
+    /// The number whose 16.16 bits are `bits`, e.g. `0x0001_8000` for 1.5.
+    pub const fn from_bits(bits: i32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
+    /// The closest number to `f`, wrapping around outside of `-32768..32768` like pico8 does.
+    pub fn from_f32(f: f32) -> Self {
+        Self((f64::from(f) * 65536.0).round() as i64 as i32)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / 65536.0
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Flr>
+    pub fn flr(self) -> Self {
+        Self(self.0 & !0xffff)
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Ceil>
+    pub fn ceil(self) -> Self {
+        -(-self).flr()
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Abs>
+    ///
+    /// `abs(-32768)` doesn't fit, and is `0x7fff.ffff` like in pico8.
+    pub fn abs(self) -> Self {
+        match self.0 {
+            i32::MIN => Self::MAX,
+            bits => Self(bits.abs()),
+        }
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Sgn>
+    ///
+    /// -1 for negative numbers, 1 otherwise (zero included).
+    pub fn sgn(self) -> Self {
+        if self.0 < 0 {
+            -Self::ONE
+        } else {
+            Self::ONE
+        }
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Sqrt>
+    ///
+    /// Rounded down to pico8's precision, and 0 for negative numbers.
+    pub fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Self::ZERO;
+        }
+
+        // The root of the bits shifted by 16 more keeps the 16.16 point in place.
+        let squared = (self.0 as u64) << 16;
+        let mut root = (squared as f64).sqrt() as u64;
+        while root * root > squared {
+            root -= 1;
+        }
+        while (root + 1) * (root + 1) <= squared {
+            root += 1;
+        }
+
+        Self(root as i32)
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Mid>
+    pub fn mid(first: Self, second: Self, third: Self) -> Self {
+        let mut numbers = [first, second, third];
+        numbers.sort();
+
+        numbers[1]
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Sin>
+    ///
+    /// In turns and negated, like [`crate::sin`]: `sin(0.25)` is -1.
+    pub fn sin(self) -> Self {
+        Self::from_f32(crate::sin(self.to_f32()))
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Cos>
+    pub fn cos(self) -> Self {
+        Self::from_f32(crate::cos(self.to_f32()))
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Lshr>
+    ///
+    /// A logical shift, filling with zeroes.
+    pub fn lshr(self, bits: i32) -> Self {
+        match bits {
+            i32::MIN..=-1 => self << bits.saturating_neg(),
+            0..=31 => Self(((self.0 as u32) >> bits) as i32),
+            _ => Self::ZERO,
+        }
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Band>
+    pub fn band(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Bor>
+    pub fn bor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Bxor>
+    pub fn bxor(self, other: Self) -> Self {
+        Self(self.0 ^ other.0)
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Bnot>
+    pub fn bnot(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+impl From<i16> for P8Num {
+    fn from(integer: i16) -> Self {
+        Self(i32::from(integer) << 16)
+    }
+}
+
+impl Add for P8Num {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0.wrapping_add(other.0))
+    }
+}
+
+impl Sub for P8Num {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0.wrapping_sub(other.0))
+    }
+}
+
+impl Neg for P8Num {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.wrapping_neg())
+    }
+}
+
+impl Mul for P8Num {
+    type Output = Self;
+
+    /// Keeps the low 32 bits of the product, dropping the lowest fractional bits.
+    fn mul(self, other: Self) -> Self {
+        Self(((i64::from(self.0) * i64::from(other.0)) >> 16) as i32)
+    }
+}
+
+impl Div for P8Num {
+    type Output = Self;
+
+    /// Unlike the others, division saturates: results that don't fit (including dividing by 0)
+    /// are `0x7fff.ffff`, or `-0x7fff.ffff` when negative.
+    fn div(self, other: Self) -> Self {
+        if other.0 != 0 {
+            let quotient = (i64::from(self.0) << 16) / i64::from(other.0);
+
+            if let Ok(bits) = i32::try_from(quotient) {
+                return Self(bits);
+            }
+        }
+
+        if (self.0 ^ other.0) >= 0 {
+            Self::MAX
+        } else {
+            Self(-i32::MAX)
+        }
+    }
+}
+
+impl Rem for P8Num {
+    type Output = Self;
+
+    /// Pico8's `%`, which is never negative: `-1 % 3` is 2. `x % 0` is 0.
+    fn rem(self, other: Self) -> Self {
+        if other.0 == 0 {
+            return Self::ZERO;
+        }
+
+        Self(i64::from(self.0).rem_euclid(i64::from(other.0)) as i32)
+    }
+}
+
+impl BitAnd for P8Num {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        self.band(other)
+    }
+}
+
+impl BitOr for P8Num {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        self.bor(other)
+    }
+}
+
+impl BitXor for P8Num {
+    type Output = Self;
+
+    fn bitxor(self, other: Self) -> Self {
+        self.bxor(other)
+    }
+}
+
+impl Not for P8Num {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        self.bnot()
+    }
+}
+
+impl Shl<i32> for P8Num {
+    type Output = Self;
+
+    /// <https://pico-8.fandom.com/wiki/Shl>
+    ///
+    /// Shifting by 32 or more gives 0, negative shifts go right instead.
+    fn shl(self, bits: i32) -> Self {
+        match bits {
+            i32::MIN..=-1 => self >> bits.saturating_neg(),
+            0..=31 => Self(self.0 << bits),
+            _ => Self::ZERO,
+        }
+    }
+}
+
+impl Shr<i32> for P8Num {
+    type Output = Self;
+
+    /// <https://pico-8.fandom.com/wiki/Shr>
+    ///
+    /// An arithmetic shift, keeping the sign. Negative shifts go left instead.
+    fn shr(self, bits: i32) -> Self {
+        match bits {
+            i32::MIN..=-1 => self << bits.saturating_neg(),
+            0..=31 => Self(self.0 >> bits),
+            _ => Self(self.0 >> 31),
+        }
+    }
+}
+
+impl Display for P8Num {
+    /// Like pico8's `print`: up to 4 decimals, without trailing zeroes.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let number = format!("{:.4}", f64::from(self.0) / 65536.0);
+        let number = number.trim_end_matches('0').trim_end_matches('.');
+
+        f.write_str(if number == "-0" { "0" } else { number })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::P8Num;
+
+    fn num(integer: i16) -> P8Num {
+        P8Num::from(integer)
+    }
+
+    #[test]
+    fn arithmetic_wraps_around() {
+        assert_eq!(num(32767) + num(1), num(-32768));
+        assert_eq!(num(-32768) - num(1), num(32767));
+        assert_eq!(-num(-32768), num(-32768));
+        // 0x7fff * 2 = 0xfffe
+        assert_eq!(num(32767) * num(2), num(-2));
+        assert_eq!(num(200) * num(200), num(-25536));
+        assert_eq!(
+            P8Num::from_f32(0.5) * P8Num::from_f32(0.5),
+            P8Num::from_f32(0.25)
+        );
+        assert_eq!(P8Num::from_f32(32768.0), num(-32768));
+    }
+
+    #[test]
+    fn division_saturates() {
+        assert_eq!(num(1) / num(0), P8Num::MAX);
+        assert_eq!(num(-1) / num(0), P8Num::from_bits(-0x7fff_ffff));
+        assert_eq!(num(-32768) / num(-1), P8Num::MAX);
+        assert_eq!(num(16384) / P8Num::from_f32(0.25), P8Num::MAX);
+        assert_eq!(num(7) / num(2), P8Num::from_f32(3.5));
+        assert_eq!(num(-32768) / num(1), num(-32768));
+    }
+
+    #[test]
+    fn remainder_is_never_negative() {
+        assert_eq!(num(-1) % num(3), num(2));
+        assert_eq!(num(7) % num(-3), num(1));
+        assert_eq!(P8Num::from_f32(5.5) % num(2), P8Num::from_f32(1.5));
+        assert_eq!(num(5) % num(0), P8Num::ZERO);
+    }
+
+    #[test]
+    fn rounding_and_signs() {
+        assert_eq!(P8Num::from_f32(-1.5).flr(), num(-2));
+        assert_eq!(P8Num::from_f32(1.5).flr(), num(1));
+        assert_eq!(P8Num::from_f32(-1.5).ceil(), num(-1));
+        assert_eq!(P8Num::from_f32(1.25).ceil(), num(2));
+        assert_eq!(num(-32768).abs(), P8Num::MAX);
+        assert_eq!(num(-3).abs(), num(3));
+        assert_eq!(num(0).sgn(), num(1));
+        assert_eq!(num(-5).sgn(), num(-1));
+        assert_eq!(P8Num::mid(num(3), num(-1), num(2)), num(2));
+        assert_eq!(num(4).sqrt(), num(2));
+        assert_eq!(num(2).sqrt(), P8Num::from_bits(0x1_6a09));
+        assert_eq!(num(-1).sqrt(), P8Num::ZERO);
+    }
+
+    #[test]
+    fn shifts_and_bitwise_operations() {
+        assert_eq!(num(1) << 4, num(16));
+        assert_eq!(num(1) << 15, num(-32768));
+        assert_eq!(num(1) << 32, P8Num::ZERO);
+        assert_eq!(num(1) >> 1, P8Num::from_f32(0.5));
+        assert_eq!(num(-8) >> 1, num(-4));
+        assert_eq!(num(-1) >> 40, P8Num::from_bits(-1));
+        assert_eq!(num(-1).lshr(16), P8Num::from_bits(0xffff));
+        assert_eq!(num(16) << -2, num(4));
+
+        assert_eq!(num(0b1100) & num(0b1010), num(0b1000));
+        assert_eq!(num(0b1100) | num(0b1010), num(0b1110));
+        assert_eq!(num(0b1100) ^ num(0b1010), num(0b0110));
+        assert_eq!(!P8Num::ZERO, P8Num::from_bits(-1));
+    }
+
+    #[test]
+    fn trigonometry_follows_pico8() {
+        assert_eq!(P8Num::from_f32(0.25).sin(), num(-1));
+        assert_eq!(P8Num::ZERO.cos(), num(1));
+        assert_eq!(P8Num::from_f32(0.5).cos(), num(-1));
+    }
+
+    #[test]
+    fn prints_like_pico8() {
+        assert_eq!((num(1) / num(3)).to_string(), "0.3333");
+        assert_eq!(num(-12).to_string(), "-12");
+        assert_eq!(P8Num::from_f32(2.5).to_string(), "2.5");
+    }
+}
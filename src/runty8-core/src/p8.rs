@@ -0,0 +1,660 @@
+//! Reading and writing pico8's `.p8` cartridge format, and reading `.p8.png` carts.
+//!
+//! Only the sprite sheet, sprite flags, map, sound effects and music are used.
+use crate::music::Pattern;
+use crate::serialize::Serialize;
+use crate::sfx::{Effect, Instrument, Note};
+use crate::{Flags, Map, Music, Sfx, Sound, SpriteSheet};
+use itertools::Itertools;
+
+const HEADER: &str = "pico-8 cartridge // http://www.pico-8.com\nversion 41\n";
+
+const GFX_SIZE: usize = 128;
+const MAP_SECTION_ROWS: usize = 32;
+
+/// Sections of a `.p8` cart, as lines of text.
+#[derive(Debug, Default)]
+struct Sections<'a> {
+    gfx: Vec<&'a str>,
+    gff: Vec<&'a str>,
+    map: Vec<&'a str>,
+    sfx: Vec<&'a str>,
+    music: Vec<&'a str>,
+}
+
+impl<'a> Sections<'a> {
+    fn parse(contents: &'a str) -> Self {
+        let mut sections = Self::default();
+        let mut current: Option<&mut Vec<&'a str>> = None;
+
+        for line in contents.lines().map(str::trim) {
+            if line.starts_with("__") && line.ends_with("__") {
+                current = match line {
+                    "__gfx__" => Some(&mut sections.gfx),
+                    "__gff__" => Some(&mut sections.gff),
+                    "__map__" => Some(&mut sections.map),
+                    "__sfx__" => Some(&mut sections.sfx),
+                    "__music__" => Some(&mut sections.music),
+                    _ => None,
+                };
+            } else if let Some(lines) = current.as_mut() {
+                if !line.is_empty() {
+                    lines.push(line);
+                }
+            }
+        }
+
+        sections
+    }
+}
+
+/// The assets in a cart.
+#[derive(Debug)]
+pub(crate) struct Cart {
+    pub sprite_sheet: SpriteSheet,
+    pub flags: Flags,
+    pub map: Map,
+    pub sfx: Sfx,
+    pub music: Music,
+}
+
+/// Parses the assets out of a `.p8` cart.
+pub(crate) fn parse(contents: &str) -> Result<Cart, String> {
+    let sections = Sections::parse(contents);
+
+    let sprite_sheet = parse_gfx(&sections.gfx)?;
+    let flags = parse_gff(&sections.gff)?;
+    let map = parse_map(&sections.map, &sprite_sheet)?;
+    let sfx = Sfx::deserialize(&sections.sfx.join("\n"))?;
+    let music = Music::deserialize(&sections.music.join("\n"))?;
+
+    Ok(Cart {
+        sprite_sheet,
+        flags,
+        map,
+        sfx,
+        music,
+    })
+}
+
+fn hex_digit(c: char) -> Result<u8, String> {
+    c.to_digit(16)
+        .map(|digit| digit as u8)
+        .ok_or_else(|| format!("Invalid hex digit in cart: {c:?}"))
+}
+
+// Rows of hex bytes (two characters each), e.g. "00a1ff".
+fn hex_bytes(line: &str) -> Result<Vec<u8>, String> {
+    let digits = line.chars().map(hex_digit).collect::<Result<Vec<_>, _>>()?;
+
+    Ok(digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair.get(1).copied().unwrap_or(0))
+        .collect())
+}
+
+fn too_many(section: &str, expected: usize, found: usize) -> String {
+    format!("Too many rows in {section}: expected at most {expected}, found {found}")
+}
+
+// One hex digit per pixel, one line per row of pixels.
+fn parse_gfx(lines: &[&str]) -> Result<SpriteSheet, String> {
+    if lines.len() > GFX_SIZE {
+        return Err(too_many("__gfx__", GFX_SIZE, lines.len()));
+    }
+
+    let mut sprite_sheet = SpriteSheet::new();
+    for (y, line) in lines.iter().enumerate() {
+        for (x, c) in line.chars().take(GFX_SIZE).enumerate() {
+            sprite_sheet.set(x, y, hex_digit(c)?);
+        }
+    }
+
+    Ok(sprite_sheet)
+}
+
+// One hex byte per sprite, 128 sprites per line.
+fn parse_gff(lines: &[&str]) -> Result<Flags, String> {
+    let mut flags = Flags::new();
+
+    let bytes = lines
+        .iter()
+        .map(|line| hex_bytes(line))
+        .collect::<Result<Vec<_>, _>>()?
+        .concat();
+    if bytes.len() > SpriteSheet::SPRITE_COUNT {
+        return Err(too_many("__gff__", SpriteSheet::SPRITE_COUNT, bytes.len()));
+    }
+
+    for (sprite, value) in bytes.into_iter().enumerate() {
+        flags.fset_all(sprite, value);
+    }
+
+    Ok(flags)
+}
+
+// One hex byte per cell, for the top half of the map.
+// The bottom half shares memory with the bottom half of the sprite sheet.
+fn parse_map(lines: &[&str], sprite_sheet: &SpriteSheet) -> Result<Map, String> {
+    if lines.len() > MAP_SECTION_ROWS {
+        return Err(too_many("__map__", MAP_SECTION_ROWS, lines.len()));
+    }
+
+    let mut map = Map::with_size(Map::WIDTH_SPRITES, Map::HEIGHT_SPRITES);
+    for (y, line) in lines.iter().enumerate() {
+        for (x, sprite) in hex_bytes(line)?
+            .into_iter()
+            .take(Map::WIDTH_SPRITES)
+            .enumerate()
+        {
+            map.mset(x as i32, y as i32, sprite);
+        }
+    }
+
+    for y in MAP_SECTION_ROWS..Map::HEIGHT_SPRITES {
+        for x in 0..Map::WIDTH_SPRITES {
+            map.mset(x as i32, y as i32, shared_byte(sprite_sheet, x, y));
+        }
+    }
+
+    Ok(map)
+}
+
+const PNG_WIDTH: usize = 160;
+const PNG_HEIGHT: usize = 205;
+
+// Where each asset lives in pico8's memory (and in a cart's ROM).
+const GFX_ADDRESS: usize = 0x0000;
+const MAP_ADDRESS: usize = 0x2000;
+const GFF_ADDRESS: usize = 0x3000;
+const MUSIC_ADDRESS: usize = 0x3100;
+const SFX_ADDRESS: usize = 0x3200;
+const ROM_DATA_SIZE: usize = 0x4300;
+
+// Bytes per sound effect: 2 per note, then editor mode, speed, loop start and loop end.
+const SFX_SIZE: usize = 2 * Sound::NOTES + 4;
+
+/// Extracts the cart's ROM from a `.p8.png` image.
+///
+/// Each byte of the ROM is hidden in the two lowest bits of each channel of a pixel,
+/// from most to least significant: alpha, red, green, blue.
+/// Pixels are read left to right, top to bottom.
+fn decode_png(png: &[u8]) -> Result<Vec<u8>, String> {
+    let mut decoder = png::Decoder::new(png);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|error| format!("Couldn't read cart image: {error}"))?;
+
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut pixels)
+        .map_err(|error| format!("Couldn't decode cart image: {error}"))?;
+
+    if (info.width as usize, info.height as usize) != (PNG_WIDTH, PNG_HEIGHT)
+        || info.color_type != png::ColorType::Rgba
+        || info.bit_depth != png::BitDepth::Eight
+    {
+        return Err(format!(
+            "Expected a {PNG_WIDTH}x{PNG_HEIGHT} RGBA cart image, got a {}x{} {:?} one",
+            info.width, info.height, info.color_type
+        ));
+    }
+
+    Ok(pixels[..info.buffer_size()]
+        .chunks_exact(4)
+        .map(|rgba| {
+            let [r, g, b, a] = [rgba[0], rgba[1], rgba[2], rgba[3]].map(|channel| channel & 0b11);
+
+            (a << 6) | (r << 4) | (g << 2) | b
+        })
+        .collect())
+}
+
+/// Parses the assets out of a `.p8.png` cart.
+pub(crate) fn parse_png(png: &[u8]) -> Result<Cart, String> {
+    parse_rom(&decode_png(png)?)
+}
+
+/// Parses the assets out of a cart's ROM.
+fn parse_rom(rom: &[u8]) -> Result<Cart, String> {
+    if rom.len() < ROM_DATA_SIZE {
+        return Err(format!(
+            "Cart is too small: expected at least {ROM_DATA_SIZE} bytes, got {}",
+            rom.len()
+        ));
+    }
+
+    // Two pixels per byte, the left one in the low nibble.
+    let mut sprite_sheet = SpriteSheet::new();
+    for (index, byte) in rom[GFX_ADDRESS..MAP_ADDRESS].iter().enumerate() {
+        let (x, y) = ((index * 2) % GFX_SIZE, (index * 2) / GFX_SIZE);
+
+        sprite_sheet.set(x, y, byte & 0x0f);
+        sprite_sheet.set(x + 1, y, byte >> 4);
+    }
+
+    let mut flags = Flags::new();
+    for (sprite, &value) in rom[GFF_ADDRESS..MUSIC_ADDRESS].iter().enumerate() {
+        flags.fset_all(sprite, value);
+    }
+
+    let mut map = Map::with_size(Map::WIDTH_SPRITES, Map::HEIGHT_SPRITES);
+    for (index, &sprite) in rom[MAP_ADDRESS..GFF_ADDRESS].iter().enumerate() {
+        let (x, y) = (index % Map::WIDTH_SPRITES, index / Map::WIDTH_SPRITES);
+
+        map.mset(x as i32, y as i32, sprite);
+    }
+    for y in MAP_SECTION_ROWS..Map::HEIGHT_SPRITES {
+        for x in 0..Map::WIDTH_SPRITES {
+            map.mset(x as i32, y as i32, shared_byte(&sprite_sheet, x, y));
+        }
+    }
+
+    // A byte per channel: the sound effect in the low 6 bits (bit 6 meaning no sound),
+    // and the pattern's loop start, loop back and stop flags in the top bit of the first 3.
+    let mut music = Music::new();
+    for (index, bytes) in rom[MUSIC_ADDRESS..SFX_ADDRESS].chunks_exact(4).enumerate() {
+        let flag = |channel: usize| bytes[channel] & 0x80 != 0;
+        let pattern = music.get_mut(index).unwrap();
+
+        *pattern = Pattern {
+            loop_start: flag(0),
+            loop_back: flag(1),
+            stop: flag(2),
+            ..Pattern::default()
+        };
+        for (channel, &byte) in pattern.channels.iter_mut().zip(bytes) {
+            *channel = (byte & Pattern::DISABLED == 0).then_some(byte & 0x3f);
+        }
+    }
+
+    // Notes are little endian: 6 bits of pitch, 3 of instrument, 3 of volume and 3 of effect
+    // (the top bit marks custom instruments, which aren't supported).
+    let mut sfx = Sfx::new();
+    for (index, bytes) in rom[SFX_ADDRESS..ROM_DATA_SIZE]
+        .chunks_exact(SFX_SIZE)
+        .enumerate()
+    {
+        let sound = sfx.get_mut(index).unwrap();
+
+        for (note, pair) in sound.notes.iter_mut().zip(bytes.chunks_exact(2)) {
+            let bits = u16::from_le_bytes([pair[0], pair[1]]);
+            let field = |shift: u16| ((bits >> shift) & 0b111) as u8;
+
+            *note = Note {
+                pitch: (bits & 0x3f) as u8,
+                instrument: Instrument::from_index(field(6)),
+                volume: field(9),
+                effect: Effect::from_index(field(12)),
+            };
+        }
+        sound.speed = bytes[2 * Sound::NOTES + 1];
+        sound.loop_start = bytes[2 * Sound::NOTES + 2];
+        sound.loop_end = bytes[2 * Sound::NOTES + 3];
+    }
+
+    Ok(Cart {
+        sprite_sheet,
+        flags,
+        map,
+        sfx,
+        music,
+    })
+}
+
+/// Writes the sprite sheet, flags, map, sound effects and music as a `.p8` cart.
+///
+/// Pico8 keeps the bottom half of the map and of the sprite sheet in the same memory.
+/// The sprite sheet takes precedence, unless its bottom half is blank.
+/// Only the top left 128x64 cells of bigger maps are written.
+pub(crate) fn serialize(
+    sprite_sheet: &SpriteSheet,
+    flags: &Flags,
+    map: &Map,
+    sfx: &Sfx,
+    music: &Music,
+) -> String {
+    let mut sprite_sheet = sprite_sheet.clone();
+    let bottom_half_blank =
+        (GFX_SIZE / 2..GFX_SIZE).all(|y| (0..GFX_SIZE).all(|x| sprite_sheet.get(x, y) == 0));
+
+    if bottom_half_blank {
+        for y in MAP_SECTION_ROWS..Map::HEIGHT_SPRITES {
+            for x in 0..Map::WIDTH_SPRITES {
+                let (pixel_x, pixel_y) = shared_pixels(x, y);
+                let byte = map.mget(x as i32, y as i32);
+
+                sprite_sheet.set(pixel_x, pixel_y, byte & 0x0f);
+                sprite_sheet.set(pixel_x + 1, pixel_y, byte >> 4);
+            }
+        }
+    } else if (MAP_SECTION_ROWS..Map::HEIGHT_SPRITES).any(|y| {
+        (0..Map::WIDTH_SPRITES)
+            .any(|x| map.mget(x as i32, y as i32) != shared_byte(&sprite_sheet, x, y))
+    }) {
+        log::warn!("Bottom half of the map conflicts with the sprite sheet, it won't be exported.");
+    }
+
+    let gfx = (0..GFX_SIZE)
+        .map(|y| {
+            (0..GFX_SIZE)
+                .map(|x| format!("{:x}", sprite_sheet.get(x, y)))
+                .join("")
+        })
+        .join("\n");
+
+    let gff = (0..SpriteSheet::SPRITE_COUNT)
+        .map(|sprite| format!("{:02x}", flags.get(sprite).unwrap_or(0)))
+        .chunks(SpriteSheet::SPRITE_COUNT / 2)
+        .into_iter()
+        .map(|mut row| row.join(""))
+        .join("\n");
+
+    let map = (0..MAP_SECTION_ROWS)
+        .map(|y| {
+            (0..Map::WIDTH_SPRITES)
+                .map(|x| format!("{:02x}", map.mget(x as i32, y as i32)))
+                .join("")
+        })
+        .join("\n");
+
+    let sfx = sfx.serialize();
+    let music = music.serialize();
+
+    format!(
+        "{HEADER}__gfx__\n{gfx}\n__gff__\n{gff}\n__map__\n{map}\n__sfx__\n{sfx}\n__music__\n{music}\n"
+    )
+}
+
+/// The byte of sprite sheet memory that map cell (`x`, `y`) shares (`y` in the bottom half).
+///
+/// Each byte holds two pixels: the left one in the low nibble.
+pub(crate) fn shared_byte(sprite_sheet: &SpriteSheet, x: usize, y: usize) -> u8 {
+    let (pixel_x, pixel_y) = shared_pixels(x, y);
+
+    let left = sprite_sheet.get(pixel_x, pixel_y);
+    let right = sprite_sheet.get(pixel_x + 1, pixel_y);
+
+    left | (right << 4)
+}
+
+/// Sprite sheet coordinates of the first of the two pixels
+/// that share memory with map cell (`x`, `y`).
+pub(crate) fn shared_pixels(x: usize, y: usize) -> (usize, usize) {
+    let offset = (y - MAP_SECTION_ROWS) * Map::WIDTH_SPRITES + x;
+    let bytes_per_row = GFX_SIZE / 2;
+
+    (
+        (offset % bytes_per_row) * 2,
+        GFX_SIZE / 2 + offset / bytes_per_row,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_png, serialize, PNG_HEIGHT, PNG_WIDTH};
+    use crate::sfx::{Effect, Instrument, Note};
+    use crate::{Flags, Map, Music, Sfx, SpriteSheet};
+
+    fn cart(gfx: &[&str], gff: &[&str], map: &[&str]) -> String {
+        cart_with_audio(gfx, gff, map, &[], &[])
+    }
+
+    fn cart_with_audio(
+        gfx: &[&str],
+        gff: &[&str],
+        map: &[&str],
+        sfx: &[&str],
+        music: &[&str],
+    ) -> String {
+        format!(
+            "pico-8 cartridge // http://www.pico-8.com\nversion 41\n__lua__\nprint(\"hi\")\n__gfx__\n{}\n__gff__\n{}\n__map__\n{}\n__sfx__\n{}\n__music__\n{}\n",
+            gfx.join("\n"),
+            gff.join("\n"),
+            map.join("\n"),
+            sfx.join("\n"),
+            music.join("\n")
+        )
+    }
+
+    #[test]
+    fn parses_sprite_pixels() {
+        let sprite_sheet = parse(&cart(&["0123456789abcdef", "f"], &[], &[]))
+            .unwrap()
+            .sprite_sheet;
+
+        assert_eq!(sprite_sheet.get(1, 0), 1);
+        assert_eq!(sprite_sheet.get(15, 0), 15);
+        assert_eq!(sprite_sheet.get(0, 1), 15);
+        // Second sprite in the row.
+        assert_eq!(sprite_sheet.get_sprite(1).sprite[0], 8);
+    }
+
+    #[test]
+    fn parses_flags() {
+        let flags = parse(&cart(&[], &["00ff81"], &[])).unwrap().flags;
+
+        assert_eq!(flags.get(0), Some(0));
+        assert_eq!(flags.get(1), Some(0xff));
+        assert_eq!(flags.get(2), Some(0x81));
+    }
+
+    #[test]
+    fn parses_map_top_half() {
+        let map = parse(&cart(&[], &[], &["0102", "", "ff"])).unwrap().map;
+
+        assert_eq!(map.mget(0, 0), 1);
+        assert_eq!(map.mget(1, 0), 2);
+        assert_eq!(map.mget(0, 1), 0xff);
+    }
+
+    #[test]
+    fn map_bottom_half_comes_from_sprite_sheet() {
+        let mut gfx = vec!["0"; 64];
+        // Pixels 0 and 1 of row 64 are the first cell of row 32 of the map.
+        gfx.push("21");
+        let map = parse(&cart(&gfx, &[], &[])).unwrap().map;
+
+        assert_eq!(map.mget(0, 32), 0x12);
+    }
+
+    #[test]
+    fn rejects_invalid_hex() {
+        assert!(parse(&cart(&["0g"], &[], &[])).is_err());
+    }
+
+    // A cart with every section full, as pico8 writes them.
+    fn full_cart() -> String {
+        let hex = "0123456789abcdef";
+        let gfx: Vec<String> = (0..128)
+            .map(|y| {
+                (0..128)
+                    .map(|x: usize| hex.as_bytes()[(x * 7 + y * 3) % 16] as char)
+                    .collect()
+            })
+            .collect();
+        let gff: Vec<String> = (0..2)
+            .map(|row| (0..128).map(|i| format!("{:02x}", i + row * 128)).collect())
+            .collect();
+        let map: Vec<String> = (0..32)
+            .map(|y| (0..128).map(|x| format!("{:02x}", (x * y) % 256)).collect())
+            .collect();
+
+        let sfx: Vec<String> = (0..64)
+            .map(|i| format!("00{:02x}0000{}", i + 1, "0c355".repeat(32)))
+            .collect();
+        let music: Vec<String> = (0..64)
+            .map(|i| format!("0{} {:02x}424344", i % 8, i))
+            .collect();
+
+        cart_with_audio(
+            &lines(&gfx),
+            &lines(&gff),
+            &lines(&map),
+            &lines(&sfx),
+            &lines(&music),
+        )
+    }
+
+    fn lines(rows: &[String]) -> Vec<&str> {
+        rows.iter().map(String::as_str).collect()
+    }
+
+    fn section<'a>(cart: &'a str, name: &str) -> Vec<&'a str> {
+        cart.lines()
+            .skip_while(|line| *line != name)
+            .skip(1)
+            .take_while(|line| !line.starts_with("__"))
+            .collect()
+    }
+
+    #[test]
+    fn round_trip_is_identical() {
+        let original = full_cart();
+        let cart = parse(&original).unwrap();
+
+        let exported = serialize(
+            &cart.sprite_sheet,
+            &cart.flags,
+            &cart.map,
+            &cart.sfx,
+            &cart.music,
+        );
+
+        for name in ["__gfx__", "__gff__", "__map__", "__sfx__", "__music__"] {
+            assert_eq!(section(&exported, name), section(&original, name), "{name}");
+        }
+    }
+
+    #[test]
+    fn map_bottom_half_is_exported_over_blank_sprites() {
+        let mut map = Map::new();
+        map.mset(0, 32, 0x12);
+
+        let exported = serialize(
+            &SpriteSheet::new(),
+            &Flags::new(),
+            &map,
+            &Sfx::new(),
+            &Music::new(),
+        );
+        let reimported = parse(&exported).unwrap();
+
+        assert_eq!(reimported.sprite_sheet.get(0, 64), 2);
+        assert_eq!(reimported.sprite_sheet.get(1, 64), 1);
+        assert_eq!(reimported.map.mget(0, 32), 0x12);
+    }
+
+    // Hides each byte of `rom` in a cart image, on top of a plain grey label.
+    fn encode_png(rom: &[u8]) -> Vec<u8> {
+        let pixels: Vec<u8> = (0..PNG_WIDTH * PNG_HEIGHT)
+            .flat_map(|index| {
+                let byte = rom.get(index).copied().unwrap_or(0);
+                let [a, r, g, b] = [6, 4, 2, 0].map(|shift| (byte >> shift) & 0b11);
+
+                [0x80 | r, 0x80 | g, 0x80 | b, 0xfc | a]
+            })
+            .collect();
+
+        let mut png = vec![];
+        let mut encoder = png::Encoder::new(&mut png, PNG_WIDTH as u32, PNG_HEIGHT as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&pixels)
+            .unwrap();
+
+        png
+    }
+
+    #[test]
+    fn png_cart_data_is_decoded() {
+        let mut rom = vec![0; 0x8000];
+        // Pixels (0, 0) and (1, 0).
+        rom[0x0000] = 0x21;
+        // Pixels (0, 64) and (1, 64), shared with map cell (0, 32).
+        rom[0x1000] = 0xba;
+        // Map cell (1, 1).
+        rom[0x2000 + 128 + 1] = 0xcd;
+        // Flags of sprite 255.
+        rom[0x30ff] = 0x81;
+
+        let cart = parse_png(&encode_png(&rom)).unwrap();
+
+        assert_eq!(
+            (cart.sprite_sheet.get(0, 0), cart.sprite_sheet.get(1, 0)),
+            (1, 2)
+        );
+        assert_eq!(cart.map.mget(1, 1), 0xcd);
+        assert_eq!(cart.map.mget(0, 32), 0xba);
+        assert_eq!(cart.flags.get(255), Some(0x81));
+    }
+
+    #[test]
+    fn png_cart_audio_is_decoded() {
+        let mut rom = vec![0; 0x8000];
+        // Pattern 1: sound 5 on the first channel, loop back, the others disabled.
+        rom[0x3104..0x3108].copy_from_slice(&[0x05, 0xc1, 0x42, 0x43]);
+        // Sound 2, note 0: pitch 29, square, volume 7, fade in.
+        let sound = 0x3200 + 2 * 68;
+        rom[sound..sound + 2].copy_from_slice(&(29_u16 | 3 << 6 | 7 << 9 | 4 << 12).to_le_bytes());
+        rom[sound + 64..sound + 68].copy_from_slice(&[0, 10, 4, 8]);
+
+        let cart = parse_png(&encode_png(&rom)).unwrap();
+
+        let pattern = cart.music.get(1).unwrap();
+        assert_eq!(pattern.channels, [Some(5), None, None, None]);
+        assert!(!pattern.loop_start && pattern.loop_back && !pattern.stop);
+
+        let sound = cart.sfx.get(2).unwrap();
+        assert_eq!((sound.speed, sound.loop_start, sound.loop_end), (10, 4, 8));
+        assert_eq!(
+            sound.notes[0],
+            Note {
+                pitch: 29,
+                instrument: Instrument::Square,
+                volume: 7,
+                effect: Effect::FadeIn,
+            }
+        );
+    }
+
+    #[test]
+    fn png_of_wrong_size_is_rejected() {
+        let mut png = vec![];
+        let mut encoder = png::Encoder::new(&mut png, 1, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&[0; 4])
+            .unwrap();
+
+        assert!(parse_png(&png).is_err());
+    }
+
+    #[test]
+    fn parses_sfx_and_music() {
+        let sfx = format!("000a0000{}", "1d374".repeat(32));
+        let cart = parse(&cart_with_audio(
+            &[],
+            &[],
+            &[],
+            &[sfx.as_str()],
+            &["03 00014243"],
+        ))
+        .unwrap();
+
+        let sound = cart.sfx.get(0).unwrap();
+        assert_eq!(sound.speed, 10);
+        assert_eq!(sound.notes[31].pitch, 0x1d);
+        assert_eq!(cart.sfx.get(1), Some(&Default::default()));
+
+        let pattern = cart.music.get(0).unwrap();
+        assert_eq!(pattern.channels, [Some(0), Some(1), None, None]);
+        assert!(pattern.loop_start && pattern.loop_back);
+    }
+}
@@ -0,0 +1,190 @@
+//! Pico8's pause menu: continue, the cart's own entries (see [`crate::Pico8::menuitem`]),
+//! and resetting the cart.
+use crate::draw_data::DrawData;
+use crate::{colors, Pico8};
+
+/// An entry added with [`Pico8::menuitem`].
+pub(crate) struct MenuItem {
+    pub label: String,
+    /// Returns whether the menu should stay open.
+    pub callback: Box<dyn FnMut(&mut Pico8) -> bool>,
+}
+
+impl std::fmt::Debug for MenuItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MenuItem")
+            .field("label", &self.label)
+            .finish_non_exhaustive()
+    }
+}
+
+/// What choosing a menu entry does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Choice {
+    Continue,
+    /// The index in [`PauseMenu::items`].
+    Item(usize),
+    Reset,
+}
+
+/// Buttons pressed this frame, as far as the menu cares.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct MenuInput {
+    pub up: bool,
+    pub down: bool,
+    pub select: bool,
+    pub pause: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct PauseMenu {
+    open: bool,
+    selected: usize,
+    pub items: [Option<MenuItem>; PauseMenu::ITEMS],
+}
+
+impl PauseMenu {
+    /// How many entries carts can add.
+    pub const ITEMS: usize = 5;
+    /// Labels longer than this are cut, like in pico8.
+    pub const MAX_LABEL_LENGTH: usize = 16;
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+        self.selected = 0;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    fn entries(&self) -> Vec<(Choice, &str)> {
+        let items =
+            self.items.iter().enumerate().filter_map(|(index, item)| {
+                Some((Choice::Item(index), item.as_ref()?.label.as_str()))
+            });
+
+        std::iter::once((Choice::Continue, "continue"))
+            .chain(items)
+            .chain(std::iter::once((Choice::Reset, "reset cart")))
+            .collect()
+    }
+
+    /// Moves the selection, returning what was chosen (if anything).
+    /// The pause button closes the menu again.
+    pub fn navigate(&mut self, input: MenuInput) -> Option<Choice> {
+        let entries: Vec<Choice> = self
+            .entries()
+            .into_iter()
+            .map(|(choice, _)| choice)
+            .collect();
+
+        if input.pause {
+            return Some(Choice::Continue);
+        }
+        if input.up {
+            self.selected = (self.selected + entries.len() - 1) % entries.len();
+        }
+        if input.down {
+            self.selected = (self.selected + 1) % entries.len();
+        }
+
+        // Entries might have been removed while the menu was open.
+        self.selected = self.selected.min(entries.len() - 1);
+
+        input.select.then(|| entries[self.selected])
+    }
+
+    /// Draws the menu in the middle of the screen, on top of what's already there.
+    pub fn draw(&self, draw_data: &mut DrawData) {
+        let entries = self.entries();
+        let longest = entries
+            .iter()
+            .map(|(_, label)| label.chars().count())
+            .max()
+            .unwrap_or(0) as i32;
+
+        let width = 4 * longest + 14;
+        let height = 8 * entries.len() as i32 + 6;
+        let (screen_width, screen_height) = draw_data.size();
+        let x = (screen_width as i32 - width) / 2;
+        let y = (screen_height as i32 - height) / 2;
+
+        draw_data.with_default_state(|draw_data| {
+            draw_data.rectfill(x, y, x + width - 1, y + height - 1, colors::BLACK);
+            draw_data.rect(x, y, x + width - 1, y + height - 1, colors::WHITE);
+
+            for (index, (_, label)) in entries.iter().enumerate() {
+                let line_y = y + 4 + 8 * index as i32;
+
+                if index == self.selected {
+                    draw_data.print(">", x + 4, line_y, colors::WHITE);
+                }
+                draw_data.print(label, x + 10, line_y, colors::WHITE);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Choice, MenuInput, MenuItem, PauseMenu};
+
+    const DOWN: MenuInput = MenuInput {
+        up: false,
+        down: true,
+        select: false,
+        pause: false,
+    };
+    const UP: MenuInput = MenuInput {
+        up: true,
+        down: false,
+        ..DOWN
+    };
+    const SELECT: MenuInput = MenuInput {
+        select: true,
+        down: false,
+        ..DOWN
+    };
+
+    fn item(label: &str) -> Option<MenuItem> {
+        Some(MenuItem {
+            label: label.to_owned(),
+            callback: Box::new(|_| false),
+        })
+    }
+
+    #[test]
+    fn navigates_the_entries() {
+        let mut menu = PauseMenu::default();
+        menu.items[2] = item("sound");
+        menu.open();
+
+        assert_eq!(menu.navigate(SELECT), Some(Choice::Continue));
+        assert_eq!(menu.navigate(DOWN), None);
+        assert_eq!(menu.navigate(SELECT), Some(Choice::Item(2)));
+        menu.navigate(DOWN);
+        assert_eq!(menu.navigate(SELECT), Some(Choice::Reset));
+        // Wraps around.
+        menu.navigate(DOWN);
+        assert_eq!(menu.navigate(SELECT), Some(Choice::Continue));
+        menu.navigate(UP);
+        assert_eq!(menu.navigate(SELECT), Some(Choice::Reset));
+    }
+
+    #[test]
+    fn pause_continues() {
+        let mut menu = PauseMenu::default();
+        menu.open();
+
+        let pause = MenuInput {
+            pause: true,
+            ..MenuInput::default()
+        };
+        assert_eq!(menu.navigate(pause), Some(Choice::Continue));
+    }
+}
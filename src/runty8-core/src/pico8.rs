@@ -1,10 +1,19 @@
 use rand::Rng;
 use std::f32::consts::PI;
 
-use crate::draw_data::DrawData;
-use crate::sprite_sheet::Sprite;
+use crate::cart_data::CartData;
+use crate::clock;
+use crate::draw_data::{ClipRect, DrawData, DEFAULT_PEN};
+use crate::font::CustomFont;
+use crate::memory::{self, Memory};
+use crate::pause_menu::{Choice, MenuInput, MenuItem, PauseMenu};
+use crate::prng::Prng;
+use crate::sprite_sheet::{Sprite, SpriteSheet};
 use crate::state::State;
-use crate::{Button, Color, Resources};
+use crate::{
+    App, Audio, Button, ButtonMap, Buttons, Color, Flags, FrameStats, InputSnapshot, Map, P8Num,
+    Resources, RunConfig,
+};
 
 /// Struct providing an implementation of the pico8 API.
 #[derive(Debug)]
@@ -13,47 +22,151 @@ pub struct Pico8 {
     pub state: State,
     pub resources: Resources,
     new_title: Option<String>,
+    audio: Audio,
+    prng: Prng,
+    frames: u64,
+    // Real time that passed since the previous update, see `Pico8::delta_time`.
+    delta_millis: f64,
+    pending_millis: f64,
+    fps: u32,
+    update_fps: u32,
+    gamepad_deadzone: f32,
+    button_map: ButtonMap,
+    pause_menu: PauseMenu,
+    memory: Memory,
+    cart_data: Option<CartData>,
+    // Set by `Pico8::load`, for `update_app` and `draw_app` to restart the game.
+    cart_loaded: bool,
+    // From `RunConfig::debug_overlay`.
+    debug_overlay: bool,
+    frame_stats: FrameStats,
+    // From `RunConfig::fps_overlay`, then toggled by `Pico8::toggle_fps_overlay`.
+    fps_overlay: bool,
+    // The screen with the overlays on top, see `Pico8::display_buffer`.
+    overlay: Option<DrawData>,
 }
 
 impl Pico8 {
-    pub fn new(resources: Resources) -> Self {
+    pub fn new(mut resources: Resources) -> Self {
+        let memory = Memory::new(&mut resources);
+
         Self {
             draw_data: DrawData::new(),
             state: State::new(),
             resources,
             new_title: None,
+            audio: Audio::default(),
+            // Pico8 starts from a random seed too.
+            prng: Prng::new(rand::thread_rng().gen()),
+            frames: 0,
+            delta_millis: 0.0,
+            pending_millis: 0.0,
+            fps: RunConfig::default().fps,
+            update_fps: RunConfig::default().update_fps(),
+            gamepad_deadzone: RunConfig::default().gamepad_deadzone,
+            button_map: ButtonMap::default(),
+            pause_menu: PauseMenu::default(),
+            memory,
+            cart_data: None,
+            cart_loaded: false,
+            debug_overlay: false,
+            frame_stats: FrameStats::new(),
+            fps_overlay: false,
+            overlay: None,
         }
     }
 }
 
 // Public (Pico8) interface
 impl Pico8 {
+    /// The whole map, e.g. for pathfinding over it. Named so as not to clash with [`Pico8::map`].
+    pub fn tilemap(&self) -> &Map {
+        &self.resources.map
+    }
+
+    /// Like [`Pico8::tilemap`], for changing it: changes show up in the next [`Pico8::map`] draws,
+    /// like with [`Pico8::mset`].
+    pub fn tilemap_mut(&mut self) -> &mut Map {
+        &mut self.resources.map
+    }
+
+    /// The whole sprite sheet.
+    pub fn sprite_sheet(&self) -> &SpriteSheet {
+        &self.resources.sprite_sheet
+    }
+
+    /// Like [`Pico8::sprite_sheet`], for changing it: changes show up in the next sprites drawn,
+    /// like with [`Pico8::sset`].
+    pub fn sprite_sheet_mut(&mut self) -> &mut SpriteSheet {
+        &mut self.resources.sprite_sheet
+    }
+
+    /// The flags of every sprite.
+    pub fn sprite_flags(&self) -> &Flags {
+        &self.resources.sprite_flags
+    }
+
+    /// Like [`Pico8::sprite_flags`], for changing them: changes affect the next [`Pico8::map`]
+    /// draws with a layer, like with [`Pico8::fset`].
+    pub fn sprite_flags_mut(&mut self) -> &mut Flags {
+        &mut self.resources.sprite_flags
+    }
+
     pub fn mget(&self, x: i32, y: i32) -> u8 {
         self.resources.map.mget(x, y)
     }
 
+    /// The bottom half of the map is also the bottom half of the sprite sheet,
+    /// so setting a cell there changes two of its pixels.
     pub fn mset(&mut self, x: i32, y: i32, spr: u8) {
-        self.resources
-            .map
-            .mset(x.try_into().unwrap(), y.try_into().unwrap(), spr);
+        match memory::shared_map_address(x, y) {
+            Some(address) => self.poke(address, spr),
+            None => self.resources.map.mset(x, y, spr),
+        }
     }
 
-    // TODO: Check we do the same left-to-right (or vice versa)
-    // order as pico8
+    /// <https://pico-8.fandom.com/wiki/Fget>
+    ///
+    /// All the flags of `sprite`, flag 0 being the least significant bit.
+    pub fn fget(&self, sprite: usize) -> u8 {
+        self.resources.sprite_flags.fget(sprite)
+    }
+
+    /// Whether `flag` (`0..8`) is set for `sprite`.
     pub fn fget_n(&self, sprite: usize, flag: u8) -> bool {
         self.resources.sprite_flags.fget_n(sprite, flag)
     }
 
+    /// Whether the map sprite under screen pixel (x, y) has `flag` set, the usual
+    /// `fget(mget(x \ 8, y \ 8), flag)` collision check (for a map drawn at (0, 0)).
+    ///
+    /// `false` outside of the map, even if sprite 0 has the flag.
+    pub fn map_flag_at(&self, x: i32, y: i32, flag: u8) -> bool {
+        let map = &self.resources.map;
+        let (cel_x, cel_y) = (
+            x.div_euclid(Sprite::WIDTH as i32),
+            y.div_euclid(Sprite::HEIGHT as i32),
+        );
+        let inside = |cel: i32, size: usize| usize::try_from(cel).map_or(false, |cel| cel < size);
+
+        inside(cel_x, map.width())
+            && inside(cel_y, map.height())
+            && self.fget_n(map.mget(cel_x, cel_y).into(), flag)
+    }
+
     pub fn fset(&mut self, sprite: usize, flag: usize, value: bool) -> u8 {
         self.resources.sprite_flags.fset(sprite, flag, value)
     }
 
-    // TODO: Find a better naming scheme
-    // TODO: Do we need to return the new flags?
+    /// `fset(n, v)`: sets all the flags of `sprite` at once.
     pub fn fset_all(&mut self, sprite: usize, flags: u8) {
         self.resources.sprite_flags.fset_all(sprite, flags);
     }
 
+    /// Whether `button` was pressed this frame.
+    ///
+    /// Like in Pico8, holding the button makes this return `true` again after 15 frames,
+    /// and then every 4 frames.
     pub fn btnp(&self, button: Button) -> bool {
         self.state.button(button).btnp()
     }
@@ -62,6 +175,17 @@ impl Pico8 {
         self.state.button(button).btn()
     }
 
+    /// Every button's `btn` and `btnp`, the mouse and whether a gamepad is in use,
+    /// e.g. for a debugging overlay (see [`RunConfig::debug_overlay`]).
+    pub fn input_snapshot(&self) -> InputSnapshot {
+        InputSnapshot {
+            held: Buttons::from_held(|button| self.btn(button)),
+            pressed: Buttons::from_held(|button| self.btnp(button)),
+            mouse: self.state.mouse(),
+            gamepad: self.state.gamepad_used(),
+        }
+    }
+
     pub fn pal(&mut self, old: Color, new: Color) {
         self.draw_data.pal(old, new);
     }
@@ -70,14 +194,50 @@ impl Pico8 {
         self.draw_data.palt(transparent_color);
     }
 
+    /// `palt(c, t)`: sets whether `color` is skipped when drawing sprites.
+    pub fn palt_(&mut self, color: Color, transparent: bool) {
+        self.draw_data.set_transparent(color, transparent);
+    }
+
+    /// `palt()`: only color 0 is transparent.
+    pub fn reset_palt(&mut self) {
+        self.draw_data.reset_palt();
+    }
+
+    /// `pal(c0, c1, 1)`: shows `old` as `new` on screen, including what's already drawn.
+    ///
+    /// `new` can also be one of pico8's 16 extra colors, `128..=143` (see [`crate::EXTENDED_COLORS`]).
+    pub fn screen_pal(&mut self, old: Color, new: Color) {
+        self.draw_data.screen_pal(old, new);
+    }
+
+    /// `pal()`: resets the draw and screen palettes, and transparency.
     pub fn reset_pal(&mut self) {
         self.draw_data.reset_pal();
     }
 
     pub fn pset(&mut self, x: i32, y: i32, color: Color) {
+        self.draw_data.color(color);
         self.draw_data.pset(x, y, color);
     }
 
+    /// <https://pico-8.fandom.com/wiki/Pget>
+    ///
+    /// The color of the screen pixel at (x, y), ignoring the camera and clipping rectangle.
+    /// 0 outside the screen.
+    pub fn pget(&self, x: i32, y: i32) -> Color {
+        self.draw_data.pget(x, y)
+    }
+
+    /// A stable hash of what's on screen, see [`DrawData::frame_hash`].
+    pub fn frame_hash(&self) -> u64 {
+        self.draw_data.frame_hash()
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Cls>
+    ///
+    /// Clears the screen to `color` (pico8's `cls()` is `cls(0)`), ignoring the camera,
+    /// and resets the clipping rectangle and the print cursor.
     pub fn cls(&mut self, color: Color) {
         self.draw_data.cls_color(color);
     }
@@ -87,8 +247,39 @@ impl Pico8 {
         self.draw_data.camera(x, y)
     }
 
-    pub fn clip(&mut self, _x: i32, _y: i32, _w: i32, _h: i32) {
-        todo!()
+    /// `camera()` with no arguments: resets the camera to (0, 0) and returns the previous offset.
+    pub fn reset_camera(&mut self) -> (i32, i32) {
+        self.draw_data.reset_camera()
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Clip>
+    ///
+    /// Returns the previous clipping rectangle as `(x, y, w, h)`, so it can be restored later.
+    pub fn clip(&mut self, x: i32, y: i32, w: i32, h: i32) -> (i32, i32, i32, i32) {
+        self.clip_(x, y, w, h, false)
+    }
+
+    /// Like [`Pico8::clip`], but with `clip_previous` the new rectangle is
+    /// also clipped to the current one.
+    pub fn clip_(
+        &mut self,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        clip_previous: bool,
+    ) -> (i32, i32, i32, i32) {
+        let previous = self.draw_data.clip(ClipRect { x, y, w, h }, clip_previous);
+
+        (previous.x, previous.y, previous.w, previous.h)
+    }
+
+    /// `clip()` with no arguments: draw to the whole screen again.
+    /// Returns the previous clipping rectangle.
+    pub fn reset_clip(&mut self) -> (i32, i32, i32, i32) {
+        let previous = self.draw_data.reset_clip();
+
+        (previous.x, previous.y, previous.w, previous.h)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -116,59 +307,559 @@ impl Pico8 {
         );
     }
 
+    /// <https://pico-8.fandom.com/wiki/Tline>
+    ///
+    /// Draws a line textured with the map, starting at cell (`mx`, `my`)
+    /// and moving one pixel to the right on the map for each pixel drawn.
+    #[allow(clippy::too_many_arguments)]
+    pub fn tline(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, mx: f32, my: f32) {
+        self.tline_(x0, y0, x1, y1, mx, my, 1.0 / 8.0, 0.0);
+    }
+
+    /// Like [`Pico8::tline`], but moving (`mdx`, `mdy`) cells on the map for each pixel drawn.
+    #[allow(clippy::too_many_arguments)]
+    pub fn tline_(
+        &mut self,
+        x0: i32,
+        y0: i32,
+        x1: i32,
+        y1: i32,
+        mx: f32,
+        my: f32,
+        mdx: f32,
+        mdy: f32,
+    ) {
+        self.draw_data.tline(
+            (x0, y0, x1, y1),
+            (mx, my),
+            (mdx, mdy),
+            &self.resources.map,
+            &self.resources.sprite_sheet,
+        );
+    }
+
     pub fn spr(&mut self, spr: usize, x: i32, y: i32) {
         let spr = self.resources.sprite_sheet.get_sprite(spr);
 
         self.draw_data.spr(spr, x, y);
     }
 
+    /// <https://pico-8.fandom.com/wiki/Spr>
+    ///
+    /// Draws `w`x`h` sprites (starting at `spr` on the sprite sheet) at once,
+    /// flipping the whole region if asked to.
     #[allow(clippy::too_many_arguments)]
     pub fn spr_(&mut self, spr: usize, x: i32, y: i32, w: f32, h: f32, flip_x: bool, flip_y: bool) {
-        let spr = self.resources.sprite_sheet.get_sprite(spr);
+        let sx = (spr % SpriteSheet::SPRITES_PER_ROW * Sprite::WIDTH) as i32;
+        let sy = (spr / SpriteSheet::SPRITES_PER_ROW * Sprite::HEIGHT) as i32;
+        let w = (w * Sprite::WIDTH as f32).max(0.0) as i32;
+        let h = (h * Sprite::HEIGHT as f32).max(0.0) as i32;
 
-        self.draw_data.spr_(spr, x, y, w, h, flip_x, flip_y);
+        self.sspr_(sx, sy, w, h, x, y, w, h, flip_x, flip_y);
     }
 
-    // TODO: Test
+    /// <https://pico-8.fandom.com/wiki/Sspr>
+    ///
+    /// Draws the `sw`x`sh` rectangle of the sprite sheet at (`sx`, `sy`) at (`dx`, `dy`), unscaled.
+    pub fn sspr(&mut self, sx: i32, sy: i32, sw: i32, sh: i32, dx: i32, dy: i32) {
+        self.sspr_(sx, sy, sw, sh, dx, dy, sw, sh, false, false);
+    }
+
+    /// Like [`Pico8::sspr`], but stretched to `dw`x`dh` and optionally flipped.
+    #[allow(clippy::too_many_arguments)]
+    pub fn sspr_(
+        &mut self,
+        sx: i32,
+        sy: i32,
+        sw: i32,
+        sh: i32,
+        dx: i32,
+        dy: i32,
+        dw: i32,
+        dh: i32,
+        flip_x: bool,
+        flip_y: bool,
+    ) {
+        self.draw_data.sspr(
+            &self.resources.sprite_sheet,
+            (sx, sy, sw, sh),
+            (dx, dy, dw, dh),
+            flip_x,
+            flip_y,
+        );
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Sget>
+    ///
+    /// The color of the sprite sheet's pixel at (x, y), 0 outside of the sheet.
+    pub fn sget(&self, x: i32, y: i32) -> Color {
+        self.resources.sprite_sheet.sget(x, y)
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Sset>
+    ///
+    /// Colors the sprite sheet's pixel at (x, y), ignored outside of the sheet.
+    /// Later [`Pico8::spr`] calls draw the new color.
     pub fn sset(&mut self, x: i32, y: i32, color: Color) {
-        if let (Ok(x), Ok(y)) = (x.try_into(), y.try_into()) {
-            self.resources.sprite_sheet.set(x, y, color);
+        self.resources.sprite_sheet.sset(x, y, color);
+
+        // Written again so that the map cell sharing the pixel changes too.
+        if let Some(address) = memory::shared_sprite_address(x, y) {
+            self.poke(address, self.peek(address));
+        }
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Peek>
+    ///
+    /// The sprite sheet, map, flags, draw state and screen are read from where the game keeps them.
+    /// Addresses past `0x7fff` read as 0.
+    pub fn peek(&self, address: usize) -> u8 {
+        self.memory.peek(&self.resources, &self.draw_data, address)
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Poke>
+    ///
+    /// Writes to the sprite sheet, map, flags, draw state and screen change them,
+    /// e.g. `poke(0x5f00 + c, c1)` works like `pal(c, c1)`. Addresses past `0x7fff` are ignored.
+    pub fn poke(&mut self, address: usize, value: u8) {
+        self.memory
+            .poke(&mut self.resources, &mut self.draw_data, address, value);
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Memcpy>
+    ///
+    /// Copies `length` bytes from `source` to `destination`, which may overlap.
+    pub fn memcpy(&mut self, destination: usize, source: usize, length: usize) {
+        let length = memory::length_within(destination, length, memory::SIZE);
+        let bytes: Vec<u8> = (0..length)
+            .map(|offset| self.peek(source.saturating_add(offset)))
+            .collect();
+
+        for (offset, byte) in bytes.into_iter().enumerate() {
+            self.poke(destination + offset, byte);
+        }
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Memset>
+    pub fn memset(&mut self, destination: usize, value: u8, length: usize) {
+        let length = memory::length_within(destination, length, memory::SIZE);
+
+        for address in destination..destination + length {
+            self.poke(address, value);
+        }
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Reload>
+    ///
+    /// Puts the sprite sheet, map and flags back the way they were when the cart was loaded
+    /// (or last stored with [`Pico8::cstore`]).
+    pub fn reload(&mut self) {
+        self.reload_(0, 0, memory::CART_SIZE);
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Reload>
+    ///
+    /// Copies `length` bytes of the cart's data from `source` to `destination` in memory.
+    /// Only the cart's sprite sheet, map and flags (`0x0000..0x3100`) are kept yet,
+    /// music and sound effects read as 0. Reading from another cart isn't supported.
+    pub fn reload_(&mut self, destination: usize, source: usize, length: usize) {
+        let length =
+            memory::length_within(destination, length.min(memory::CART_SIZE), memory::SIZE);
+
+        for offset in 0..length {
+            let byte = self.memory.cart_byte(source.saturating_add(offset));
+            self.poke(destination + offset, byte);
+        }
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Cstore>
+    ///
+    /// Stores the sprite sheet, map and flags in the cart, saving them to the assets path.
+    pub fn cstore(&mut self) {
+        self.cstore_(0, 0, memory::CART_SIZE);
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Cstore>
+    ///
+    /// Copies `length` bytes from `source` in memory to `destination` in the cart,
+    /// then saves the cart's sprite sheet, map and flags to the assets path.
+    /// Music and sound effects aren't saved yet, and neither is writing to another cart.
+    pub fn cstore_(&mut self, destination: usize, source: usize, length: usize) {
+        for offset in 0..memory::length_within(destination, length, memory::CART_SIZE) {
+            let byte = self.peek(source.saturating_add(offset));
+            self.memory.store_cart_byte(destination + offset, byte);
+        }
+
+        self.memory.save_cart(&self.resources);
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Cartdata>
+    ///
+    /// Loads the numbers saved for `id` (lowercase letters, digits and `_`), for [`Pico8::dget`]
+    /// and [`Pico8::dset`]. They're kept in the assets path, under a file named after `id`.
+    /// Returns whether there was saved data, `false` for invalid ids too.
+    pub fn cartdata(&mut self, id: &str) -> bool {
+        if !CartData::is_valid_id(id) {
+            log::warn!("Invalid cartdata id: {id:?}");
+            return false;
+        }
+
+        let (cart_data, found) = CartData::load(&self.resources.assets_path, id);
+        self.cart_data = Some(cart_data);
+
+        found
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Dget>
+    ///
+    /// The number saved at `index` (`0..256`), 0 if nothing was or [`Pico8::cartdata`] wasn't called.
+    pub fn dget(&self, index: usize) -> f32 {
+        self.cart_data
+            .as_ref()
+            .map_or(0.0, |cart_data| cart_data.get(index))
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Dset>
+    ///
+    /// Saves `value` at `index` (`0..256`) right away. Does nothing before [`Pico8::cartdata`].
+    pub fn dset(&mut self, index: usize, value: f32) {
+        match &mut self.cart_data {
+            Some(cart_data) => cart_data.set(&self.resources.assets_path, index, value),
+            None => log::warn!("dset({index}, {value}) called before cartdata"),
         }
     }
 
-    pub fn fillp(&mut self) {
-        todo!()
+    /// <https://pico-8.fandom.com/wiki/Fillp>
+    ///
+    /// `fillp(0)` goes back to solid shapes.
+    pub fn fillp(&mut self, pattern: u16) {
+        self.fillp_(pattern, false);
+    }
+
+    /// Like [`Pico8::fillp`], but with `transparent` the pattern's set bits aren't drawn at all.
+    pub fn fillp_(&mut self, pattern: u16, transparent: bool) {
+        self.draw_data.fillp(pattern, transparent);
     }
 
     pub fn circ(&mut self, x: i32, y: i32, r: i32, color: Color) {
+        self.draw_data.color(color);
         self.draw_data.circ(x, y, r, color);
     }
     pub fn circfill(&mut self, x: i32, y: i32, r: i32, color: Color) {
+        self.draw_data.color(color);
         self.draw_data.circfill(x, y, r, color);
     }
 
+    /// <https://pico-8.fandom.com/wiki/Oval>
+    ///
+    /// Draws the outline of the ellipse that fits in the (x0, y0), (x1, y1) rectangle.
+    pub fn oval(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        self.draw_data.color(color);
+        self.draw_data.oval(x0, y0, x1, y1, color);
+    }
+
+    /// Like [`Pico8::oval`], filled.
+    pub fn ovalfill(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        self.draw_data.color(color);
+        self.draw_data.ovalfill(x0, y0, x1, y1, color);
+    }
+
     pub fn rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        self.draw_data.color(color);
         self.draw_data.rect(x0, y0, x1, y1, color);
     }
 
     pub fn rectfill(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        self.draw_data.color(color);
         self.draw_data.rectfill(x0, y0, x1, y1, color);
     }
 
+    /// <https://pico-8.fandom.com/wiki/Line>
+    ///
+    /// [`Pico8::line_to`] continues from (x1, y1) afterwards.
     pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        self.draw_data.color(color);
         self.draw_data.line(x0, y0, x1, y1, color);
+        self.draw_data.set_line_end(Some((x1, y1)));
+    }
+
+    /// `line(x1, y1)`: draws from the end of the previous line to (x1, y1), for polylines.
+    /// After [`Pico8::reset_line`] it only moves the end without drawing.
+    pub fn line_to(&mut self, x1: i32, y1: i32, color: Color) {
+        self.draw_data.color(color);
+        self.draw_data.line_to(x1, y1, color);
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Load>
+    ///
+    /// Switches to the cart at `cart_path` (see [`Resources::from_path`]), and starts the game
+    /// over on it once the current update or draw returns, with `App::init`.
+    ///
+    /// Like a fresh start, the draw state, memory, time, cart data and menu items are reset,
+    /// and every sound and the music stop. The current cart stays if the new one can't be loaded.
+    pub fn load(&mut self, cart_path: &str) -> Result<(), String> {
+        let mut resources = Resources::from_path(cart_path)?;
+
+        let (width, height) = self.draw_data.size();
+        self.draw_data = DrawData::with_size(width, height);
+        self.memory = Memory::new(&mut resources);
+        self.resources = resources;
+        self.cart_data = None;
+        self.pause_menu = PauseMenu::default();
+        self.stop_music(0);
+        self.stop_sfx(None);
+        self.cart_loaded = true;
+
+        Ok(())
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Color>
+    ///
+    /// Sets the pen color, returning the previous one. Drawing with a color also sets it,
+    /// so [`Pico8::pen_color`] can stand in for pico8's omitted color arguments.
+    pub fn color(&mut self, color: Color) -> Color {
+        self.draw_data.color(color)
     }
 
-    pub fn print(&mut self, text: &str, x: i32, y: i32, color: Color) {
-        self.draw_data.print(text, x, y, color);
+    /// `color()` with no arguments: the pen goes back to color 6. Returns the previous one.
+    pub fn reset_color(&mut self) -> Color {
+        self.draw_data.color(DEFAULT_PEN)
+    }
+
+    /// The pen color: the last one drawn with, or set with [`Pico8::color`].
+    ///
+    /// E.g. pico8's `rect(0, 0, 8, 8)` is `pico8.rect(0, 0, 8, 8, pico8.pen_color())`.
+    pub fn pen_color(&self) -> Color {
+        self.draw_data.pen()
+    }
+
+    /// `line()` with no arguments: the next [`Pico8::line_to`] starts a new polyline.
+    pub fn reset_line(&mut self) {
+        self.draw_data.set_line_end(None);
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Print>
+    ///
+    /// Prints `text` at (x, y), each `\n` starting a new line. Moves the cursor below the text.
+    /// Returns the x coordinate right after the end of the text.
+    ///
+    /// Supports pico8's control codes for colors (`\f`, written `\x0c` in Rust), backgrounds (`\#`, `\x02`),
+    /// cursor movement (`\-`, `\|`, `\+`, `\t`, `\r` and `\b`) and home (`\^g` and `\^h`).
+    /// Other control codes are skipped.
+    ///
+    /// Pico8's wide symbols (P8SCII `0x80` to `0x99`) can be written either as `'\u{80}'` to `'\u{99}'`,
+    /// or as the characters pico8 shows for them, like `"⬅️➡️"` or `"♥"`.
+    pub fn print(&mut self, text: &str, x: i32, y: i32, color: Color) -> i32 {
+        self.draw_data.color(color);
+        self.draw_data.print(text, x, y, color)
+    }
+
+    /// `print(text, color)`: like [`Pico8::print`], at the cursor (see [`Pico8::cursor`]).
+    /// Text that reaches the bottom of the screen scrolls it up, like a console.
+    pub fn print_at_cursor(&mut self, text: &str, color: Color) -> i32 {
+        self.draw_data.color(color);
+        self.draw_data.print_at_cursor(text, color)
+    }
+
+    /// Prints with `font` instead of the built-in font, like pico8's custom font.
+    /// Characters it doesn't have (past `'\x7f'`) still use the built-in font.
+    pub fn set_font(&mut self, font: CustomFont) {
+        self.draw_data.set_font(Some(font));
+    }
+
+    /// Goes back to printing with the built-in font.
+    pub fn reset_font(&mut self) {
+        self.draw_data.set_font(None);
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Cursor>
+    ///
+    /// Sets where [`Pico8::print_at_cursor`] prints next. Returns the previous position.
+    pub fn cursor(&mut self, x: i32, y: i32) -> (i32, i32) {
+        self.draw_data.cursor(x, y)
     }
 
     // audio
-    pub fn sfx(&mut self, _sound_id: u8) {
-        todo!()
+    /// <https://pico-8.fandom.com/wiki/Sfx>
+    ///
+    /// Plays one of the 64 sound effects on a free channel.
+    pub fn sfx(&mut self, sound_id: u8) {
+        self.sfx_(sound_id, None, 0, None);
+    }
+
+    /// Like [`Pico8::sfx`], but on `channel` (`0..4`, or a free one if `None`),
+    /// from note `offset` and for `length` notes (or until the sound ends).
+    pub fn sfx_(
+        &mut self,
+        sound_id: u8,
+        channel: Option<usize>,
+        offset: usize,
+        length: Option<usize>,
+    ) {
+        if let Some(sound) = self.resources.sfx.get(sound_id.into()) {
+            self.audio
+                .mixer()
+                .play(sound_id.into(), sound, channel, offset, length);
+        }
+    }
+
+    /// The sound effect playing on `channel`, like `stat(46 + channel)`.
+    pub fn sfx_playing(&self, channel: usize) -> Option<u8> {
+        self.audio.mixer().playing(channel).map(|sound| sound as u8)
+    }
+
+    /// `sfx(-1, channel)`: stops the sound on `channel`, or on every channel if `None`.
+    pub fn stop_sfx(&mut self, channel: Option<usize>) {
+        self.audio.mixer().stop(channel);
+    }
+    /// <https://pico-8.fandom.com/wiki/Music>
+    ///
+    /// Plays the music starting at `pattern`.
+    pub fn music(&mut self, pattern: u8) {
+        self.music_(pattern, 0, 0);
+    }
+
+    /// Like [`Pico8::music`], but fading in for `fade_millis`,
+    /// and keeping sound effects off of the channels in `channel_mask` unless they're all busy.
+    pub fn music_(&mut self, pattern: u8, fade_millis: u32, channel_mask: u8) {
+        self.audio.mixer().play_music(
+            pattern.into(),
+            &self.resources.sfx,
+            &self.resources.music,
+            fade_millis,
+            channel_mask,
+        );
+    }
+
+    /// `music(-1, fade_millis)`: stops the music, fading out for `fade_millis` first.
+    pub fn stop_music(&mut self, fade_millis: u32) {
+        self.audio.mixer().stop_music(fade_millis);
+    }
+
+    /// The music pattern being played, like `stat(24)`.
+    pub fn music_pattern(&self) -> Option<u8> {
+        self.audio
+            .mixer()
+            .music_pattern()
+            .map(|pattern| pattern as u8)
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Rnd>
+    ///
+    /// A random number in `0..limit`, from the generator seeded by [`Pico8::srand`].
+    /// `rnd()` is `rnd(1.0)`.
+    pub fn rnd(&mut self, limit: f32) -> f32 {
+        self.prng.rnd(limit)
+    }
+
+    /// [`Pico8::rnd`] for [`P8Num`]s, the same numbers without going through `f32`.
+    pub fn rnd_num(&mut self, limit: P8Num) -> P8Num {
+        P8Num::from_bits(self.prng.rnd_bits(limit.to_bits()))
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Sin>
+    ///
+    /// Same as the free [`sin`](crate::sin), for code ported from carts.
+    pub fn sin(&self, turns: f32) -> f32 {
+        sin(turns)
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Cos>
+    ///
+    /// Same as the free [`cos`](crate::cos).
+    pub fn cos(&self, turns: f32) -> f32 {
+        cos(turns)
     }
-    pub fn music(&mut self, _music_id: u8) {
-        todo!()
+
+    /// <https://pico-8.fandom.com/wiki/Atan2>
+    ///
+    /// Same as the free [`atan2`](crate::atan2).
+    pub fn atan2(&self, dx: f32, dy: f32) -> f32 {
+        atan2(dx, dy)
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Srand>
+    ///
+    /// Seeds [`Pico8::rnd`], which then returns the same numbers every time it's given that seed.
+    /// They aren't checked against the numbers pico8 itself gives for it.
+    pub fn srand(&mut self, seed: u32) {
+        self.prng = Prng::new(seed);
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Menuitem>
+    ///
+    /// Adds an entry to the pause menu, in slot `index` (`1..=5`), replacing what was there.
+    /// Choosing it calls `callback`, which returns whether the menu stays open.
+    /// Other indices are ignored, and labels are cut to 16 characters.
+    pub fn menuitem(
+        &mut self,
+        index: u8,
+        label: &str,
+        callback: impl FnMut(&mut Pico8) -> bool + 'static,
+    ) {
+        if let Some(slot) = Self::menuitem_slot(&mut self.pause_menu, index) {
+            *slot = Some(MenuItem {
+                label: label.chars().take(PauseMenu::MAX_LABEL_LENGTH).collect(),
+                callback: Box::new(callback),
+            });
+        }
+    }
+
+    /// `menuitem(index)`: removes the pause menu entry at `index`.
+    pub fn remove_menuitem(&mut self, index: u8) {
+        if let Some(slot) = Self::menuitem_slot(&mut self.pause_menu, index) {
+            *slot = None;
+        }
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Time>
+    ///
+    /// Seconds since the game started, counted in updates (see [`Pico8::update_fps`]).
+    pub fn time(&self) -> f32 {
+        self.frames as f32 / self.update_fps as f32
+    }
+
+    /// Same as [`Pico8::time`].
+    pub fn t(&self) -> f32 {
+        self.time()
+    }
+
+    /// Seconds of real time between the previous update and this one.
+    ///
+    /// Updates still run at a fixed rate, so this is usually `1 / update_fps`. It's longer
+    /// after a hitch, and 0 for extra updates made to catch up on one. For animations that
+    /// follow real time rather than [`Pico8::time`], or for noticing slow frames.
+    pub fn delta_time(&self) -> f32 {
+        (self.delta_millis / 1000.0) as f32
+    }
+
+    /// <https://pico-8.fandom.com/wiki/Stat>
+    ///
+    /// Supported stats, the rest return 0:
+    /// - 7, 8 and 9: the frame rate (frames are never skipped, so all 3 are [`Pico8::fps`]).
+    /// - 16 to 19: the sound effect playing on channels 0 to 3, or -1.
+    /// - 24: the music pattern being played, or -1.
+    /// - 32 and 33: the mouse's position, 34: the mouse buttons (see [`Pico8::mouse_buttons`]).
+    /// - 80 to 85: UTC year, month, day, hour, minute and second.
+    /// - 90 to 95: the same in local time (UTC except on Unix systems).
+    ///
+    /// Memory and CPU usage (0 and 1) aren't measured. The devkit keyboard's stats aren't
+    /// supported either: 30 (whether a key was typed) is always 0, and so is 31, which pico8
+    /// returns the typed character from as a string.
+    pub fn stat(&self, n: u8) -> f32 {
+        let or_minus_one = |value: Option<u8>| value.map_or(-1.0, f32::from);
+
+        match n {
+            7..=9 => self.fps as f32,
+            16..=19 => or_minus_one(self.sfx_playing((n - 16).into())),
+            24 => or_minus_one(self.music_pattern()),
+            32 => self.state.mouse_x as f32,
+            33 => self.state.mouse_y as f32,
+            34 => self.mouse_buttons().into(),
+            80..=85 => clock::now()[usize::from(n - 80)] as f32,
+            90..=95 => clock::local_now()[usize::from(n - 90)] as f32,
+            _ => 0.0,
+        }
     }
 
     // Non-standard stuf
@@ -176,13 +867,39 @@ impl Pico8 {
         self.draw_data.append_camera(x, y);
     }
 
+    /// The mouse's position on the game's screen.
     pub fn mouse(&self) -> (i32, i32) {
         self.state.mouse()
     }
 
+    /// The mouse buttons being held: 1 for the left one, 2 for the right one and 4 for the middle one.
+    pub fn mouse_buttons(&self) -> u8 {
+        self.state.mouse_buttons()
+    }
+
     pub fn set_title(&mut self, new_title: String) {
         self.new_title = Some(new_title);
     }
+
+    /// The frame rate the game is drawn at (see [`RunConfig::fps`]).
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
+
+    /// The rate the game is updated at (see [`RunConfig::update_fps`]).
+    pub fn update_fps(&self) -> u32 {
+        self.update_fps
+    }
+
+    /// How far a gamepad stick has to move to press a direction (see [`RunConfig::gamepad_deadzone`]).
+    pub fn gamepad_deadzone(&self) -> f32 {
+        self.gamepad_deadzone
+    }
+
+    /// Which inputs press which buttons (see [`RunConfig::button_map`]).
+    pub fn button_map(&self) -> &ButtonMap {
+        &self.button_map
+    }
 }
 
 // Utility pub(crate) methods
@@ -201,19 +918,204 @@ impl Pico8 {
         self.draw_data.spr_(sprite, x, y, w, h, flip_x, flip_y);
     }
 
+    /// The sounds being played, for an audio backend to output.
+    pub fn audio(&self) -> Audio {
+        self.audio.clone()
+    }
+
+    /// How many times the game has been updated.
+    pub fn frame_count(&self) -> u64 {
+        self.frames
+    }
+
+    /// Whether the pause menu is open, and the game frozen.
+    pub fn is_paused(&self) -> bool {
+        self.pause_menu.is_open()
+    }
+
+    /// Updates `game`, unless the pause menu is open: then it's the menu that responds to input.
+    /// [`Button::Pause`] opens and closes the menu, and "reset cart" starts `game` over.
+    ///
+    /// Runtimes call this instead of `App::update`, after updating the input.
+    pub fn update_app<Game: App>(&mut self, game: &mut Game) {
+        self.delta_millis = std::mem::take(&mut self.pending_millis);
+
+        if !self.pause_menu.is_open() {
+            if self.btnp(Button::Pause) {
+                self.pause_menu.open();
+            } else {
+                game.update(self);
+                self.advance_frame();
+                self.restart_if_loaded(game);
+            }
+            return;
+        }
+
+        let input = MenuInput {
+            up: self.btnp(Button::Up),
+            down: self.btnp(Button::Down),
+            select: self.btnp(Button::Circle) || self.btnp(Button::Cross),
+            pause: self.btnp(Button::Pause),
+        };
+
+        match self.pause_menu.navigate(input) {
+            None => {}
+            Some(Choice::Continue) => self.pause_menu.close(),
+            Some(Choice::Reset) => {
+                self.pause_menu.close();
+                *game = Game::init(self);
+            }
+            Some(Choice::Item(index)) => {
+                // Taken out while it runs, since it gets to change the menu too.
+                if let Some(mut item) = self.pause_menu.items[index].take() {
+                    let stay_open = (item.callback)(self);
+
+                    self.pause_menu.items[index].get_or_insert(item);
+                    if !stay_open {
+                        self.pause_menu.close();
+                    }
+                    self.restart_if_loaded(game);
+                }
+            }
+        }
+    }
+
+    /// Draws `game`, or the pause menu on top of its last frame while paused.
+    ///
+    /// Runtimes call this instead of `App::draw`.
+    pub fn draw_app<Game: App>(&mut self, game: &mut Game) {
+        if self.pause_menu.is_open() {
+            self.pause_menu.draw(&mut self.draw_data);
+        } else {
+            game.draw(self);
+            self.restart_if_loaded(game);
+        }
+    }
+
+    /// What to show for the last drawn frame: the game's screen, with
+    /// [`RunConfig::debug_overlay`] and [`RunConfig::fps_overlay`] on top when they're on.
+    /// The overlays are drawn on a copy, so the game never sees them through `pget` or memory.
+    /// Runtimes show this after every draw.
+    pub fn display_buffer(&mut self) -> &[u8] {
+        if !self.debug_overlay && !self.fps_overlay {
+            return self.draw_data.buffer();
+        }
+
+        let snapshot = self.input_snapshot();
+        let overlay = self.overlay.get_or_insert_with(DrawData::new);
+        overlay.copy_screen(&self.draw_data);
+        if self.debug_overlay {
+            snapshot.draw(overlay);
+        }
+        if self.fps_overlay {
+            self.frame_stats.draw(overlay);
+        }
+
+        overlay.buffer()
+    }
+
+    /// Counts a drawn frame towards [`Pico8::frame_stats`]: `frame_millis` since the previous
+    /// one, `draw_millis` of which were spent in [`Pico8::draw_app`].
+    /// Runtimes call this after every draw.
+    pub fn record_frame_time(&mut self, frame_millis: f64, draw_millis: f64) {
+        self.frame_stats.record(frame_millis, draw_millis);
+    }
+
+    /// Averages of the last frames' timing, as shown by [`RunConfig::fps_overlay`].
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+
+    /// Shows or hides the [`RunConfig::fps_overlay`]. Runtimes call this on
+    /// [`RunConfig::fps_overlay_key`].
+    pub fn toggle_fps_overlay(&mut self) {
+        self.fps_overlay = !self.fps_overlay;
+    }
+
+    /// Starts `game` over on the cart [`Pico8::load`] switched to, if it was called.
+    fn restart_if_loaded<Game: App>(&mut self, game: &mut Game) {
+        if std::mem::take(&mut self.cart_loaded) {
+            self.frames = 0;
+            *game = Game::init(self);
+        }
+    }
+
+    /// Counts `delta_millis` of real time passing, towards the next [`Pico8::delta_time`].
+    /// Runtimes call this on every tick of their event loop.
+    pub fn advance_real_time(&mut self, delta_millis: f64) {
+        self.pending_millis += delta_millis;
+    }
+
+    /// Counts an update of the game, towards [`Pico8::time`].
+    /// Runtimes call this after each `App::update`.
+    pub fn advance_frame(&mut self) {
+        self.frames += 1;
+    }
+
+    fn menuitem_slot(pause_menu: &mut PauseMenu, index: u8) -> Option<&mut Option<MenuItem>> {
+        let index = usize::from(index).checked_sub(1)?;
+
+        pause_menu.items.get_mut(index)
+    }
+
     pub fn take_new_title(&mut self) -> Option<String> {
         self.new_title.take()
     }
+
+    pub fn apply_config(&mut self, config: &RunConfig) {
+        self.fps = config.fps;
+        self.update_fps = config.update_fps();
+        self.gamepad_deadzone = config.gamepad_deadzone;
+        self.button_map = config.button_map.clone();
+        self.debug_overlay = config.debug_overlay;
+        self.fps_overlay = config.fps_overlay;
+        if let Some(seed) = config.rng_seed {
+            self.srand(seed);
+        }
+
+        let (width, height) = config.resolution;
+        if self.draw_data.size() != (width as usize, height as usize) {
+            self.draw_data.resize(width as usize, height as usize);
+        }
+    }
 }
 
 // Top level functions that pico8 provides that don't modify the global state.
 // cos, sin, etc.
 
 /// Pico8's [`sin`](<https://pico-8.fandom.com/wiki/Sin>) function.
+///
+/// Angles are in turns (`0.25` is a quarter turn), and it's negated compared to [`f32::sin`]
+/// so that with y pointing down on the screen, angles go counterclockwise: `sin(0.25)` is -1.
 pub fn sin(f: f32) -> f32 {
     (-f * 2.0 * PI).sin()
 }
 
+/// Pico8's [`cos`](<https://pico-8.fandom.com/wiki/Cos>) function.
+///
+/// Angles are in turns, like in [`sin`]: `cos(0.5)` is -1.
+pub fn cos(f: f32) -> f32 {
+    (f * 2.0 * PI).cos()
+}
+
+/// Pico8's [`atan2`](<https://pico-8.fandom.com/wiki/Atan2>) function.
+///
+/// The angle of `(dx, dy)` in turns, in `0..1`, going the same way as [`sin`] and [`cos`]:
+/// `atan2(0, -1)` (up on the screen) is `0.25`. `atan2(0, 0)` is `0.25` too, like in pico8.
+pub fn atan2(dx: f32, dy: f32) -> f32 {
+    if dx == 0.0 && dy == 0.0 {
+        return 0.25;
+    }
+
+    let turns = (-dy).atan2(dx) / (2.0 * PI);
+
+    // `rem_euclid` can round up to exactly 1.
+    match turns.rem_euclid(1.0) {
+        turns if turns >= 1.0 => 0.0,
+        turns => turns,
+    }
+}
+
 /// Pico8's [`rnd`](<https://pico-8.fandom.com/wiki/Rnd>) function.
 pub fn rnd(limit: f32) -> f32 {
     rand::thread_rng().gen_range(0.0..limit)
@@ -232,9 +1134,60 @@ pub fn flr(num: f32) -> i32 {
     num.floor() as i32
 }
 
+/// Pico8's [`ceil`](<https://pico-8.fandom.com/wiki/Ceil>) function.
+pub fn ceil(num: f32) -> i32 {
+    num.ceil() as i32
+}
+
+/// Pico8's [`sqrt`](<https://pico-8.fandom.com/wiki/Sqrt>) function.
+///
+/// Negative numbers don't have a square root, and get 0 instead of NaN.
+pub fn sqrt(num: f32) -> f32 {
+    if num <= 0.0 {
+        0.0
+    } else {
+        num.sqrt()
+    }
+}
+
+/// Pico8's [`%`](<https://pico-8.fandom.com/wiki/Math#Modulo>) operator.
+///
+/// Unlike Rust's `%`, the result is never negative: `modulo(-1.0, 8.0)` is 7,
+/// which is what wrapping around a range needs. `modulo(x, 0.0)` is 0.
+pub fn modulo(num: f32, divisor: f32) -> f32 {
+    if divisor == 0.0 {
+        return 0.0;
+    }
+
+    // `rem_euclid` can round up to exactly `|divisor|` for tiny negative numbers.
+    match num.rem_euclid(divisor) {
+        result if result >= divisor.abs() => 0.0,
+        result => result,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{flr, mid, rnd, sin};
+    use super::{atan2, ceil, cos, flr, mid, modulo, rnd, sin, sqrt, P8Num, Pico8};
+    use crate::{
+        colors, App, Button, Input, InputEvent, Key, KeyState, KeyboardEvent, Map, Resources,
+        RunConfig, COLORS,
+    };
+
+    fn pico8() -> Pico8 {
+        Pico8::new(Resources::blank("."))
+    }
+
+    fn pixel(pico8: &Pico8, x: usize, y: usize) -> u32 {
+        buffer_pixel(pico8.draw_data.buffer(), x, y)
+    }
+
+    fn buffer_pixel(buffer: &[u8], x: usize, y: usize) -> u32 {
+        let index = 3 * (x + y * 128);
+        let rgb = &buffer[index..index + 3];
+
+        (rgb[0] as u32) << 16 | (rgb[1] as u32) << 8 | rgb[2] as u32
+    }
 
     macro_rules! assert_delta {
         ($x:expr, $y:expr, $d:expr) => {
@@ -257,6 +1210,85 @@ mod tests {
         assert_delta!(sin(1.0), 0.0, 0.00001);
     }
 
+    #[test]
+    fn cos_works() {
+        assert_eq!(cos(0.0), 1.0);
+        assert_delta!(cos(0.125), 0.70710677, 0.00001);
+        assert_delta!(cos(0.25), 0.0, 0.00001);
+        assert_delta!(cos(0.5), -1.0, 0.00001);
+        assert_delta!(cos(0.75), 0.0, 0.00001);
+    }
+
+    #[test]
+    fn trig_uses_turns_like_pico8() {
+        let pico8 = pico8();
+
+        assert_eq!(pico8.sin(0.25), -1.0);
+        assert_eq!(pico8.cos(0.0), 1.0);
+
+        assert_eq!(pico8.atan2(1.0, 0.0), 0.0);
+        assert_eq!(pico8.atan2(0.0, -1.0), 0.25);
+        assert_eq!(pico8.atan2(-1.0, 0.0), 0.5);
+        assert_eq!(pico8.atan2(0.0, 1.0), 0.75);
+        assert_delta!(pico8.atan2(1.0, -1.0), 0.125, 0.00001);
+        assert_eq!(pico8.atan2(0.0, 0.0), 0.25);
+
+        // Going around with cos and sin gets the same angle back.
+        for step in 0..16 {
+            let angle = step as f32 / 16.0;
+            assert_delta!(atan2(cos(angle), sin(angle)), angle, 0.00001);
+        }
+    }
+
+    #[test]
+    fn math_matches_pico8() {
+        let floors = [(1.5, 1), (-1.5, -2), (2.0, 2), (-0.1, -1), (0.0, 0)];
+        for (num, expected) in floors {
+            assert_eq!(flr(num), expected, "flr({num})");
+        }
+
+        let ceilings = [(1.5, 2), (-1.5, -1), (2.0, 2), (0.1, 1), (-0.9, 0)];
+        for (num, expected) in ceilings {
+            assert_eq!(ceil(num), expected, "ceil({num})");
+        }
+
+        let roots = [
+            (4.0, 2.0),
+            (0.25, 0.5),
+            (0.0, 0.0),
+            (-1.0, 0.0),
+            (-4.0, 0.0),
+        ];
+        for (num, expected) in roots {
+            assert_eq!(sqrt(num), expected, "sqrt({num})");
+        }
+
+        let mids = [
+            ((1.0, 2.0, 3.0), 2.0),
+            ((3.0, 1.0, 2.0), 2.0),
+            // Clamping to a range, either way around.
+            ((-5.0, 0.0, 10.0), 0.0),
+            ((10.0, 15.0, 0.0), 10.0),
+            ((1.0, 1.0, 0.0), 1.0),
+        ];
+        for ((first, second, third), expected) in mids {
+            assert_eq!(mid(first, second, third), expected);
+        }
+
+        let modulos = [
+            ((7.0, 3.0), 1.0),
+            ((-1.0, 8.0), 7.0),
+            ((-9.0, 8.0), 7.0),
+            ((7.0, -3.0), 1.0),
+            ((5.5, 2.0), 1.5),
+            ((-0.5, 1.0), 0.5),
+            ((5.0, 0.0), 0.0),
+        ];
+        for ((num, divisor), expected) in modulos {
+            assert_eq!(modulo(num, divisor), expected, "{num} % {divisor}");
+        }
+    }
+
     #[test]
     fn rnd_works() {
         for _ in 0..100 {
@@ -266,6 +1298,445 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sset_changes_what_spr_draws() {
+        let mut pico8 = pico8();
+
+        // Sprite 17's top left pixel.
+        pico8.sset(8, 8, colors::ORANGE);
+        assert_eq!(pico8.sget(8, 8), colors::ORANGE);
+        assert_eq!(pico8.sget(-1, 8), 0);
+
+        pico8.cls(0);
+        pico8.spr(17, 20, 30);
+        assert_eq!(pico8.pget(20, 30), colors::ORANGE);
+        assert_eq!(pico8.pget(21, 30), 0);
+    }
+
+    #[test]
+    fn srand_repeats_rnd_sequences() {
+        let mut pico8 = pico8();
+
+        pico8.srand(1);
+        let first: Vec<f32> = (0..10).map(|_| pico8.rnd(10.0)).collect();
+        pico8.srand(1);
+        let second: Vec<f32> = (0..10).map(|_| pico8.rnd(10.0)).collect();
+
+        assert_eq!(first, second);
+
+        pico8.srand(1);
+        let numbers: Vec<f32> = (0..10)
+            .map(|_| pico8.rnd_num(P8Num::from(10)).to_f32())
+            .collect();
+        assert_eq!(numbers, first);
+    }
+
+    struct Counter {
+        updates: u32,
+    }
+
+    impl App for Counter {
+        fn init(_: &mut Pico8) -> Self {
+            Self { updates: 0 }
+        }
+
+        fn update(&mut self, _: &mut Pico8) {
+            self.updates += 1;
+        }
+
+        fn draw(&mut self, _: &mut Pico8) {}
+    }
+
+    /// Runs an update of `game`, with `keys` held.
+    fn update_with(pico8: &mut Pico8, game: &mut Counter, input: &mut Input, keys: &[Key]) {
+        for key in [Key::P, Key::C, Key::DownArrow] {
+            let state = if keys.contains(&key) {
+                KeyState::Down
+            } else {
+                KeyState::Up
+            };
+            input.on_event(InputEvent::Keyboard(KeyboardEvent { key, state }));
+        }
+
+        pico8.state.update_input(input);
+        pico8.update_app(game);
+    }
+
+    #[test]
+    fn pausing_stops_updates() {
+        let mut pico8 = pico8();
+        let mut game = Counter::init(&mut pico8);
+        let mut input = Input::new();
+
+        update_with(&mut pico8, &mut game, &mut input, &[]);
+        update_with(&mut pico8, &mut game, &mut input, &[Key::P]);
+        assert!(pico8.is_paused());
+
+        for _ in 0..10 {
+            update_with(&mut pico8, &mut game, &mut input, &[]);
+        }
+        assert_eq!(game.updates, 1);
+        assert_eq!(pico8.frame_count(), 1);
+
+        // "continue" is selected first.
+        update_with(&mut pico8, &mut game, &mut input, &[Key::C]);
+        assert!(!pico8.is_paused());
+        update_with(&mut pico8, &mut game, &mut input, &[]);
+        assert_eq!(game.updates, 2);
+    }
+
+    #[test]
+    fn pause_menu_runs_menuitems_and_resets() {
+        use std::{cell::Cell, rc::Rc};
+
+        let mut pico8 = pico8();
+        let mut game = Counter::init(&mut pico8);
+        let mut input = Input::new();
+        let chosen = Rc::new(Cell::new(0));
+        let chosen_in_callback = Rc::clone(&chosen);
+        pico8.menuitem(1, "cheat", move |_| {
+            chosen_in_callback.set(chosen_in_callback.get() + 1);
+            true
+        });
+        pico8.menuitem(6, "ignored", |_| false);
+
+        update_with(&mut pico8, &mut game, &mut input, &[]);
+        update_with(&mut pico8, &mut game, &mut input, &[Key::P]);
+        update_with(&mut pico8, &mut game, &mut input, &[Key::DownArrow]);
+        update_with(&mut pico8, &mut game, &mut input, &[Key::C]);
+        assert_eq!(chosen.get(), 1);
+        // The callback asked to stay open.
+        assert!(pico8.is_paused());
+
+        update_with(&mut pico8, &mut game, &mut input, &[]);
+        update_with(&mut pico8, &mut game, &mut input, &[Key::DownArrow]);
+        update_with(&mut pico8, &mut game, &mut input, &[Key::C]);
+        assert!(!pico8.is_paused());
+        assert_eq!(game.updates, 0);
+        assert_eq!(chosen.get(), 1);
+    }
+
+    struct Hub {
+        loads: &'static str,
+    }
+
+    impl App for Hub {
+        fn init(pico8: &mut Pico8) -> Self {
+            // Each cart's map says which cart it goes to next.
+            let loads = if pico8.mget(0, 1) == 1 { "b" } else { "a" };
+
+            Self { loads }
+        }
+
+        fn update(&mut self, pico8: &mut Pico8) {
+            let dir = std::env::temp_dir().join("runty8-load-test");
+            pico8.load(dir.join(self.loads).to_str().unwrap()).unwrap();
+        }
+
+        fn draw(&mut self, _: &mut Pico8) {}
+    }
+
+    #[test]
+    fn load_switches_carts_and_restarts_the_game() {
+        let dir = std::env::temp_dir().join("runty8-load-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        for (cart, spr) in [("a", 1), ("b", 2)] {
+            std::fs::create_dir_all(dir.join(cart)).unwrap();
+            let mut map = Map::new();
+            map.mset(0, 1, spr);
+            crate::serialize::serialize(dir.join(cart).to_str().unwrap(), &Map::file_name(), &map);
+        }
+
+        let mut pico8 = pico8();
+        let mut game = Hub::init(&mut pico8);
+        assert_eq!(game.loads, "a");
+
+        pico8.pset(0, 0, 7);
+        pico8.resources.music.get_mut(0).unwrap().channels[0] = Some(2);
+        pico8.music(0);
+        pico8.sfx_(1, Some(3), 0, None);
+        assert_eq!(
+            (pico8.music_pattern(), pico8.sfx_playing(3)),
+            (Some(0), Some(1))
+        );
+        pico8.update_app(&mut game);
+        assert_eq!(pico8.mget(0, 1), 1);
+        assert_eq!(game.loads, "b");
+        assert_eq!(pico8.pget(0, 0), 0);
+        assert_eq!(pico8.time(), 0.0);
+        assert_eq!(pico8.music_pattern(), None);
+        assert_eq!(pico8.sfx_playing(3), None);
+
+        pico8.update_app(&mut game);
+        assert_eq!(pico8.mget(0, 1), 2);
+        assert_eq!(game.loads, "a");
+
+        assert!(pico8.load(dir.join("c.p8").to_str().unwrap()).is_err());
+        assert_eq!(pico8.mget(0, 1), 2);
+    }
+
+    #[test]
+    fn rng_seed_makes_runs_repeat() {
+        let config = RunConfig {
+            rng_seed: Some(42),
+            ..RunConfig::default()
+        };
+        let draws = || {
+            let mut pico8 = pico8();
+            pico8.apply_config(&config);
+
+            [pico8.rnd(1.0), pico8.rnd(100.0), pico8.rnd(5.0)]
+        };
+
+        assert_eq!(draws(), draws());
+
+        let mut seeded = pico8();
+        seeded.srand(42);
+        assert_eq!(seeded.rnd(1.0), draws()[0]);
+    }
+
+    #[test]
+    fn input_snapshot_shows_btn_btnp_and_the_debug_overlay() {
+        let mut pico8 = pico8();
+        let mut game = Counter::init(&mut pico8);
+        let mut input = Input::new();
+
+        update_with(&mut pico8, &mut game, &mut input, &[Key::DownArrow]);
+        update_with(&mut pico8, &mut game, &mut input, &[Key::DownArrow, Key::C]);
+        let snapshot = pico8.input_snapshot();
+        assert!(snapshot.held.held(Button::Down) && !snapshot.pressed.held(Button::Down));
+        assert!(snapshot.held.held(Button::Circle) && snapshot.pressed.held(Button::Circle));
+        assert!(!snapshot.held.held(Button::Left));
+        assert_eq!(snapshot.mouse, (64, 64));
+        assert!(!snapshot.gamepad);
+
+        // Nothing is shown without the overlay.
+        pico8.draw_app(&mut game);
+        assert_eq!(pico8.display_buffer().to_vec(), pico8.draw_data.buffer());
+
+        pico8.apply_config(&RunConfig {
+            debug_overlay: true,
+            ..RunConfig::default()
+        });
+        pico8.draw_app(&mut game);
+        let shown = |pico8: &mut Pico8, x, y| buffer_pixel(pico8.display_buffer(), x, y);
+        // Left, right, up, down, then circle.
+        assert_eq!(
+            shown(&mut pico8, 1, 127 - 4),
+            COLORS[colors::DARK_GREY as usize]
+        );
+        assert_eq!(
+            shown(&mut pico8, 4 * 3 + 1, 127 - 4),
+            COLORS[colors::WHITE as usize]
+        );
+        assert_eq!(
+            shown(&mut pico8, 4 * 4 + 1, 127 - 4),
+            COLORS[colors::RED as usize]
+        );
+        assert_eq!(shown(&mut pico8, 64, 64), COLORS[colors::PINK as usize]);
+        // The game's own screen doesn't have it.
+        assert_eq!(pico8.pget(4 * 3 + 1, 127 - 4), 0);
+        assert_eq!(pico8.pget(64, 64), 0);
+    }
+
+    #[test]
+    fn fps_overlay_is_only_on_the_display_buffer() {
+        let mut pico8 = pico8();
+        let mut game = Counter::init(&mut pico8);
+        pico8.camera(10, 10);
+        pico8.pal(0, 8);
+        pico8.record_frame_time(20.0, 2.0);
+        assert_eq!(pico8.frame_stats().fps(), 50.0);
+
+        pico8.draw_app(&mut game);
+        assert_eq!(pico8.pget(0, 0), 0);
+
+        pico8.toggle_fps_overlay();
+        pico8.draw_app(&mut game);
+        // The camera doesn't move the overlay, and its box stays black.
+        let shown = pico8.display_buffer().to_vec();
+        assert_eq!(buffer_pixel(&shown, 0, 0), COLORS[colors::BLACK as usize]);
+        assert!((0..24).any(|x| buffer_pixel(&shown, x, 2) == COLORS[colors::WHITE as usize]));
+        assert!((0..24).all(|x| pico8.pget(x, 2) == 0));
+
+        pico8.pset(10, 10, 0);
+        assert_eq!(pico8.pget(0, 0), 8);
+    }
+
+    #[test]
+    fn asset_accessors_change_what_is_drawn() {
+        let mut pico8 = pico8();
+        pico8.sprite_sheet_mut().sset(8, 0, 9);
+        pico8.tilemap_mut().mset(0, 2, 1);
+        pico8.sprite_flags_mut().fset(1, 4, true);
+
+        assert_eq!(pico8.tilemap().mget(0, 2), 1);
+        assert_eq!(pico8.sprite_sheet().sget(8, 0), 9);
+        assert!(pico8.sprite_flags().fget_n(1, 4));
+
+        pico8.map(0, 2, 0, 0, 1, 1, 1 << 4);
+        assert_eq!(pico8.pget(0, 0), 9);
+    }
+
+    #[test]
+    fn map_flag_at_checks_the_cell_under_a_pixel() {
+        let mut pico8 = pico8();
+        pico8.mset(2, 3, 5);
+        pico8.fset(5, 1, true);
+        pico8.fset(0, 1, true);
+
+        assert!(pico8.map_flag_at(2 * 8 + 7, 3 * 8, 1));
+        assert!(!pico8.map_flag_at(2 * 8 + 7, 3 * 8, 0));
+        pico8.fset(5, 1, false);
+        assert!(!pico8.map_flag_at(2 * 8 + 7, 3 * 8, 1));
+
+        // Sprite 0 has the flag, but there's no map out here.
+        assert!(pico8.map_flag_at(4 * 8, 3 * 8, 1));
+        assert!(!pico8.map_flag_at(-1, 3 * 8, 1));
+        assert!(!pico8.map_flag_at(128 * 8, 3 * 8, 1));
+        assert!(!pico8.map_flag_at(0, 64 * 8, 1));
+    }
+
+    #[test]
+    fn drawing_sets_the_pen_color() {
+        let mut pico8 = pico8();
+        assert_eq!(pico8.pen_color(), 6);
+
+        pico8.circfill(20, 20, 2, 9);
+        assert_eq!(pico8.pen_color(), 9);
+        pico8.rect(0, 0, 4, 4, pico8.pen_color());
+        assert_eq!(pico8.pget(4, 0), 9);
+
+        assert_eq!(pico8.color(12), 9);
+        assert_eq!(pico8.reset_color(), 12);
+        assert_eq!(pico8.pen_color(), 6);
+    }
+
+    #[test]
+    fn color_sets_the_pen_for_the_next_primitives() {
+        let mut pico8 = pico8();
+        assert_eq!(pico8.color(8), 6);
+
+        pico8.pset(0, 0, pico8.pen_color());
+        pico8.line_to(3, 3, pico8.pen_color());
+        assert_eq!(pico8.pget(0, 0), 8);
+        assert_eq!(pico8.pen_color(), 8);
+    }
+
+    #[test]
+    fn reversed_rects_are_drawn() {
+        let mut pico8 = pico8();
+        pico8.rectfill(5, 6, 2, 3, 8);
+        pico8.rect(20, 9, 10, 0, 9);
+
+        assert_eq!(pico8.pget(2, 3), 8);
+        assert_eq!(pico8.pget(5, 6), 8);
+        assert_eq!(pico8.pget(10, 9), 9);
+        assert_eq!(pico8.pget(20, 0), 9);
+        assert_eq!(pico8.pget(15, 5), 0);
+    }
+
+    #[test]
+    fn line_to_continues_the_last_line() {
+        let mut pico8 = pico8();
+        pico8.line(0, 0, 3, 0, 7);
+        pico8.line_to(3, 2, 8);
+        assert_eq!(pico8.pget(3, 1), 8);
+        assert_eq!(pico8.pget(3, 2), 8);
+
+        // A new polyline only starts at the first point.
+        pico8.reset_line();
+        pico8.line_to(10, 10, 9);
+        pico8.line_to(12, 10, 9);
+        assert_eq!(pico8.pget(9, 9), 0);
+        assert_eq!(pico8.pget(10, 10), 9);
+        assert_eq!(pico8.pget(12, 10), 9);
+        assert_eq!(pico8.pget(4, 2), 0);
+    }
+
+    #[test]
+    fn cls_resets_the_clip_and_the_cursor() {
+        let mut pico8 = pico8();
+        pico8.clip(10, 10, 20, 20);
+        pico8.cursor(40, 50);
+        pico8.camera(5, 5);
+
+        pico8.cls(3);
+        assert_eq!(pico8.reset_clip(), (0, 0, 128, 128));
+        assert_eq!(pico8.cursor(0, 0), (0, 0));
+        assert_eq!(pico8.pget(0, 0), 3);
+        assert_eq!(pico8.pget(127, 127), 3);
+    }
+
+    #[test]
+    fn delta_time_follows_real_time() {
+        let mut pico8 = pico8();
+        let mut game = Counter::init(&mut pico8);
+
+        pico8.advance_real_time(20.0);
+        pico8.advance_real_time(13.0);
+        pico8.update_app(&mut game);
+        assert_delta!(pico8.delta_time(), 0.033, 0.00001);
+
+        // A second update for the same ticks, catching up.
+        pico8.update_app(&mut game);
+        assert_eq!(pico8.delta_time(), 0.0);
+        assert_eq!(game.updates, 2);
+    }
+
+    #[test]
+    fn time_counts_updates() {
+        let mut pico8 = pico8();
+        assert_eq!(pico8.time(), 0.0);
+
+        for _ in 0..30 {
+            pico8.advance_frame();
+        }
+
+        assert_eq!(pico8.frame_count(), 30);
+        assert_delta!(pico8.time(), 1.0, 0.00001);
+
+        pico8.apply_config(&RunConfig {
+            fps: 60,
+            ..RunConfig::default()
+        });
+        assert_delta!(pico8.t(), 0.5, 0.00001);
+    }
+
+    #[test]
+    fn stat_reports_mouse_audio_and_date() {
+        let mut pico8 = pico8();
+        pico8.state.on_mouse_move(10, 20);
+
+        assert_eq!(
+            (pico8.stat(32), pico8.stat(33), pico8.stat(34)),
+            (10.0, 20.0, 0.0)
+        );
+        assert_eq!(pico8.stat(8), 30.0);
+        assert_eq!(pico8.stat(16), -1.0);
+        assert_eq!(pico8.stat(24), -1.0);
+        assert!(pico8.stat(80) >= 2023.0);
+        assert!((1.0..=12.0).contains(&pico8.stat(81)));
+        assert!((0.0..24.0).contains(&pico8.stat(93)));
+        assert_eq!(pico8.stat(30), 0.0);
+        assert_eq!(pico8.stat(200), 0.0);
+    }
+
+    #[test]
+    fn pget_reads_back_pset() {
+        let mut pico8 = pico8();
+
+        pico8.pset(3, 4, 9);
+        pico8.pal(9, 2);
+        pico8.pset(5, 6, 9);
+
+        assert_eq!(pico8.pget(3, 4), 9);
+        assert_eq!(pico8.pget(5, 6), 2);
+        assert_eq!(pico8.pget(0, 0), 0);
+        assert_eq!(pico8.pget(-1, 4), 0);
+        assert_eq!(pico8.pget(3, 128), 0);
+    }
+
     #[test]
     fn mid_works() {
         assert_delta!(mid(8.0, 2.0, 4.0), 4.0, 0.00001);
@@ -280,4 +1751,26 @@ mod tests {
         assert_eq!(flr(7.0), 7);
         assert_eq!(flr(-7.0), -7);
     }
+
+    #[test]
+    fn spr_draws_flipped_regions() {
+        let mut pico8 = pico8();
+        // Sprites 1 and 2 side by side, with a red pixel in the top left of 1
+        // and a blue one in the bottom right of 2.
+        pico8.sset(8, 0, colors::RED);
+        pico8.sset(23, 7, colors::BLUE);
+
+        pico8.spr_(1, 0, 0, 2.0, 1.0, false, false);
+        pico8.spr_(1, 0, 8, 2.0, 1.0, true, false);
+        pico8.spr_(1, 0, 16, 2.0, 1.0, true, true);
+
+        assert_eq!(pixel(&pico8, 0, 0), COLORS[colors::RED as usize]);
+        assert_eq!(pixel(&pico8, 15, 7), COLORS[colors::BLUE as usize]);
+
+        assert_eq!(pixel(&pico8, 15, 8), COLORS[colors::RED as usize]);
+        assert_eq!(pixel(&pico8, 0, 15), COLORS[colors::BLUE as usize]);
+
+        assert_eq!(pixel(&pico8, 15, 23), COLORS[colors::RED as usize]);
+        assert_eq!(pixel(&pico8, 0, 16), COLORS[colors::BLUE as usize]);
+    }
 }
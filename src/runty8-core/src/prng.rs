@@ -0,0 +1,101 @@
+/// A seedable random number generator, following the algorithm pico8 emulators (zepto8)
+/// use for `rnd`. It hasn't been checked against numbers from pico8 itself, so seeded games
+/// aren't known to get the same numbers they do in pico8.
+///
+/// Its state is two 32 bit words, starting from a seed in pico8's 16.16 fixed point.
+#[derive(Clone, Debug)]
+pub(crate) struct Prng {
+    a: u32,
+    b: u32,
+}
+
+impl Prng {
+    // What zepto8 replaces a seed of 0 with.
+    const ZERO_SEED: u32 = 0xdead_beef;
+
+    /// The generator `srand(seed)` leaves behind.
+    pub(crate) fn new(seed: u32) -> Self {
+        // `seed` is an integer, pico8 works with its 16.16 fixed point bits.
+        let a = match seed << 16 {
+            0 => Self::ZERO_SEED,
+            bits => bits,
+        };
+        let mut prng = Self {
+            a,
+            b: a ^ 0xbead_29ba,
+        };
+
+        for _ in 0..32 {
+            prng.next_bits();
+        }
+
+        prng
+    }
+
+    fn next_bits(&mut self) -> u32 {
+        self.b = self.b.rotate_left(16).wrapping_add(self.a);
+        self.a = self.a.wrapping_add(self.b);
+
+        self.b
+    }
+
+    /// A random number in `0..limit`, with the 16 bits of fractional precision pico8 has.
+    ///
+    /// Limits that are negative (or below pico8's precision) return 0.
+    pub(crate) fn rnd(&mut self, limit: f32) -> f32 {
+        self.rnd_bits((limit * 65536.0) as i32) as f32 / 65536.0
+    }
+
+    /// [`Prng::rnd`] with the 16.16 bits of the limit and result.
+    pub(crate) fn rnd_bits(&mut self, limit_bits: i32) -> i32 {
+        let bits = self.next_bits();
+
+        if limit_bits <= 0 {
+            0
+        } else {
+            (bits % limit_bits as u32) as i32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Prng;
+
+    #[test]
+    fn seeded_sequences_repeat() {
+        let mut first = Prng::new(42);
+        let mut second = Prng::new(42);
+
+        for _ in 0..100 {
+            assert_eq!(first.rnd(100.0), second.rnd(100.0));
+        }
+        assert_ne!(Prng::new(1).rnd(100.0), Prng::new(2).rnd(100.0));
+    }
+
+    #[test]
+    fn seeded_sequences_dont_change() {
+        // Pinned from this implementation to catch accidental changes, not captured from pico8.
+        let mut prng = Prng::new(0);
+        let bits: Vec<u32> = (0..4).map(|_| prng.next_bits()).collect();
+
+        assert_eq!(bits, [0x24b8_8474, 0xebfc_5724, 0xaaa9_758c, 0x73ba_a9c5]);
+
+        let mut prng = Prng::new(1);
+        let values: Vec<f32> = (0..3).map(|_| prng.rnd(100.0)).collect();
+
+        assert_eq!(values, [27.080917, 61.988647, 76.59329]);
+    }
+
+    #[test]
+    fn rnd_stays_in_range() {
+        let mut prng = Prng::new(7);
+
+        for _ in 0..1000 {
+            let value = prng.rnd(3.5);
+            assert!((0.0..3.5).contains(&value));
+        }
+        assert_eq!(prng.rnd(-1.0), 0.0);
+        assert_eq!(prng.rnd(0.0), 0.0);
+    }
+}
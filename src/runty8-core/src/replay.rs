@@ -0,0 +1,139 @@
+use crate::serialize::Serialize;
+use crate::{Button, Input};
+use itertools::Itertools;
+
+/// The buttons held during a frame.
+///
+/// Stored like pico8's `btn()` bitfield (left, right, up, down, O and X from the lowest bit),
+/// with pause and the left mouse button after them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Buttons(u8);
+
+impl Buttons {
+    pub(crate) const ORDER: [Button; 8] = [
+        Button::Left,
+        Button::Right,
+        Button::Up,
+        Button::Down,
+        Button::Circle,
+        Button::Cross,
+        Button::Pause,
+        Button::Mouse,
+    ];
+
+    /// The buttons `input` currently holds.
+    pub fn from_input(input: &Input) -> Self {
+        Self::from_held(|button| input.button(button).unwrap_or(false))
+    }
+
+    pub fn from_held(held: impl Fn(Button) -> bool) -> Self {
+        Self(
+            (0..)
+                .zip(Self::ORDER)
+                .filter(|&(_, button)| held(button))
+                .fold(0, |bits, (bit, _)| bits | 1 << bit),
+        )
+    }
+
+    pub fn held(self, button: Button) -> bool {
+        let bit = Self::ORDER
+            .iter()
+            .position(|&other| other == button)
+            .unwrap();
+
+        self.0 & 1 << bit != 0
+    }
+}
+
+/// A recording of the buttons held during each frame of a playthrough.
+///
+/// Record live input with [`Replay::record`] once per update,
+/// and play it back by passing each frame to `pico8.state.update_buttons` instead of the live input.
+/// Only buttons are recorded, not the mouse position.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Replay {
+    frames: Vec<Buttons>,
+}
+
+impl Replay {
+    pub fn file_name() -> String {
+        "replay.txt".to_owned()
+    }
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a frame with the buttons `input` currently holds.
+    pub fn record(&mut self, input: &Input) {
+        self.push(Buttons::from_input(input));
+    }
+
+    pub fn push(&mut self, buttons: Buttons) {
+        self.frames.push(buttons);
+    }
+
+    pub fn get(&self, frame: usize) -> Option<Buttons> {
+        self.frames.get(frame).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Reads a replay written by [`Serialize::serialize`]:
+    /// one line per frame, with the [`Buttons`] bitfield in hex.
+    pub fn deserialize(file_contents: &str) -> Result<Self, String> {
+        let frames = file_contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                u8::from_str_radix(line, 16)
+                    .map(Buttons)
+                    .map_err(|_| format!("Invalid replay frame: {line:?}"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { frames })
+    }
+}
+
+impl Serialize for Replay {
+    fn serialize(&self) -> String {
+        self.frames
+            .iter()
+            .map(|buttons| format!("{:02x}", buttons.0))
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Buttons, Replay};
+    use crate::serialize::Serialize;
+    use crate::Button;
+
+    #[test]
+    fn buttons_use_the_btn_bitfield() {
+        let buttons = Buttons::from_held(|button| matches!(button, Button::Right | Button::Cross));
+
+        assert_eq!(buttons, Buttons(0b10_0010));
+        assert!(buttons.held(Button::Right));
+        assert!(!buttons.held(Button::Circle));
+    }
+
+    #[test]
+    fn replays_round_trip() {
+        let mut replay = Replay::new();
+        replay.push(Buttons::default());
+        replay.push(Buttons::from_held(|button| button == Button::Mouse));
+
+        assert_eq!(Replay::deserialize(&replay.serialize()).unwrap(), replay);
+        assert!(Replay::deserialize("00\nzz").is_err());
+    }
+}
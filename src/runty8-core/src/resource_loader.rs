@@ -0,0 +1,157 @@
+//! Where a game's assets come from, see [`Resources::from_loader`].
+
+use crate::{Flags, Map, Music, Resources, Sfx, SpriteSheet};
+use std::collections::HashMap;
+
+/// A source of asset files: a directory ([`FileLoader`]), bytes in memory ([`BytesLoader`]),
+/// or anything else, like an archive or the network.
+///
+/// Only [`ResourceLoader::load_file`] needs implementing, the assets are parsed from the files
+/// it returns by default.
+pub trait ResourceLoader {
+    /// The contents of the asset file called `file_name` (e.g. [`Map::file_name`]),
+    /// or `None` if there's no such file, which gets a blank asset.
+    fn load_file(&self, file_name: &str) -> Result<Option<String>, String>;
+
+    fn load_sprite_sheet(&self) -> Result<SpriteSheet, String> {
+        load(self, &SpriteSheet::file_name(), SpriteSheet::deserialize)
+    }
+
+    fn load_flags(&self) -> Result<Flags, String> {
+        load(self, &Flags::file_name(), Flags::deserialize)
+    }
+
+    fn load_map(&self) -> Result<Map, String> {
+        load(self, &Map::file_name(), Map::deserialize)
+    }
+
+    fn load_sfx(&self) -> Result<Sfx, String> {
+        load(self, &Sfx::file_name(), Sfx::deserialize)
+    }
+
+    fn load_music(&self) -> Result<Music, String> {
+        load(self, &Music::file_name(), Music::deserialize)
+    }
+}
+
+fn load<Loader: ResourceLoader + ?Sized, T: Default>(
+    loader: &Loader,
+    file_name: &str,
+    deserialize: fn(&str) -> Result<T, String>,
+) -> Result<T, String> {
+    match loader.load_file(file_name)? {
+        Some(contents) => {
+            deserialize(&contents).map_err(|error| format!("Invalid {file_name}: {error}"))
+        }
+        None => Ok(T::default()),
+    }
+}
+
+/// Loads the asset files in a directory while the game runs.
+/// On the web they're loaded from local storage instead.
+#[derive(Clone, Debug)]
+pub struct FileLoader {
+    assets_path: String,
+}
+
+impl FileLoader {
+    pub fn new(assets_path: &str) -> Self {
+        Self {
+            assets_path: assets_path.to_owned(),
+        }
+    }
+}
+
+impl ResourceLoader for FileLoader {
+    fn load_file(&self, file_name: &str) -> Result<Option<String>, String> {
+        let file_path = format!("{}/{file_name}", self.assets_path);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        return match std::fs::read_to_string(&file_path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(format!("Couldn't read {file_path}: {error}")),
+        };
+
+        #[cfg(target_arch = "wasm32")]
+        Ok(crate::load(&file_path))
+    }
+}
+
+/// Asset files that are already in memory, e.g. embedded with `include_bytes!`
+/// or downloaded.
+#[derive(Clone, Debug, Default)]
+pub struct BytesLoader {
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl BytesLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the file called `file_name` (e.g. [`Map::file_name`]).
+    pub fn with_file(mut self, file_name: &str, contents: impl Into<Vec<u8>>) -> Self {
+        self.files.insert(file_name.to_owned(), contents.into());
+        self
+    }
+}
+
+impl ResourceLoader for BytesLoader {
+    fn load_file(&self, file_name: &str) -> Result<Option<String>, String> {
+        self.files
+            .get(file_name)
+            .map(|contents| {
+                String::from_utf8(contents.clone())
+                    .map_err(|_| format!("{file_name} isn't valid UTF-8"))
+            })
+            .transpose()
+    }
+}
+
+impl Resources {
+    /// Loads the sprite sheet, flags, map, sound effects and music from `loader`.
+    /// They'll be saved to `assets_path`, like with [`Resources::from_dir`].
+    pub fn from_loader(assets_path: &str, loader: &impl ResourceLoader) -> Result<Self, String> {
+        Ok(Self {
+            assets_path: assets_path.to_owned(),
+            sprite_sheet: loader.load_sprite_sheet()?,
+            sprite_flags: loader.load_flags()?,
+            map: loader.load_map()?,
+            sfx: loader.load_sfx()?,
+            music: loader.load_music()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BytesLoader, ResourceLoader};
+    use crate::serialize::Serialize;
+    use crate::{Flags, Map, Resources};
+
+    #[test]
+    fn bytes_loader_loads_the_files_it_has() {
+        let mut map = Map::new();
+        map.mset(3, 4, 5);
+        let loader = BytesLoader::new().with_file(&Map::file_name(), map.serialize());
+
+        let resources = Resources::from_loader("assets", &loader).unwrap();
+        assert_eq!(resources.map.mget(3, 4), 5);
+        assert_eq!(resources.sprite_flags.fget(0), 0);
+        assert_eq!(resources.assets_path, "assets");
+    }
+
+    #[test]
+    fn bytes_loader_names_bad_files() {
+        let loader = BytesLoader::new()
+            .with_file(&Flags::file_name(), "not flags")
+            .with_file(&Map::file_name(), vec![0xff]);
+
+        assert!(loader
+            .load_flags()
+            .unwrap_err()
+            .contains(&Flags::file_name()));
+        assert!(loader.load_map().unwrap_err().contains(&Map::file_name()));
+    }
+}
@@ -0,0 +1,451 @@
+use crate::{ButtonMap, Key};
+
+/// Settings used when running a Runty8 game.
+///
+/// Use [`RunConfig::default`] to get Pico8's defaults, and override what you need:
+///
+/// ```
+/// use runty8_core::RunConfig;
+///
+/// let config = RunConfig {
+///     fps: 60,
+///     ..RunConfig::default()
+/// };
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct RunConfig {
+    /// How many times per second `draw` gets called.
+    ///
+    /// Pico8 supports `30` (`_update`) and `60` (`_update60`).
+    pub fps: u32,
+    /// How many times per second `update` gets called.
+    ///
+    /// `None` means the same as [`RunConfig::fps`].
+    /// Use this to step the simulation at a different rate than it is drawn,
+    /// e.g. update at 60 fps while drawing at 30.
+    pub update_fps: Option<u32>,
+    /// Initial title of the window (or the page, in wasm).
+    pub title: String,
+    /// Icon of the window, `None` for the system's default one. Ignored on the web.
+    pub icon: Option<WindowIcon>,
+    /// Size of the game's screen, in pixels.
+    ///
+    /// Pico8 carts expect its `128` by `128` screen, the default.
+    /// Bigger (or non-square) screens are for games written for Runty8.
+    pub resolution: (u32, u32),
+    /// Initial size of the window, in logical pixels.
+    pub window_size: (u32, u32),
+    /// Whether the game starts in a window or covering the whole screen.
+    ///
+    /// Defaults to [`WindowMode::Fullscreen`] with the `steamdeck` feature.
+    pub window_mode: WindowMode,
+    /// Key that switches between a window and fullscreen, `None` to disable it.
+    ///
+    /// Scaling (see [`RunConfig::scaling`]) follows the new size of the window.
+    /// Going back to a window restores its previous size.
+    pub fullscreen_key: Option<Key>,
+    /// Whether drawing waits for the display's refresh, which avoids tearing.
+    ///
+    /// Turning it off lowers latency. Either way, [`RunConfig::fps`] limits how often
+    /// the game updates and draws; vsync only decides when a drawn frame gets shown.
+    /// Browsers always sync to the display.
+    pub vsync: bool,
+    /// How the event loop waits for the next frame, see [`FramePacing`].
+    pub frame_pacing: FramePacing,
+    /// Whether the system cursor is shown on top of the game.
+    pub cursor_visible: bool,
+    /// How the game's screen is scaled to fit the window.
+    pub scaling: ScalingMode,
+    /// Color of the window area not covered by the game's screen, as `0xRRGGBB`. Black by default.
+    ///
+    /// Only visible when the screen is letterboxed by [`ScalingMode::IntegerPixelPerfect`].
+    pub border_color: u32,
+    /// Built-in effect applied to the screen, before [`RunConfig::fragment_shaders`].
+    pub display_filter: DisplayFilter,
+    /// Fragment shaders the screen goes through before being shown, in order,
+    /// e.g. for CRT effects. Empty means showing the screen as is.
+    ///
+    /// Each shader's source is prefixed with the GLSL `#version` line
+    /// (`410` natively, or `330` and `300 es` when only an older graphics context is available,
+    /// `300 es` on the web), and can use:
+    /// - `in vec2 v_tex_coords`: the position being drawn, from `(0, 0)` at the top left to `(1, 1)`.
+    /// - `uniform sampler2D tex`: the game's screen for the first shader,
+    ///   and the previous shader's output (at the size of `resolution`) for the rest.
+    /// - `uniform vec2 resolution`: the size of the drawn area, in physical pixels.
+    /// - `uniform float time`: seconds since the game started.
+    /// - `uniform float screen_width` and `screen_height`: the [`RunConfig::resolution`].
+    ///
+    /// and has to write the pixel's color to an `out vec4` variable.
+    /// See `examples/shaders/crt.frag` for an example.
+    pub fragment_shaders: Vec<String>,
+    /// Whether the screen's rows are flipped when drawn, because its pixels start with the
+    /// top row (as [`crate::DrawData`]'s do). On by default.
+    ///
+    /// Turn it off for a buffer that starts with the bottom row, e.g. from an external renderer.
+    /// The first shader's `v_tex_coords` then start from `(0, 0)` at the bottom left.
+    pub flip_screen_y: bool,
+    /// How far (from `0.0` to `1.0`) a gamepad stick has to move before it presses a direction.
+    pub gamepad_deadzone: f32,
+    /// Which keys and gamepad buttons press which Pico8 buttons.
+    pub button_map: ButtonMap,
+    /// Key that saves a screenshot of the game, `None` to disable screenshots.
+    pub screenshot_key: Option<Key>,
+    /// Size screenshots are saved at.
+    pub screenshot_scale: ScreenshotScale,
+    /// Key that saves the last [`RunConfig::gif_length_secs`] seconds as a GIF,
+    /// `None` to disable recording.
+    pub gif_key: Option<Key>,
+    /// How many seconds of gameplay are kept for [`RunConfig::gif_key`].
+    pub gif_length_secs: u32,
+    /// Frames per second of recorded GIFs.
+    pub gif_fps: u32,
+    /// Directory screenshots and GIFs are saved to.
+    ///
+    /// `None` means the game's assets directory.
+    pub capture_dir: Option<String>,
+    /// Whether the sprite sheet, map and flags are reloaded while the game runs
+    /// when their files in the assets directory change, e.g. from an external editor.
+    ///
+    /// For development: it keeps checking the files, so leave it off in release builds.
+    /// Not supported on the web.
+    pub hot_reload: bool,
+    /// What the random number generator starts from, as if the game began with `srand(seed)`.
+    /// `None` picks a random seed every time, so games vary between runs.
+    ///
+    /// A fixed seed makes [`crate::Pico8::rnd`] give the same numbers on every run,
+    /// e.g. for reproducing a bug or replaying recorded input.
+    pub rng_seed: Option<u32>,
+    /// Whether the state of the buttons, the mouse and the gamepad is drawn on top of
+    /// every frame (see [`crate::InputSnapshot`]), for checking input mappings on a device.
+    pub debug_overlay: bool,
+    /// Whether the game starts with the frame rate, the time between frames and the time
+    /// spent drawing them (averaged over the last 30 frames) shown in the top left corner.
+    pub fps_overlay: bool,
+    /// Key that shows or hides the [`RunConfig::fps_overlay`], `None` to disable it.
+    pub fps_overlay_key: Option<Key>,
+}
+
+/// An image for [`RunConfig::icon`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowIcon {
+    pub width: u32,
+    pub height: u32,
+    /// The pixels row by row from the top left, 4 bytes each: red, green, blue and alpha
+    /// (`0` is transparent). That's `4 * width * height` bytes.
+    pub rgba: Vec<u8>,
+}
+
+/// Size screenshots are saved at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenshotScale {
+    /// The game's [`RunConfig::resolution`].
+    Native,
+    /// The largest whole multiple of the game's resolution that fits the window.
+    Window,
+}
+
+/// Built-in effects for the game's screen (see [`RunConfig::display_filter`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisplayFilter {
+    /// Shows the screen as is.
+    None,
+    /// Looks like an old CRT screen.
+    Crt {
+        /// How dark the lines between pixel rows are, from `0.0` (not at all) to `1.0` (black).
+        scanline_strength: f32,
+        /// How much the screen bulges out, from `0.0` (flat) to `1.0`.
+        curvature: f32,
+    },
+}
+
+/// Whether the game is shown in a window (see [`RunConfig::window_mode`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowMode {
+    /// A regular window, [`RunConfig::window_size`] big.
+    Windowed,
+    /// A borderless window covering the current monitor.
+    Fullscreen,
+}
+
+/// How the event loop waits between frames.
+///
+/// Both only decide how often the loop wakes up: each wakeup runs as many updates
+/// and draws as [`RunConfig::fps`] and [`RunConfig::update_fps`] call for, possibly none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramePacing {
+    /// Wakes up again as soon as possible. With [`RunConfig::vsync`],
+    /// the loop is held back by the display's refresh, otherwise it keeps a CPU core busy
+    /// for the smallest delay between input and the next frame.
+    Poll,
+    /// Sleeps until the next frame is due, which uses less power.
+    /// Frames may be shown slightly late if the system wakes the game up late.
+    WaitUntil,
+}
+
+/// How the game's screen is scaled to fit the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalingMode {
+    /// Stretch the screen to cover the whole window.
+    Stretch,
+    /// Scale the screen by the largest whole number that fits the window,
+    /// centering it and filling the rest with [`RunConfig::border_color`].
+    ///
+    /// Every Pico8 pixel ends up the same size, at the cost of some borders.
+    IntegerPixelPerfect,
+}
+
+impl RunConfig {
+    /// The largest supported [`RunConfig::resolution`], in each direction.
+    pub const MAX_RESOLUTION: u32 = 1024;
+
+    /// Frame rates supported by Pico8.
+    pub const SUPPORTED_FPS: [u32; 2] = [30, 60];
+
+    /// Checks that the settings can be used to run a game.
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, fps) in [("fps", self.fps), ("update_fps", self.update_fps())] {
+            if !Self::SUPPORTED_FPS.contains(&fps) {
+                return Err(format!(
+                    "Unsupported {name}: {fps}, expected one of: {:?}",
+                    Self::SUPPORTED_FPS
+                ));
+            }
+        }
+
+        let (width, height) = self.resolution;
+        if !(1..=Self::MAX_RESOLUTION).contains(&width)
+            || !(1..=Self::MAX_RESOLUTION).contains(&height)
+        {
+            return Err(format!(
+                "Invalid resolution: {width}x{height}, expected sizes in 1..={}",
+                Self::MAX_RESOLUTION
+            ));
+        }
+
+        if self.gif_length_secs == 0 || !(1..=100).contains(&self.gif_fps) {
+            return Err(format!(
+                "Invalid GIF settings: {} seconds at {} fps, expected at least 1 second at 1..=100 fps",
+                self.gif_length_secs, self.gif_fps
+            ));
+        }
+
+        if let Some(icon) = &self.icon {
+            let expected = 4 * icon.width as usize * icon.height as usize;
+
+            if icon.width == 0 || icon.height == 0 || icon.rgba.len() != expected {
+                return Err(format!(
+                    "Invalid icon: {} bytes for {}x{} pixels, expected {expected} (4 per pixel)",
+                    icon.rgba.len(),
+                    icon.width,
+                    icon.height
+                ));
+            }
+        }
+
+        if !(0.0..1.0).contains(&self.gamepad_deadzone) {
+            return Err(format!(
+                "Invalid gamepad_deadzone: {}, expected a value in 0.0..1.0",
+                self.gamepad_deadzone
+            ));
+        }
+
+        if let DisplayFilter::Crt {
+            scanline_strength,
+            curvature,
+        } = self.display_filter
+        {
+            if !(0.0..=1.0).contains(&scanline_strength) || !(0.0..=1.0).contains(&curvature) {
+                return Err(format!(
+                    "Invalid CRT filter: scanline_strength {scanline_strength} and curvature {curvature}, expected values in 0.0..=1.0"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// How many times per second `update` gets called.
+    pub fn update_fps(&self) -> u32 {
+        self.update_fps.unwrap_or(self.fps)
+    }
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        #[cfg(not(feature = "steamdeck"))]
+        let (window_size, window_mode) = ((640, 640), WindowMode::Windowed);
+        #[cfg(feature = "steamdeck")]
+        let (window_size, window_mode) = ((320, 320), WindowMode::Fullscreen);
+
+        Self {
+            fps: 30,
+            update_fps: None,
+            title: "Runty8".to_owned(),
+            icon: None,
+            resolution: (128, 128),
+            window_size,
+            window_mode,
+            fullscreen_key: Some(Key::F11),
+            vsync: true,
+            frame_pacing: FramePacing::Poll,
+            cursor_visible: false,
+            scaling: ScalingMode::Stretch,
+            border_color: 0x000000,
+            display_filter: DisplayFilter::None,
+            fragment_shaders: vec![],
+            flip_screen_y: true,
+            gamepad_deadzone: crate::DEFAULT_GAMEPAD_DEADZONE,
+            button_map: ButtonMap::default(),
+            screenshot_key: Some(Key::F6),
+            screenshot_scale: ScreenshotScale::Native,
+            gif_key: Some(Key::F9),
+            gif_length_secs: 8,
+            gif_fps: 30,
+            capture_dir: None,
+            hot_reload: false,
+            rng_seed: None,
+            debug_overlay: false,
+            fps_overlay: false,
+            fps_overlay_key: Some(Key::F3),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DisplayFilter, RunConfig, WindowIcon};
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(RunConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_unsupported_fps() {
+        for fps in [0, 29, 45, 120] {
+            let config = RunConfig {
+                fps,
+                ..RunConfig::default()
+            };
+
+            assert!(config.validate().is_err());
+        }
+
+        let config = RunConfig {
+            fps: 60,
+            ..RunConfig::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_unsupported_update_fps() {
+        let config = RunConfig {
+            update_fps: Some(45),
+            ..RunConfig::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = RunConfig {
+            fps: 30,
+            update_fps: Some(60),
+            ..RunConfig::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.update_fps(), 60);
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_deadzone() {
+        for gamepad_deadzone in [-0.1, 1.0, 2.0] {
+            let config = RunConfig {
+                gamepad_deadzone,
+                ..RunConfig::default()
+            };
+
+            assert!(config.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn validate_checks_the_icon_size() {
+        let config = |width, height, bytes| RunConfig {
+            icon: Some(WindowIcon {
+                width,
+                height,
+                rgba: vec![0xff; bytes],
+            }),
+            ..RunConfig::default()
+        };
+
+        assert!(config(16, 16, 4 * 16 * 16).validate().is_ok());
+        assert!(config(16, 16, 3 * 16 * 16).validate().is_err());
+        assert!(config(0, 0, 0).validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_resolutions() {
+        for resolution in [(0, 128), (128, 0), (2048, 128)] {
+            let config = RunConfig {
+                resolution,
+                ..RunConfig::default()
+            };
+
+            assert!(config.validate().is_err());
+        }
+
+        let config = RunConfig {
+            resolution: (256, 144),
+            ..RunConfig::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_invalid_gif_settings() {
+        for (gif_length_secs, gif_fps) in [(0, 30), (8, 0), (8, 101)] {
+            let config = RunConfig {
+                gif_length_secs,
+                gif_fps,
+                ..RunConfig::default()
+            };
+
+            assert!(config.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_crt_filters() {
+        for (scanline_strength, curvature) in [(-0.5, 0.0), (1.5, 0.0), (0.5, -0.1), (0.5, 2.0)] {
+            let config = RunConfig {
+                display_filter: DisplayFilter::Crt {
+                    scanline_strength,
+                    curvature,
+                },
+                ..RunConfig::default()
+            };
+
+            assert!(config.validate().is_err());
+        }
+
+        let config = RunConfig {
+            display_filter: DisplayFilter::Crt {
+                scanline_strength: 0.5,
+                curvature: 0.2,
+            },
+            ..RunConfig::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn update_fps_defaults_to_fps() {
+        let config = RunConfig {
+            fps: 60,
+            ..RunConfig::default()
+        };
+
+        assert_eq!(config.update_fps(), 60);
+    }
+}
@@ -26,6 +26,16 @@ fn write(file_name: &str, contents: &str) -> Result<(), WriteError> {
     return wasm::write(file_name, contents);
 }
 
+/// Reads back a file stored with [`write`], `None` if it can't be read:
+///  - Native: Uses regular `std::fs::read_to_string`
+///  - Web: Uses `localStorage.getItem`
+pub(crate) fn read(file_name: &str) -> Option<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::fs::read_to_string(file_name).ok();
+    #[cfg(target_arch = "wasm32")]
+    return crate::wasm::load(file_name);
+}
+
 #[cfg(target_arch = "wasm32")]
 mod wasm {
     pub(super) type Error = wasm_bindgen::JsValue;
@@ -97,25 +107,23 @@ pub struct Ppm {
 impl Ppm {
     #[allow(dead_code)]
     pub fn from_map(map: &Map, sprite_sheet: &SpriteSheet) -> Self {
-        let width = 1024;
-        let height = 4 * 16 * 8;
+        let width = map.width() * 8;
+        let height = map.height() * 8;
         let mut data = vec![Color { r: 0, g: 0, b: 0 }; width * height];
 
-        for (y, row) in map.map.iter().chunks(128).into_iter().enumerate() {
-            for (x, sprite_id) in row.into_iter().copied().enumerate() {
-                let real_x = x * 8;
-                let real_y = y * 8;
+        for (x, y, sprite_id) in map.iter() {
+            let real_x = x * 8;
+            let real_y = y * 8;
 
-                let sprite = sprite_sheet.get_sprite(sprite_id as usize);
+            let sprite = sprite_sheet.get_sprite(sprite_id as usize);
 
-                for (pixel_index, pixel) in sprite.iter().enumerate() {
-                    let offset_x = pixel_index % 8;
-                    let offset_y = pixel_index / 8;
+            for (pixel_index, pixel) in sprite.iter().enumerate() {
+                let offset_x = pixel_index % 8;
+                let offset_y = pixel_index / 8;
 
-                    let color = Color::from_pico8(pixel);
+                let color = Color::from_pico8(pixel);
 
-                    data[(real_x + offset_x) + (real_y + offset_y) * width] = color;
-                }
+                data[(real_x + offset_x) + (real_y + offset_y) * width] = color;
             }
         }
 
@@ -0,0 +1,300 @@
+use crate::serialize::Serialize;
+use itertools::Itertools;
+
+/// The waveform a note is played with.
+///
+/// Pico8's custom instruments (which play another sound effect) aren't supported,
+/// notes using them play the waveform with the same number modulo 8.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Instrument {
+    #[default]
+    Triangle,
+    TiltedSaw,
+    Saw,
+    Square,
+    Pulse,
+    Organ,
+    Noise,
+    Phaser,
+}
+
+impl Instrument {
+    pub(crate) fn from_index(index: u8) -> Self {
+        [
+            Self::Triangle,
+            Self::TiltedSaw,
+            Self::Saw,
+            Self::Square,
+            Self::Pulse,
+            Self::Organ,
+            Self::Noise,
+            Self::Phaser,
+        ][index as usize % 8]
+    }
+}
+
+/// How a note changes while it's played.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Effect {
+    #[default]
+    None,
+    /// Slides from the previous note's pitch and volume.
+    Slide,
+    Vibrato,
+    /// Drops down to the lowest pitch.
+    Drop,
+    FadeIn,
+    FadeOut,
+    /// Cycles through the 4 notes of its group, quickly.
+    ArpFast,
+    /// Cycles through the 4 notes of its group, slowly.
+    ArpSlow,
+}
+
+impl Effect {
+    pub(crate) fn from_index(index: u8) -> Self {
+        [
+            Self::None,
+            Self::Slide,
+            Self::Vibrato,
+            Self::Drop,
+            Self::FadeIn,
+            Self::FadeOut,
+            Self::ArpFast,
+            Self::ArpSlow,
+        ][index as usize % 8]
+    }
+}
+
+/// A note of a [`Sound`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Note {
+    /// Semitones above C-0, in `0..64`.
+    pub pitch: u8,
+    pub instrument: Instrument,
+    /// In `0..8`, 0 being silent.
+    pub volume: u8,
+    pub effect: Effect,
+}
+
+/// One of a pico8 game's sound effects: 32 notes played one after the other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sound {
+    pub notes: [Note; Sound::NOTES],
+    /// How long each note lasts, in 1/120ths of a second.
+    pub speed: u8,
+    /// Notes `loop_start..loop_end` repeat forever, if `loop_end > loop_start`.
+    pub loop_start: u8,
+    pub loop_end: u8,
+}
+
+impl Sound {
+    pub const NOTES: usize = 32;
+
+    pub(crate) fn loops(&self) -> bool {
+        self.loop_end > self.loop_start
+    }
+
+    /// Parses a line of a cart's `__sfx__` section: 4 header bytes
+    /// (editor mode, speed, loop start, loop end) and then 5 hex digits per note
+    /// (2 for the pitch, then instrument, volume and effect).
+    pub(crate) fn from_hex(line: &str) -> Result<Self, String> {
+        let digits = line
+            .chars()
+            .map(|c| {
+                c.to_digit(16)
+                    .map(|digit| digit as u8)
+                    .ok_or_else(|| format!("Invalid hex digit in sfx: {c:?}"))
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+
+        if digits.len() != 8 + 5 * Self::NOTES {
+            return Err(format!(
+                "Expected {} hex digits per sfx, got {}",
+                8 + 5 * Self::NOTES,
+                digits.len()
+            ));
+        }
+
+        let byte = |index: usize| (digits[index] << 4) | digits[index + 1];
+        let mut sound = Self {
+            speed: byte(2),
+            loop_start: byte(4),
+            loop_end: byte(6),
+            ..Self::default()
+        };
+
+        for (note, digits) in sound.notes.iter_mut().zip(digits[8..].chunks_exact(5)) {
+            *note = Note {
+                pitch: (digits[0] << 4) | digits[1],
+                instrument: Instrument::from_index(digits[2]),
+                volume: digits[3] & 0x7,
+                effect: Effect::from_index(digits[4]),
+            };
+        }
+
+        Ok(sound)
+    }
+
+    /// The inverse of [`Sound::from_hex`].
+    pub(crate) fn to_hex(&self) -> String {
+        let header = format!(
+            "00{:02x}{:02x}{:02x}",
+            self.speed, self.loop_start, self.loop_end
+        );
+        let notes = self.notes.iter().map(|note| {
+            format!(
+                "{:02x}{:x}{:x}{:x}",
+                note.pitch, note.instrument as u8, note.volume, note.effect as u8
+            )
+        });
+
+        std::iter::once(header).chain(notes).collect()
+    }
+}
+
+impl Default for Sound {
+    fn default() -> Self {
+        Self {
+            notes: [Note::default(); Sound::NOTES],
+            speed: 16,
+            loop_start: 0,
+            loop_end: 0,
+        }
+    }
+}
+
+/// A pico8 game's sound effects.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sfx {
+    sounds: Vec<Sound>,
+}
+
+impl Sfx {
+    pub const COUNT: usize = 64;
+
+    pub fn file_name() -> String {
+        "sfx.txt".to_owned()
+    }
+
+    pub fn new() -> Self {
+        Self {
+            sounds: vec![Sound::default(); Self::COUNT],
+        }
+    }
+
+    pub fn get(&self, sound: usize) -> Option<&Sound> {
+        self.sounds.get(sound)
+    }
+
+    pub fn get_mut(&mut self, sound: usize) -> Option<&mut Sound> {
+        self.sounds.get_mut(sound)
+    }
+
+    /// Reads sound effects in the format of a cart's `__sfx__` section:
+    /// one line per sound effect (see [`Sound::from_hex`]), blank and missing ones being left blank.
+    pub fn deserialize(file_contents: &str) -> Result<Self, String> {
+        let lines: Vec<&str> = file_contents.lines().map(str::trim).collect();
+        // Blank lines keep their place, except for the ones ending the file.
+        let count = lines
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(0, |last| last + 1);
+        let lines = &lines[..count];
+
+        if lines.len() > Self::COUNT {
+            return Err(format!(
+                "Too many sound effects: expected at most {}, got {}",
+                Self::COUNT,
+                lines.len()
+            ));
+        }
+
+        let mut sfx = Self::new();
+        for (sound, line) in sfx.sounds.iter_mut().zip(lines) {
+            if !line.is_empty() {
+                *sound = Sound::from_hex(line)?;
+            }
+        }
+
+        Ok(sfx)
+    }
+}
+
+impl Serialize for Sfx {
+    fn serialize(&self) -> String {
+        self.sounds.iter().map(Sound::to_hex).join("\n")
+    }
+}
+
+impl Default for Sfx {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Effect, Instrument, Sfx, Sound};
+    use crate::serialize::Serialize;
+
+    #[test]
+    fn parses_sfx_lines() {
+        let line = format!("010a0408{}{}", "1d374", "00000".repeat(31));
+
+        let sound = Sound::from_hex(&line).unwrap();
+
+        assert_eq!(sound.speed, 10);
+        assert_eq!((sound.loop_start, sound.loop_end), (4, 8));
+        assert_eq!(sound.notes[0].pitch, 0x1d);
+        assert_eq!(sound.notes[0].instrument, Instrument::Square);
+        assert_eq!(sound.notes[0].volume, 7);
+        assert_eq!(sound.notes[0].effect, Effect::FadeIn);
+        assert_eq!(sound.notes[1].volume, 0);
+    }
+
+    #[test]
+    fn sfx_lines_round_trip() {
+        let line = format!("000a0408{}", "1d374".repeat(32));
+
+        assert_eq!(Sound::from_hex(&line).unwrap().to_hex(), line);
+    }
+
+    #[test]
+    fn rejects_short_sfx_lines() {
+        assert!(Sound::from_hex("000a0408").is_err());
+        assert!(Sound::from_hex(&"g".repeat(168)).is_err());
+    }
+
+    #[test]
+    fn sfx_files_round_trip() {
+        let mut sfx = Sfx::new();
+        *sfx.get_mut(63).unwrap() =
+            Sound::from_hex(&format!("000a0408{}", "1d374".repeat(32))).unwrap();
+
+        let deserialized = Sfx::deserialize(&sfx.serialize()).unwrap();
+
+        assert_eq!(deserialized, sfx);
+    }
+
+    #[test]
+    fn missing_sfx_are_blank() {
+        let line = format!("000a0408{}", "1d374".repeat(32));
+
+        let sfx = Sfx::deserialize(&format!("{line}\n\n")).unwrap();
+
+        assert_eq!(sfx.get(0).unwrap().speed, 10);
+        assert_eq!(sfx.get(1), Some(&Sound::default()));
+        assert!(Sfx::deserialize(&format!("{line}\n").repeat(65)).is_err());
+    }
+
+    #[test]
+    fn blank_lines_keep_the_sfx_numbers() {
+        let line = format!("000a0408{}", "1d374".repeat(32));
+
+        let sfx = Sfx::deserialize(&format!("{line}\n\n{line}\n")).unwrap();
+
+        assert_eq!(sfx.get(1), Some(&Sound::default()));
+        assert_eq!(sfx.get(2).unwrap().speed, 10);
+    }
+}
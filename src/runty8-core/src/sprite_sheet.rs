@@ -24,6 +24,10 @@ impl SpriteSheet {
     /// and will override its data if used
     pub const SPRITE_COUNT: usize = 256;
 
+    /// Size of the sheet, in pixels.
+    pub const WIDTH: usize = Self::SPRITES_PER_ROW * Sprite::WIDTH;
+    pub const HEIGHT: usize = Self::SPRITE_COUNT / Self::SPRITES_PER_ROW * Sprite::HEIGHT;
+
     pub fn new() -> Self {
         Self {
             sprite_sheet: vec![0; Self::SPRITE_COUNT * Sprite::WIDTH * Sprite::HEIGHT],
@@ -49,6 +53,33 @@ impl SpriteSheet {
         self.sprite_sheet[Self::to_linear_index(x, y)] = c;
     }
 
+    /// Gets the color of the pixel at coordinate (x,y) in the spritesheet
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        self.sprite_sheet[Self::to_linear_index(x, y)]
+    }
+
+    /// Pico8's `sget(x, y)`: the color of the pixel at (x, y), 0 outside of the sheet.
+    pub fn sget(&self, x: i32, y: i32) -> Color {
+        Self::checked_coordinates(x, y)
+            .map(|(x, y)| self.get(x, y))
+            .unwrap_or(0)
+    }
+
+    /// Pico8's `sset(x, y, c)`: colors the pixel at (x, y), ignored outside of the sheet.
+    /// Like pico8, only the lower 4 bits of `c` are kept.
+    pub fn sset(&mut self, x: i32, y: i32, c: Color) {
+        if let Some((x, y)) = Self::checked_coordinates(x, y) {
+            self.set(x, y, c & 0x0f);
+        }
+    }
+
+    fn checked_coordinates(x: i32, y: i32) -> Option<(usize, usize)> {
+        let x = usize::try_from(x).ok().filter(|&x| x < Self::WIDTH)?;
+        let y = usize::try_from(y).ok().filter(|&y| y < Self::HEIGHT)?;
+
+        Some((x, y))
+    }
+
     pub fn to_linear_index(x: usize, y: usize) -> usize {
         let x_part = 64 * (x / 8) + x % 8;
         let y_part = 16 * 64 * (y / 8) + 8 * (y % 8);
@@ -56,6 +87,9 @@ impl SpriteSheet {
         y_part + x_part
     }
 
+    /// Sprite number `sprite`, for reading its pixels (see [`Sprite::pixels`]).
+    ///
+    /// Panics if `sprite` isn't below [`SpriteSheet::SPRITE_COUNT`].
     pub fn get_sprite(&self, sprite: usize) -> &Sprite {
         let index = self.sprite_index(sprite);
 
@@ -73,14 +107,18 @@ impl SpriteSheet {
         sprite * Sprite::WIDTH * Sprite::HEIGHT
     }
 
+    /// Parses the format of [`Serialize::serialize`]: a hex digit per pixel, sprite by sprite.
+    /// Whitespace is ignored, anything else is an error rather than a missing pixel.
     pub fn deserialize(str: &str) -> Result<Self, String> {
         let sprite_sheet = str
-            .as_bytes()
-            .iter()
-            .copied()
-            .filter_map(|c| (c as char).to_digit(16))
-            .map(|c| c as u8)
-            .collect();
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| {
+                c.to_digit(16)
+                    .map(|c| c as u8)
+                    .ok_or_else(|| format!("[SpriteSheet] Invalid color {c:?}"))
+            })
+            .collect::<Result<_, _>>()?;
 
         Self::with_vec(sprite_sheet)
     }
@@ -102,6 +140,10 @@ impl Serialize for SpriteSheet {
     }
 }
 
+/// An 8x8 sprite of a [`SpriteSheet`].
+///
+/// Its pixels are stored row by row from the top left corner:
+/// pixel (x, y) is at index `x + y * Sprite::WIDTH`.
 #[repr(transparent)]
 pub struct Sprite {
     pub sprite: [Color],
@@ -171,6 +213,17 @@ impl Sprite {
             .for_each(|row| row.rotate_right(1));
     }
 
+    /// A copy of the sprite's colors, row by row from the top left corner.
+    ///
+    /// E.g. for a collision mask, `pixels().map(|c| c != 0)`.
+    pub fn pixels(&self) -> [Color; Self::WIDTH * Self::HEIGHT] {
+        let mut pixels = [0; Self::WIDTH * Self::HEIGHT];
+        pixels.copy_from_slice(&self.sprite);
+
+        pixels
+    }
+
+    /// The sprite's colors, row by row from the top left corner.
     pub fn iter(&self) -> impl Iterator<Item = Color> + '_ {
         self.sprite.iter().copied()
     }
@@ -209,4 +262,73 @@ mod tests {
         assert_eq!(SpriteSheet::to_linear_index(8, 1), 64 + 8);
         assert_eq!(SpriteSheet::to_linear_index(1, 9), 1033);
     }
+
+    #[test]
+    fn sprite_pixels_go_row_by_row() {
+        let mut sprite_sheet = SpriteSheet::new();
+        // Sprite 17: second row and column of sprites.
+        sprite_sheet.sset(8 + 1, 8, 3);
+        sprite_sheet.sset(8, 8 + 2, 4);
+
+        let sprite = sprite_sheet.get_sprite(17);
+        let pixels = sprite.pixels();
+        assert_eq!(pixels[1], 3);
+        assert_eq!(pixels[2 * Sprite::WIDTH], 4);
+        assert_eq!(pixels[2 * Sprite::WIDTH], sprite.pget(0, 2));
+        assert!(sprite.iter().eq(pixels));
+    }
+
+    #[test]
+    fn random_sprite_sheets_round_trip() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(8);
+        for _ in 0..20 {
+            let mut sprite_sheet = SpriteSheet::new();
+            for color in sprite_sheet.sprite_sheet.iter_mut() {
+                *color = rng.gen_range(0..16);
+            }
+
+            let serialized = sprite_sheet.serialize();
+            let deserialized = SpriteSheet::deserialize(&serialized).unwrap();
+
+            assert_eq!(deserialized.sprite_sheet, sprite_sheet.sprite_sheet);
+            // Two sprites per line, a digit per pixel.
+            assert_eq!(serialized.lines().count(), SpriteSheet::SPRITE_COUNT / 2);
+            assert!(serialized.lines().all(|line| line.len() == 128));
+        }
+    }
+
+    #[test]
+    fn deserialize_checks_every_digit() {
+        let line = "0123456789abcdef".repeat(8);
+        let lines = vec![line; SpriteSheet::SPRITE_COUNT / 2].join("\r\n");
+
+        let sprite_sheet = SpriteSheet::deserialize(&lines).unwrap();
+        assert!(sprite_sheet
+            .sprite_sheet
+            .iter()
+            .enumerate()
+            .all(|(index, &color)| usize::from(color) == index % 16));
+
+        // An extra or missing pixel, or one that isn't a color, doesn't go unnoticed.
+        assert!(SpriteSheet::deserialize(&format!("{lines}0")).is_err());
+        assert!(SpriteSheet::deserialize(&lines[1..]).is_err());
+        assert!(SpriteSheet::deserialize(&lines.replacen('7', "g", 1)).is_err());
+    }
+
+    #[test]
+    fn sget_and_sset_stay_in_the_sheet() {
+        let mut sprite_sheet = SpriteSheet::new();
+
+        sprite_sheet.sset(127, 127, 0x1c);
+        assert_eq!(sprite_sheet.sget(127, 127), 0x0c);
+
+        // Neither wraps around to another pixel.
+        sprite_sheet.sset(128, 0, 5);
+        sprite_sheet.sset(-1, 0, 5);
+        assert!(sprite_sheet.sprite_sheet.iter().all(|&c| c != 5));
+        assert_eq!(sprite_sheet.sget(128, 127), 0);
+        assert_eq!(sprite_sheet.sget(0, -1), 0);
+    }
 }
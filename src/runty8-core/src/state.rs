@@ -1,4 +1,5 @@
 use crate::input::Input;
+use crate::replay::Buttons;
 use crate::Button;
 use ButtonState::*;
 
@@ -10,9 +11,12 @@ pub struct State {
     down: ButtonState,
     x: ButtonState,
     c: ButtonState,
+    pause: ButtonState,
     pub mouse_x: i32,
     pub mouse_y: i32,
     mouse_pressed: ButtonState,
+    mouse_buttons: u8,
+    gamepad_used: bool,
 }
 
 #[allow(clippy::new_without_default)]
@@ -25,9 +29,12 @@ impl State {
             down: NotPressed,
             x: NotPressed,
             c: NotPressed,
+            pause: NotPressed,
             mouse_x: 64,
             mouse_y: 64,
             mouse_pressed: NotPressed,
+            mouse_buttons: 0,
+            gamepad_used: false,
         }
     }
 
@@ -37,17 +44,32 @@ impl State {
     }
 
     pub fn update_input(&mut self, input: &Input) {
-        self.left.update(input.left);
-        self.right.update(input.right);
-        self.up.update(input.up);
-        self.down.update(input.down);
-        self.x.update(input.x);
-        self.c.update(input.c);
-        self.mouse_pressed.update(input.mouse);
+        self.left.update(input.button(Button::Left));
+        self.right.update(input.button(Button::Right));
+        self.up.update(input.button(Button::Up));
+        self.down.update(input.button(Button::Down));
+        self.x.update(input.button(Button::Cross));
+        self.c.update(input.button(Button::Circle));
+        self.pause.update(input.button(Button::Pause));
+        self.mouse_pressed.update(input.button(Button::Mouse));
+        self.mouse_buttons = input.mouse_buttons;
+        self.gamepad_used = input.gamepad_used;
         self.mouse_x = input.mouse_x;
         self.mouse_y = input.mouse_y;
     }
 
+    /// Like [`State::update_input`], with buttons that come from a [`crate::Replay`].
+    pub fn update_buttons(&mut self, buttons: Buttons) {
+        self.left.update(Some(buttons.held(Button::Left)));
+        self.right.update(Some(buttons.held(Button::Right)));
+        self.up.update(Some(buttons.held(Button::Up)));
+        self.down.update(Some(buttons.held(Button::Down)));
+        self.x.update(Some(buttons.held(Button::Cross)));
+        self.c.update(Some(buttons.held(Button::Circle)));
+        self.pause.update(Some(buttons.held(Button::Pause)));
+        self.mouse_pressed.update(Some(buttons.held(Button::Mouse)));
+    }
+
     pub(crate) fn button(&self, button: Button) -> &ButtonState {
         match button {
             Button::Left => &self.left,
@@ -57,18 +79,32 @@ impl State {
             Button::Cross => &self.x,
             Button::Circle => &self.c,
             Button::Mouse => &self.mouse_pressed,
+            Button::Pause => &self.pause,
         }
     }
 
     pub(crate) fn mouse(&self) -> (i32, i32) {
         (self.mouse_x, self.mouse_y)
     }
+
+    pub(crate) fn mouse_buttons(&self) -> u8 {
+        self.mouse_buttons
+    }
+
+    pub(crate) fn gamepad_used(&self) -> bool {
+        self.gamepad_used
+    }
 }
 
+/// Frames a button has to be held before `btnp` starts repeating (Pico8's default).
+pub(crate) const BTNP_REPEAT_DELAY: u32 = 15;
+/// Frames between each `btnp` repeat, once repeating (Pico8's default).
+pub(crate) const BTNP_REPEAT_INTERVAL: u32 = 4;
+
 #[derive(Debug)]
 pub(crate) enum ButtonState {
-    JustPressed, // btn => true, btnp => true
-    Held,        // btn => true, btnp => false
+    // `frames` is how many frames have passed since the press: 0 on the frame it happened.
+    Pressed { frames: u32 },
     NotPressed,
 }
 
@@ -88,20 +124,16 @@ impl ButtonState {
 
     // A frame has passed but we've registered no event related to this key.
     fn no_change(&mut self) {
-        *self = match self {
-            JustPressed => Held,
-            Held => Held,
-            NotPressed => NotPressed,
+        if let Pressed { frames } = self {
+            *frames += 1;
         }
     }
 
     // Caution: This may come either from a "first" press or a "repeated" press.
-    // TODO: I think we don't handle repeated presses yet.
     fn press(&mut self) {
-        *self = match self {
-            JustPressed => Held,
-            Held => Held,
-            NotPressed => JustPressed,
+        *self = match *self {
+            Pressed { frames } => Pressed { frames: frames + 1 },
+            NotPressed => Pressed { frames: 0 },
         }
     }
 
@@ -110,14 +142,81 @@ impl ButtonState {
     }
 
     pub(crate) fn btn(&self) -> bool {
+        matches!(*self, Pressed { .. })
+    }
+
+    /// True on the frame the button was pressed and, while held,
+    /// every [`BTNP_REPEAT_INTERVAL`] frames after [`BTNP_REPEAT_DELAY`].
+    pub(crate) fn btnp(&self) -> bool {
         match *self {
-            JustPressed => true,
-            Held => true,
+            Pressed { frames: 0 } => true,
+            Pressed { frames } => {
+                frames >= BTNP_REPEAT_DELAY
+                    && (frames - BTNP_REPEAT_DELAY) % BTNP_REPEAT_INTERVAL == 0
+            }
             NotPressed => false,
         }
     }
+}
 
-    pub(crate) fn btnp(&self) -> bool {
-        matches!(*self, JustPressed)
+#[cfg(test)]
+mod tests {
+    use super::{ButtonState, BTNP_REPEAT_DELAY, BTNP_REPEAT_INTERVAL};
+
+    // Which of the first `frames` frames `btnp` returns true on, holding the button throughout.
+    fn btnp_frames(frames: u32) -> Vec<u32> {
+        let mut state = ButtonState::NotPressed;
+
+        (0..frames)
+            .filter(|_| {
+                state.update(Some(true));
+                state.btnp()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn btnp_repeats_after_initial_delay() {
+        assert_eq!(btnp_frames(30), vec![0, 15, 19, 23, 27]);
+    }
+
+    #[test]
+    fn btnp_repeat_follows_pico8_constants() {
+        let frames = btnp_frames(100);
+
+        assert_eq!(frames[1], BTNP_REPEAT_DELAY);
+        assert!(frames[1..]
+            .windows(2)
+            .all(|pair| pair[1] - pair[0] == BTNP_REPEAT_INTERVAL));
+    }
+
+    #[test]
+    fn btnp_repeat_resets_on_release() {
+        let mut state = ButtonState::NotPressed;
+        for _ in 0..10 {
+            state.update(Some(true));
+        }
+
+        state.update(Some(false));
+        assert!(!state.btnp());
+        assert!(!state.btn());
+
+        state.update(Some(true));
+        assert!(state.btnp());
+    }
+
+    #[test]
+    fn btnp_repeats_without_new_events() {
+        let mut state = ButtonState::NotPressed;
+        state.update(Some(true));
+
+        let repeats = (1..=BTNP_REPEAT_DELAY)
+            .filter(|_| {
+                state.update(None);
+                state.btnp()
+            })
+            .count();
+        assert_eq!(repeats, 1);
+        assert!(state.btn());
     }
 }
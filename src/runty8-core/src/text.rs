@@ -0,0 +1,189 @@
+//! Pico8's [control codes](<https://pico-8.fandom.com/wiki/P8SCII_Control_Codes>) for printed text.
+//!
+//! In Rust strings they're written with their byte value, e.g. `"\x0c8RED"` for pico8's `"\f8RED"`.
+
+/// What printing a piece of text does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TextOp {
+    Char(char),
+    /// `\n`: the start of the next line.
+    Newline,
+    /// `\r`: the start of the current line.
+    CarriageReturn,
+    /// `\t`: the next tab stop (every 4 characters).
+    Tab,
+    /// `\b`: one character back.
+    Backspace,
+    /// `\f`: sets the text color.
+    Color(u8),
+    /// `\#`: draws a solid background behind each of the following characters.
+    Background(u8),
+    /// `\-`, `\|` and `\+`: moves the cursor by some pixels.
+    Move(i32, i32),
+    /// `\^g`: back to where printing started (or to the last `\^h`).
+    Home,
+    /// `\^h`: makes the cursor's position the new home.
+    SetHome,
+    /// `\^c`: clears the screen with a color, and goes back to the top left corner.
+    Clear(u8),
+}
+
+// Control characters.
+const END: char = '\0';
+const REPEAT: char = '\x01';
+const BACKGROUND: char = '\x02';
+const MOVE_X: char = '\x03';
+const MOVE_Y: char = '\x04';
+const MOVE: char = '\x05';
+const SPECIAL: char = '\x06';
+const AUDIO: char = '\x07';
+const BACKSPACE: char = '\x08';
+const TAB: char = '\t';
+const NEWLINE: char = '\n';
+const DECORATE: char = '\x0b';
+const COLOR: char = '\x0c';
+const CARRIAGE_RETURN: char = '\r';
+
+/// A control code's parameter: `0-9` and then `a-v` for 0 to 31.
+fn parameter(char: Option<char>) -> u8 {
+    char.and_then(|char| char.to_digit(32)).unwrap_or(0) as u8
+}
+
+/// A color parameter: like pico8, `g-v` wrap around to the 16 colors.
+fn color_parameter(char: Option<char>) -> u8 {
+    parameter(char) & 0x0f
+}
+
+/// Interprets the control codes in `text`.
+///
+/// Codes that aren't supported (audio, decorations, fonts and most `\^` commands)
+/// are skipped along with their parameters, rather than printed.
+pub(crate) fn parse(text: &str) -> Vec<TextOp> {
+    let mut ops = vec![];
+    let mut chars = text.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            END => break,
+            REPEAT => {
+                let times = parameter(chars.next());
+                if let Some(char) = chars.next() {
+                    ops.extend(std::iter::repeat(TextOp::Char(char)).take(times.into()));
+                }
+            }
+            BACKGROUND => ops.push(TextOp::Background(color_parameter(chars.next()))),
+            MOVE_X => ops.push(TextOp::Move(i32::from(parameter(chars.next())) - 16, 0)),
+            MOVE_Y => ops.push(TextOp::Move(0, i32::from(parameter(chars.next())) - 16)),
+            MOVE => {
+                let x = i32::from(parameter(chars.next())) - 16;
+                let y = i32::from(parameter(chars.next())) - 8;
+                ops.push(TextOp::Move(x, y));
+            }
+            SPECIAL => special(&mut chars, &mut ops),
+            // The sound lasts until the next space.
+            AUDIO => {
+                chars.by_ref().find(|&char| char == ' ');
+            }
+            BACKSPACE => ops.push(TextOp::Backspace),
+            TAB => ops.push(TextOp::Tab),
+            NEWLINE => ops.push(TextOp::Newline),
+            DECORATE => {
+                chars.nth(1);
+            }
+            COLOR => ops.push(TextOp::Color(color_parameter(chars.next()))),
+            CARRIAGE_RETURN => ops.push(TextOp::CarriageReturn),
+            // Font switches and the remaining control characters.
+            '\x0e'..='\x1f' => {}
+            // Pico8's wide characters are often written as emoji, which can be followed by this.
+            '\u{fe0f}' => {}
+            char => ops.push(TextOp::Char(char)),
+        }
+    }
+
+    ops
+}
+
+/// `\^` commands.
+fn special(chars: &mut std::str::Chars<'_>, ops: &mut Vec<TextOp>) {
+    let command = match chars.next() {
+        // Turns a mode off, e.g. `\^-w`.
+        Some('-') => chars.next(),
+        command => command,
+    };
+
+    let skipped_parameters = match command {
+        Some('g') => {
+            ops.push(TextOp::Home);
+            0
+        }
+        Some('h') => {
+            ops.push(TextOp::SetHome);
+            0
+        }
+        Some('c') => {
+            ops.push(TextOp::Clear(parameter(chars.next())));
+            0
+        }
+        Some('d' | 's' | 'x' | 'y' | 'r') => 1,
+        Some('j') => 2,
+        // A one-off 8x8 character, as 8 bytes or 16 hex digits.
+        Some('.') => 8,
+        Some(':') => 16,
+        _ => 0,
+    };
+
+    for _ in 0..skipped_parameters {
+        chars.next();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, TextOp};
+
+    fn chars(text: &str) -> Vec<TextOp> {
+        text.chars().map(TextOp::Char).collect()
+    }
+
+    #[test]
+    fn plain_text_is_characters() {
+        assert_eq!(parse("HI!"), chars("HI!"));
+    }
+
+    #[test]
+    fn parses_colors_and_moves() {
+        assert_eq!(
+            parse("\x0caA\x02b\x03e\x04s\x05hk"),
+            [
+                TextOp::Color(10),
+                TextOp::Char('A'),
+                TextOp::Background(11),
+                TextOp::Move(-2, 0),
+                TextOp::Move(0, 12),
+                TextOp::Move(1, 12),
+            ]
+        );
+    }
+
+    #[test]
+    fn unsupported_codes_are_skipped() {
+        assert_eq!(
+            parse("A\x07c4 B\x06w\x06-w\x06x4C\x0b3_D\x0e"),
+            chars("ABCD")
+        );
+        assert_eq!(parse("A\0B"), chars("A"));
+    }
+
+    #[test]
+    fn repeats_characters() {
+        assert_eq!(parse("\x015-"), chars("-----"));
+    }
+
+    #[test]
+    fn parses_special_commands() {
+        assert_eq!(
+            parse("\x06h\x06g\x06c1"),
+            [TextOp::SetHome, TextOp::Home, TextOp::Clear(1)]
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Threads that are kept around to draw big `map` regions, for the `parallel-map` feature.
+//!
+//! Spawning threads on every `map` call costs more than drawing the map does,
+//! so they're started on the first call and then wait for more work.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// A task that can borrow from the caller of [`run`].
+pub(crate) type Task<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+static POOL: Mutex<Option<Sender<Job>>> = Mutex::new(None);
+
+/// Runs every task, and returns once they're all done. The first one runs on this thread
+/// (so a single task never waits for another thread), the others on the pool's threads.
+///
+/// Panics (after every task is done) if one of them panicked.
+pub(crate) fn run(tasks: Vec<Task<'_>>) {
+    let mut tasks = tasks.into_iter();
+    let Some(first) = tasks.next() else {
+        return;
+    };
+    let pending = Arc::new(Pending {
+        count: Mutex::new(tasks.len()),
+        done: Condvar::new(),
+        panicked: AtomicBool::new(false),
+    });
+    // Waits for the sent tasks when dropped, even if something unexpectedly panics
+    // before the end of `run`, so no task can outlive what it borrows.
+    let wait = Wait(&pending);
+
+    if tasks.len() > 0 {
+        let mut pool = POOL.lock().unwrap_or_else(|error| error.into_inner());
+        let sender = pool.get_or_insert_with(start);
+
+        for task in tasks {
+            // SAFETY: `run` can't return or unwind before every sent task has run or been
+            // dropped: `wait` waits for them on the way out either way, and the tasks' own
+            // panics are caught by `run_job`. So nothing a task borrows is gone before it's
+            // done. The tests below check this; run them under miri after changing any of it
+            // (`cargo +nightly miri test -p runty8-core --features parallel-map thread_pool`).
+            let task: Job = unsafe { std::mem::transmute::<Task<'_>, Job>(task) };
+            let finished = Finished(pending.clone());
+            let job: Job = Box::new(move || {
+                let _finished = finished;
+                task();
+            });
+
+            // The workers never stop, but if they did the task still has to run.
+            if let Err(mpsc::SendError(job)) = sender.send(job) {
+                run_job(job);
+            }
+        }
+    }
+
+    let first = panic::catch_unwind(AssertUnwindSafe(first));
+    drop(wait);
+    if first.is_err() || pending.panicked.load(Ordering::SeqCst) {
+        panic!("a map drawing thread panicked");
+    }
+}
+
+/// Starts a worker per core, returning where to send them jobs.
+fn start() -> Sender<Job> {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let threads = std::thread::available_parallelism().map_or(1, usize::from);
+
+    for index in 0..threads {
+        let receiver = receiver.clone();
+        std::thread::Builder::new()
+            .name(format!("runty8-map-{index}"))
+            .spawn(move || loop {
+                let job = receiver
+                    .lock()
+                    .unwrap_or_else(|error| error.into_inner())
+                    .recv();
+                match job {
+                    Ok(job) => run_job(job),
+                    Err(mpsc::RecvError) => return,
+                }
+            })
+            .expect("Couldn't start a map drawing thread");
+    }
+
+    sender
+}
+
+/// Runs `job`, keeping its panic from unwinding past it: tasks borrow from the caller of
+/// [`run`], which has to wait for all of them first. The panic is reported by [`Finished`].
+fn run_job(job: Job) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(job));
+}
+
+/// Tasks of a [`run`] call that haven't finished yet.
+struct Pending {
+    count: Mutex<usize>,
+    done: Condvar,
+    panicked: AtomicBool,
+}
+
+impl Pending {
+    fn wait(&self) {
+        let mut count = self.count.lock().unwrap_or_else(|error| error.into_inner());
+        while *count > 0 {
+            count = self
+                .done
+                .wait(count)
+                .unwrap_or_else(|error| error.into_inner());
+        }
+    }
+}
+
+/// Waits for the [`Pending`] tasks when dropped.
+struct Wait<'a>(&'a Pending);
+
+impl Drop for Wait<'_> {
+    fn drop(&mut self) {
+        self.0.wait();
+    }
+}
+
+/// Counts a task as finished when dropped, even if it panicked (or never ran).
+struct Finished(Arc<Pending>);
+
+impl Drop for Finished {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.0.panicked.store(true, Ordering::SeqCst);
+        }
+
+        let mut count = self
+            .0
+            .count
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        *count -= 1;
+        if *count == 0 {
+            self.0.done.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run, Task};
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn runs_every_task_before_returning() {
+        let mut results = [0; 16];
+        let calls = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            let tasks = results
+                .chunks_mut(4)
+                .enumerate()
+                .map(|(index, chunk)| {
+                    let calls = &calls;
+                    Box::new(move || {
+                        chunk.iter_mut().for_each(|result| *result += index);
+                        calls.fetch_add(1, Ordering::SeqCst);
+                    }) as Task<'_>
+                })
+                .collect();
+            run(tasks);
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 8);
+        assert_eq!(&results[..], [[0; 4], [2; 4], [4; 4], [6; 4]].concat());
+    }
+
+    #[test]
+    fn panics_once_the_other_tasks_are_done() {
+        let calls = AtomicUsize::new(0);
+        let tasks = (0..4)
+            .map(|index| {
+                let calls = &calls;
+                Box::new(move || {
+                    assert_ne!(index, 2);
+                    calls.fetch_add(1, Ordering::SeqCst);
+                }) as Task<'_>
+            })
+            .collect();
+
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| run(tasks))).is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        // The pool still works.
+        run(vec![Box::new(|| {
+            calls.fetch_add(1, Ordering::SeqCst);
+        })]);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn panics_only_after_the_tasks_borrowing_the_buffer_are_done() {
+        let mut buffer = [0u8; 8];
+        let tasks = buffer
+            .chunks_mut(2)
+            .enumerate()
+            .map(|(index, chunk)| {
+                Box::new(move || {
+                    // The first task runs on this thread, the third on the pool's.
+                    if index == 0 || index == 2 {
+                        panic!("task {index}");
+                    }
+                    std::thread::sleep(Duration::from_millis(20));
+                    chunk.fill(index as u8);
+                }) as Task<'_>
+            })
+            .collect();
+
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| run(tasks))).is_err());
+        assert_eq!(buffer, [0, 0, 1, 1, 0, 0, 3, 3]);
+    }
+}
@@ -0,0 +1,122 @@
+/// Fixed timestep scheduler for a game's `update` and `draw` calls.
+///
+/// Elapsed time is accumulated independently for updates and draws,
+/// so a game can update at 60 fps while drawing at 30 (or vice versa).
+#[derive(Debug, Clone)]
+pub struct Timestep {
+    update_millis: f64,
+    draw_millis: f64,
+    accumulated_update: f64,
+    accumulated_draw: f64,
+}
+
+/// What should run after advancing a [`Timestep`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Steps {
+    /// How many times `update` should be called.
+    pub updates: u32,
+    /// Whether `draw` should be called (after the updates).
+    pub draw: bool,
+}
+
+impl Timestep {
+    /// Creates a scheduler for the given update and draw rates (in frames per second).
+    pub fn new(update_fps: u32, draw_fps: u32) -> Self {
+        Self {
+            update_millis: 1000.0 / update_fps as f64,
+            draw_millis: 1000.0 / draw_fps as f64,
+            accumulated_update: 0.0,
+            accumulated_draw: 0.0,
+        }
+    }
+
+    /// Advances the clock by `delta_millis`.
+    ///
+    /// Every elapsed update step is reported, so the simulation stays deterministic.
+    /// Draws that fell behind are dropped: at most one draw is requested per call.
+    pub fn advance(&mut self, delta_millis: f64) -> Steps {
+        self.accumulated_update += delta_millis;
+        self.accumulated_draw += delta_millis;
+
+        let mut updates = 0;
+        while self.accumulated_update >= self.update_millis {
+            self.accumulated_update -= self.update_millis;
+            updates += 1;
+        }
+
+        let draw = self.accumulated_draw >= self.draw_millis;
+        if draw {
+            self.accumulated_draw %= self.draw_millis;
+        }
+
+        Steps { updates, draw }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Steps, Timestep};
+
+    #[test]
+    fn same_rate_updates_once_per_draw() {
+        let mut timestep = Timestep::new(30, 30);
+
+        assert_eq!(
+            timestep.advance(10.0),
+            Steps {
+                updates: 0,
+                draw: false
+            }
+        );
+        assert_eq!(
+            timestep.advance(1000.0 / 30.0),
+            Steps {
+                updates: 1,
+                draw: true
+            }
+        );
+    }
+
+    #[test]
+    fn updates_twice_per_draw_at_60_over_30() {
+        let mut timestep = Timestep::new(60, 30);
+
+        assert_eq!(
+            timestep.advance(1000.0 / 30.0),
+            Steps {
+                updates: 2,
+                draw: true
+            }
+        );
+    }
+
+    #[test]
+    fn simulation_does_not_drift_with_jittery_ticks() {
+        let mut timestep = Timestep::new(60, 30);
+        let ticks = [7.0, 25.0, 1.0, 40.0, 16.0, 11.0];
+
+        let mut updates = 0;
+        for tick in ticks.iter().cycle().take(600) {
+            updates += timestep.advance(*tick).updates;
+        }
+
+        // Allow for floating point rounding on the very last step.
+        let elapsed: f64 = ticks.iter().cycle().take(600).sum();
+        let expected = (elapsed / (1000.0 / 60.0)).round() as i64;
+        assert!((updates as i64 - expected).abs() <= 1);
+    }
+
+    #[test]
+    fn late_draws_are_dropped() {
+        let mut timestep = Timestep::new(30, 30);
+
+        let steps = timestep.advance(3.5 * 1000.0 / 30.0);
+        assert_eq!(
+            steps,
+            Steps {
+                updates: 3,
+                draw: true
+            }
+        );
+    }
+}
@@ -3,7 +3,7 @@ use crate::ui::Element;
 use crate::Resources;
 use runty8_core::Input;
 use runty8_core::InputEvent;
-use runty8_core::{App, Event, Pico8};
+use runty8_core::{App, Event, Pico8, Timestep};
 use std::fmt::Debug;
 
 /// An Elm-style app
@@ -50,33 +50,32 @@ pub enum Pico8AppMsg {
 pub(crate) struct Pico8AppCompat<A> {
     app: A,
     keys: Input,
-    accumulated_delta: f64,
-    delta_time: f64,
+    timestep: Timestep,
 }
 
 impl<A: App> AppCompat for Pico8AppCompat<A> {
     type Msg = Pico8AppMsg;
 
     fn init(pico8: &mut Pico8) -> Self {
-        let fps = 30_f64;
+        let mut keys = Input::new();
+        keys.set_gamepad_deadzone(pico8.gamepad_deadzone());
+        keys.set_button_map(pico8.button_map().clone());
 
         Self {
             app: A::init(pico8),
-            keys: Input::new(),
-            accumulated_delta: 0.0,
-            delta_time: 1000.0 / fps,
+            keys,
+            timestep: Timestep::new(pico8.update_fps(), pico8.fps()),
         }
     }
 
     fn update(&mut self, msg: &Self::Msg, pico8: &mut Pico8) {
         match *msg {
             Pico8AppMsg::Tick { delta_millis } => {
-                self.accumulated_delta += delta_millis;
-
-                while self.accumulated_delta > self.delta_time {
+                pico8.advance_real_time(delta_millis);
+                // The editor redraws on every event, so only the updates are scheduled here.
+                for _ in 0..self.timestep.advance(delta_millis).updates {
                     pico8.state.update_input(&self.keys);
-                    self.app.update(pico8);
-                    self.accumulated_delta -= self.delta_time;
+                    pico8.update_app(&mut self.app);
                 }
             }
 
@@ -87,7 +86,11 @@ impl<A: App> AppCompat for Pico8AppCompat<A> {
     }
 
     fn view(&mut self, _: &mut Resources) -> Element<'_, Self::Msg> {
-        DrawFn::new(|draw| self.app.draw(draw)).into()
+        DrawFn::new(|pico8| pico8.draw_app(&mut self.app)).into()
+    }
+
+    fn on_exit(&mut self, pico8: &mut Pico8) {
+        self.app.on_exit(pico8);
     }
 
     fn subscriptions(&self, event: &Event) -> Vec<Self::Msg> {
@@ -106,4 +109,5 @@ pub(crate) trait AppCompat {
     fn update(&mut self, msg: &Self::Msg, pico8: &mut Pico8);
     fn view(&mut self, resources: &mut Resources) -> Element<'_, Self::Msg>;
     fn subscriptions(&self, event: &Event) -> Vec<Self::Msg>;
+    fn on_exit(&mut self, _pico8: &mut Pico8) {}
 }
@@ -8,7 +8,9 @@ use crate::{
     ui::Element,
     Resources,
 };
-use runty8_core::{DrawData, Event, InputEvent, Key, KeyboardEvent, MouseEvent, Pico8};
+use runty8_core::{
+    Audio, DrawData, Event, InputEvent, Key, KeyboardEvent, MouseEvent, Pico8, RunConfig,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum Msg<AppMsg> {
@@ -37,21 +39,30 @@ pub(crate) struct Controller<Game> {
     /// In order for these settings not to spill from the game to the editor, and viceversa,
     /// we keep an alternate [`DrawData`] that we swap, when the scene changes.
     alternate_draw_data: DrawData,
+    exited: bool,
 }
 
 impl<T> Controller<T> {
-    pub(crate) fn screen_buffer(&self) -> &[u8] {
-        self.pico8.draw_data.buffer()
+    pub(crate) fn screen_buffer(&mut self) -> &[u8] {
+        match self.scene {
+            Scene::App => self.pico8.display_buffer(),
+            Scene::Editor => self.pico8.draw_data.buffer(),
+        }
     }
 
     pub(crate) fn take_new_title(&mut self) -> Option<String> {
         self.pico8.take_new_title()
     }
+
+    pub(crate) fn audio(&self) -> Audio {
+        self.pico8.audio()
+    }
 }
 
 impl<Game: AppCompat> Controller<Game> {
-    pub fn init(scene: Scene, resources: Resources) -> Self {
+    pub fn init(scene: Scene, resources: Resources, config: &RunConfig) -> Self {
         let mut pico8 = Pico8::new(resources);
+        pico8.apply_config(config);
 
         Self {
             scene,
@@ -63,6 +74,15 @@ impl<Game: AppCompat> Controller<Game> {
             pico8,
             mouse_position: Vec2i::new(64, 64),
             alternate_draw_data: DrawData::new(),
+            exited: false,
+        }
+    }
+
+    /// Lets the game know it's closing, the first time it's called.
+    pub(crate) fn exit(&mut self) {
+        if !self.exited {
+            self.exited = true;
+            self.app.on_exit(&mut self.pico8);
         }
     }
 
@@ -87,7 +107,7 @@ impl<Game: AppCompat> Controller<Game> {
             &Msg::MouseEvent(MouseEvent::Move { x, y }) => {
                 self.mouse_position = Vec2i::new(x, y);
             }
-            &Msg::MouseEvent(MouseEvent::Button { .. }) => {}
+            &Msg::MouseEvent(MouseEvent::Button { .. } | MouseEvent::Wheel { .. }) => {}
             &Msg::Tick => {}
         }
     }
@@ -110,8 +130,10 @@ impl<Game: AppCompat> Controller<Game> {
             Event::Input(InputEvent::Keyboard(keyboard_event)) => {
                 Some(Msg::KeyboardEvent(*keyboard_event))
             }
+            Event::Input(InputEvent::Gamepad(_)) => None,
             Event::Tick { .. } => Some(Msg::Tick),
-            Event::WindowClosed => todo!("WindowClosed event not yet handled"),
+            // Handled by `Controller::exit` instead.
+            Event::WindowClosed => None,
         }
         .into_iter();
 
@@ -160,7 +182,7 @@ impl<Game: AppCompat> Controller<Game> {
 
         let mouse_position = (self.mouse_position.x, self.mouse_position.y);
         view.as_widget_mut()
-            .on_event(event, mouse_position, dispatch_event);
+            .on_event(event, mouse_position, false, dispatch_event);
 
         view.as_widget_mut().draw(&mut self.pico8);
         drop(view);
@@ -189,3 +211,43 @@ impl Scene {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Controller, Scene};
+    use crate::app::Pico8AppCompat;
+    use runty8_core::{App, Pico8, Resources, RunConfig};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // The game itself is inside the controller, so it counts its exits here.
+    static EXITS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Saver;
+
+    impl App for Saver {
+        fn init(_: &mut Pico8) -> Self {
+            Self
+        }
+
+        fn update(&mut self, _: &mut Pico8) {}
+
+        fn draw(&mut self, _: &mut Pico8) {}
+
+        fn on_exit(&mut self, _: &mut Pico8) {
+            EXITS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn exit_lets_the_game_know_once() {
+        let mut controller = Controller::<Pico8AppCompat<Saver>>::init(
+            Scene::App,
+            Resources::blank("."),
+            &RunConfig::default(),
+        );
+
+        controller.exit();
+        controller.exit();
+        assert_eq!(EXITS.load(Ordering::SeqCst), 1);
+    }
+}
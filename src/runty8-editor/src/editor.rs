@@ -384,7 +384,9 @@ impl ElmApp for Editor {
             }
 
             &Msg::ClickedMapTile { x, y } => {
-                resources.map.mset(x, y, self.selected_sprite as u8);
+                resources
+                    .map
+                    .mset(x as i32, y as i32, self.selected_sprite as u8);
             }
             &Msg::BrushSizeSelected(brush_size) => {
                 self.brush_size = brush_size;
@@ -462,7 +464,6 @@ impl ElmApp for Editor {
         match event {
             Event::Input(InputEvent::Keyboard(event)) => Some(Msg::KeyboardEvent(*event)),
             Event::Tick { .. } => None,
-            Event::WindowClosed => todo!("WindowClosed event not yet handled"),
             _ => None,
         }
         .into_iter()
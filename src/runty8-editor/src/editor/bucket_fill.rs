@@ -179,7 +179,6 @@ mod tests {
         let mut pixels_mut = PixelsMut::new(&mut pixels, 6);
 
         pixels_mut.fill_bucket(2, 5, 0);
-        drop(pixels_mut);
         assert_eq!(
             pixels,
             [
@@ -100,7 +100,7 @@ impl<'a, Msg> Notification<'a, Msg> {
 impl<'a, Msg: Copy + Debug> Widget for Notification<'a, Msg> {
     type Msg = Msg;
 
-    fn on_event(&mut self, event: Event, _: (i32, i32), _: &mut DispatchEvent<Self::Msg>) {
+    fn on_event(&mut self, event: Event, _: (i32, i32), _: bool, _: &mut DispatchEvent<Self::Msg>) {
         let state = &mut self.state;
 
         if let Event::Tick { .. } = event {
@@ -96,7 +96,7 @@ fn color_selector<'a, Msg: Debug + Copy + 'a>(
     states: &'a mut [button::State],
     on_press: impl (Fn(Color) -> Msg) + Copy,
     on_hover: impl (Fn(Color) -> Msg) + Copy,
-) -> Element<'_, Msg> {
+) -> Element<'a, Msg> {
     let mut v = Vec::with_capacity(16);
 
     let coordinates = move |index| {
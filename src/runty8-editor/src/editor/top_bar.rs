@@ -59,7 +59,7 @@ fn editor_button(
     y: i32,
     on_press: Msg,
     selected: bool,
-) -> Button<Msg> {
+) -> Button<'_, Msg> {
     Button::new(
         x,
         y,
@@ -9,7 +9,7 @@ mod app;
 pub mod ui;
 
 pub use app::ElmApp;
-use runty8_core::{App, Resources};
+use runty8_core::{App, Resources, RunConfig};
 
 mod controller;
 mod editor;
@@ -21,19 +21,38 @@ use controller::Scene;
 
 /// Run a Pico8 application.
 pub fn run_app<T: App + 'static>(resources: Resources) -> std::io::Result<()> {
-    run_app_compat::<Pico8AppCompat<T>>(resources)
+    run_app_with_config::<T>(resources, RunConfig::default())
+}
+
+/// Run a Pico8 application with custom settings.
+pub fn run_app_with_config<T: App + 'static>(
+    resources: Resources,
+    config: RunConfig,
+) -> std::io::Result<()> {
+    run_app_compat::<Pico8AppCompat<T>>(resources, config)
 }
 
 /// Run an Elm-style application.
 pub fn run_elm_app<T: ElmApp + 'static>(resources: Resources) -> std::io::Result<()> {
-    run_app_compat::<ElmAppCompat<T>>(resources)
+    run_app_compat::<ElmAppCompat<T>>(resources, RunConfig::default())
 }
 // TODO: add example
-fn run_app_compat<T: AppCompat + 'static>(resources: Resources) -> std::io::Result<()> {
-    let starting_scene = start_scene();
-    implementation::run_app::<T>(starting_scene, resources);
+fn run_app_compat<T: AppCompat + 'static>(
+    resources: Resources,
+    config: RunConfig,
+) -> std::io::Result<()> {
+    config
+        .validate()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+    let mut config = config;
+    config
+        .capture_dir
+        .get_or_insert_with(|| resources.assets_path.clone());
 
-    Ok(())
+    let starting_scene = start_scene();
+    implementation::run_app::<T>(starting_scene, resources, config)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))
 }
 
 fn start_scene() -> Scene {
@@ -48,16 +67,24 @@ mod implementation {
     use crate::app::AppCompat;
     use crate::controller::{Controller, Scene};
     use crate::Resources;
-    use runty8_core::Event;
-    use runty8_winit::ScreenInfo;
-
-    pub(super) fn run_app<Game: AppCompat + 'static>(scene: Scene, resources: Resources) {
-        let mut screen_info = ScreenInfo::new(640.0, 640.0);
-        screen_info.scale_factor = 1.0;
-
-        let mut controller = Controller::<Game>::init(scene, resources);
+    use runty8_core::{Event, RunConfig};
+    use winit::event_loop::ControlFlow;
+
+    pub(super) fn run_app<Game: AppCompat + 'static>(
+        scene: Scene,
+        resources: Resources,
+        config: RunConfig,
+    ) -> Result<(), String> {
+        let mut controller = Controller::<Game>::init(scene, resources, &config);
+        runty8_event_loop::play_audio(controller.audio());
+
+        runty8_event_loop::event_loop(&config, move |event, control_flow, draw, set_title| {
+            if let Event::WindowClosed = event {
+                controller.exit();
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
 
-        runty8_event_loop::event_loop(move |event, control_flow, draw, set_title| {
             controller.step(event);
 
             if let Some(new_title) = controller.take_new_title() {
@@ -67,6 +94,6 @@ mod implementation {
             if let Event::Tick { .. } = event {
                 draw(controller.screen_buffer(), control_flow);
             }
-        });
+        })
     }
 }
@@ -1,22 +1,59 @@
 pub mod button;
+pub mod checkbox;
 pub mod cursor;
+pub mod focus;
+pub mod layout;
+pub mod scroll;
 pub mod slider;
 pub mod text;
-use runty8_core::{Event, Pico8};
+pub mod text_input;
+pub mod tooltip;
+use runty8_core::{Event, InputEvent, KeyState, MouseEvent, Pico8};
 use std::{fmt::Debug, marker::PhantomData};
 
 pub struct DispatchEvent<'a, Msg> {
     queue: &'a mut Vec<Msg>,
+    focus: FocusOrder,
+}
+
+/// Which focusable widget is focused, while an event goes through the tree.
+///
+/// Focusable widgets are numbered in the order they get the event, skipping the rest.
+#[derive(Clone, Copy, Debug, Default)]
+struct FocusOrder {
+    focused: Option<usize>,
+    next_id: usize,
 }
 
 impl<'a, Msg> DispatchEvent<'a, Msg> {
     pub(crate) fn new(queue: &'a mut Vec<Msg>) -> Self {
-        Self { queue }
+        Self {
+            queue,
+            focus: FocusOrder::default(),
+        }
     }
 
     pub fn call(&mut self, msg: Msg) {
         self.queue.push(msg);
     }
+
+    /// Gives `widget` the next focus id if it's focusable,
+    /// returning whether it's the focused one.
+    ///
+    /// Containers call this for each child, before passing it the event.
+    pub fn focus_child(&mut self, widget: &dyn Widget<Msg = Msg>) -> bool
+    where
+        Msg: Copy + Debug,
+    {
+        if !widget.focusable() {
+            return false;
+        }
+
+        let id = self.focus.next_id;
+        self.focus.next_id += 1;
+
+        self.focus.focused == Some(id)
+    }
 }
 
 pub trait Widget {
@@ -26,10 +63,90 @@ pub trait Widget {
         &mut self,
         event: Event,
         cursor_position: (i32, i32),
+        focused: bool,
         dispatch_event: &mut DispatchEvent<Self::Msg>,
     );
 
     fn draw(&mut self, draw: &mut Pico8);
+
+    /// Whether the widget can be focused (see [`focus`]), to get keyboard input.
+    fn focusable(&self) -> bool {
+        false
+    }
+
+    /// The area the widget reacts to clicks in.
+    ///
+    /// Containers don't pass it mouse presses outside of it, so widgets that need those
+    /// (or that have no such area) return `None`, the default.
+    fn bounds(&self) -> Option<Rect> {
+        None
+    }
+
+    /// How far right and down the widget reaches from its origin,
+    /// used by [`layout`] containers to place the next child.
+    fn size(&self) -> (i32, i32) {
+        (0, 0)
+    }
+}
+
+/// An axis aligned rectangle, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        let contains_x = x >= self.x && x < self.x + self.width;
+        let contains_y = y >= self.y && y < self.y + self.height;
+
+        contains_x && contains_y
+    }
+}
+
+/// Whether a container should pass `event` on to `widget`:
+/// mouse presses only go to the widgets they happen in.
+fn receives<Msg: Copy + Debug>(
+    widget: &dyn Widget<Msg = Msg>,
+    event: Event,
+    cursor_position: (i32, i32),
+) -> bool {
+    match (event, widget.bounds()) {
+        (
+            Event::Input(InputEvent::Mouse(MouseEvent::Button {
+                state: KeyState::Down,
+                ..
+            })),
+            Some(bounds),
+        ) => bounds.contains(cursor_position.0, cursor_position.1),
+        _ => true,
+    }
+}
+
+/// `event` as seen by a child whose origin is at `(x, y)`.
+fn translate(event: Event, x: i32, y: i32) -> Event {
+    match event {
+        Event::Input(InputEvent::Mouse(MouseEvent::Move {
+            x: mouse_x,
+            y: mouse_y,
+        })) => Event::Input(InputEvent::Mouse(MouseEvent::Move {
+            x: mouse_x - x,
+            y: mouse_y - y,
+        })),
+        event => event,
+    }
 }
 
 pub struct Tree<'a, Msg> {
@@ -78,26 +195,46 @@ impl<'a, Msg: Copy + Debug + 'a, BigMsg: Copy + Debug + 'a> Widget for Map<'a, M
         &mut self,
         event: Event,
         cursor_position: (i32, i32),
+        focused: bool,
         dispatch_event: &mut DispatchEvent<Self::Msg>,
     ) {
         // TODO: Find a better way of doing this, we're now allocating a new vec
         // For every component that uses map, this is the problem we wanted to avoid
         // by introducing DispatchEvent
         let mut queue_small = vec![];
-        let mut dispatch_event_small = DispatchEvent::new(&mut queue_small);
+        let mut dispatch_event_small = DispatchEvent {
+            queue: &mut queue_small,
+            focus: dispatch_event.focus,
+        };
 
-        self.element
-            .as_widget_mut()
-            .on_event(event, cursor_position, &mut dispatch_event_small);
+        self.element.as_widget_mut().on_event(
+            event,
+            cursor_position,
+            focused,
+            &mut dispatch_event_small,
+        );
+        dispatch_event.focus = dispatch_event_small.focus;
 
         for small_msg in queue_small {
             dispatch_event.call((self.f)(small_msg));
         }
     }
 
+    fn focusable(&self) -> bool {
+        self.element.as_widget().focusable()
+    }
+
+    fn bounds(&self) -> Option<Rect> {
+        self.element.as_widget().bounds()
+    }
+
     fn draw(&mut self, draw: &mut Pico8) {
         self.element.as_widget_mut().draw(draw)
     }
+
+    fn size(&self) -> (i32, i32) {
+        self.element.as_widget().size()
+    }
 }
 
 impl<'a, Msg> Tree<'a, Msg> {
@@ -128,12 +265,17 @@ impl<'a, Msg: Copy + Debug> Widget for Tree<'a, Msg> {
         &mut self,
         event: Event,
         cursor_position: (i32, i32),
+        _focused: bool,
         dispatch_event: &mut DispatchEvent<Self::Msg>,
     ) {
         for element in self.children.iter_mut() {
-            element
-                .widget
-                .on_event(event, cursor_position, dispatch_event);
+            let focused = dispatch_event.focus_child(element.as_widget());
+
+            if receives(element.as_widget(), event, cursor_position) {
+                element
+                    .widget
+                    .on_event(event, cursor_position, focused, dispatch_event);
+            }
         }
     }
 
@@ -142,6 +284,15 @@ impl<'a, Msg: Copy + Debug> Widget for Tree<'a, Msg> {
             element.widget.draw(draw);
         }
     }
+
+    fn size(&self) -> (i32, i32) {
+        self.children
+            .iter()
+            .map(|element| element.widget.size())
+            .fold((0, 0), |(width, height), (w, h)| {
+                (width.max(w), height.max(h))
+            })
+    }
 }
 
 pub struct DrawFn<'a, Msg> {
@@ -165,6 +316,7 @@ impl<'a, Msg: Copy + Debug> Widget for DrawFn<'a, Msg> {
         &mut self,
         _event: Event,
         _cursor_position: (i32, i32),
+        _focused: bool,
         _dispatch_event: &mut DispatchEvent<Self::Msg>,
     ) {
     }
@@ -179,3 +331,42 @@ impl<'a, Msg: Copy + Debug + 'a, T: Widget<Msg = Msg> + 'a> From<T> for Element<
         Element::new(val)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{checkbox::Checkbox, DispatchEvent, Rect, Tree, Widget};
+    use runty8_core::{Event, InputEvent, KeyState, MouseButton, MouseEvent};
+
+    #[test]
+    fn rects_contain_their_inside() {
+        let rect = Rect::new(2, 3, 4, 5);
+
+        assert!(rect.contains(2, 3));
+        assert!(rect.contains(5, 7));
+        assert!(!rect.contains(6, 7));
+        assert!(!rect.contains(5, 8));
+        assert!(!rect.contains(1, 3));
+        assert!(!Rect::new(0, 0, 0, 0).contains(0, 0));
+    }
+
+    #[test]
+    fn trees_only_pass_presses_to_the_widgets_under_the_cursor() {
+        let mut queue = vec![];
+        let mut tree = Tree::new()
+            .push(Checkbox::new(0, 0, 8, 8, false, 1))
+            .push(Checkbox::new(10, 0, 8, 8, false, 2));
+        let press = Event::Input(InputEvent::Mouse(MouseEvent::Button {
+            button: MouseButton::Left,
+            state: KeyState::Down,
+        }));
+
+        tree.on_event(press, (12, 4), false, &mut DispatchEvent::new(&mut queue));
+
+        assert_eq!(queue, [2]);
+        assert_eq!(
+            tree.children[1].as_widget().bounds(),
+            Some(Rect::new(10, 0, 8, 8))
+        );
+        assert_eq!(tree.bounds(), None);
+    }
+}
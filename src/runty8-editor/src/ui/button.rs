@@ -1,6 +1,6 @@
 use runty8_core::{Event, InputEvent, KeyState, MouseButton, MouseEvent, Pico8};
 
-use super::{DispatchEvent, Element, Widget};
+use super::{DispatchEvent, Element, Rect, Widget};
 use std::fmt::Debug;
 
 pub struct Button<'a, Msg> {
@@ -87,11 +87,12 @@ impl<'a, Msg> Button<'a, Msg> {
         self
     }
 
-    fn contains(&self, x: i32, y: i32) -> bool {
-        let contains_x = x >= self.x && x < self.x + self.width;
-        let contains_y = y >= self.y && y < self.y + self.height;
+    fn rect(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.height)
+    }
 
-        contains_x && contains_y
+    fn contains(&self, x: i32, y: i32) -> bool {
+        self.rect().contains(x, y)
     }
 }
 
@@ -107,6 +108,7 @@ impl<'a, Msg: Copy + Debug + 'a> Widget for Button<'a, Msg> {
         &mut self,
         event: Event,
         cursor_position: (i32, i32),
+        _focused: bool,
         dispatch_event: &mut DispatchEvent<'_, Msg>,
     ) {
         use Event::Input;
@@ -199,4 +201,16 @@ impl<'a, Msg: Copy + Debug + 'a> Widget for Button<'a, Msg> {
         self.content.as_widget_mut().draw(pico8);
         pico8.append_camera(self.x, self.y);
     }
+
+    fn bounds(&self) -> Option<Rect> {
+        // Buttons pressed by dragging over them need to know about presses anywhere.
+        match self.active_mode {
+            ActiveMode::Release => Some(self.rect()),
+            ActiveMode::Press => None,
+        }
+    }
+
+    fn size(&self) -> (i32, i32) {
+        (self.x + self.width, self.y + self.height)
+    }
 }
@@ -0,0 +1,121 @@
+use runty8_core::{Event, InputEvent, KeyState, MouseButton, MouseEvent, Pico8};
+use std::fmt::Debug;
+
+use super::{DispatchEvent, Rect, Widget};
+
+/// A box that's checked or not, and asks to be toggled when clicked.
+pub struct Checkbox<Msg> {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    checked: bool,
+    on_toggle: Msg,
+}
+
+impl<Msg> Checkbox<Msg> {
+    pub fn new(x: i32, y: i32, width: i32, height: i32, checked: bool, on_toggle: Msg) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            checked,
+            on_toggle,
+        }
+    }
+
+    fn rect(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.height)
+    }
+}
+
+impl<Msg: Copy + Debug> Widget for Checkbox<Msg> {
+    type Msg = Msg;
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        cursor_position: (i32, i32),
+        _focused: bool,
+        dispatch_event: &mut DispatchEvent<Self::Msg>,
+    ) {
+        if let Event::Input(InputEvent::Mouse(MouseEvent::Button {
+            button: MouseButton::Left,
+            state: KeyState::Down,
+        })) = event
+        {
+            if self.rect().contains(cursor_position.0, cursor_position.1) {
+                dispatch_event.call(self.on_toggle);
+            }
+        }
+    }
+
+    fn draw(&mut self, pico8: &mut Pico8) {
+        let x1 = self.x + self.width - 1;
+        let y1 = self.y + self.height - 1;
+
+        pico8.rectfill(self.x, self.y, x1, y1, 0);
+        pico8.rect(self.x, self.y, x1, y1, 7);
+
+        if self.checked {
+            // A tick: down from the left third, then up to the top right corner.
+            let middle_x = self.x + self.width / 3;
+            pico8.line(self.x + 1, self.y + self.height / 2, middle_x, y1 - 1, 7);
+            pico8.line(middle_x, y1 - 1, x1 - 1, self.y + 1, 7);
+        }
+    }
+
+    fn bounds(&self) -> Option<Rect> {
+        Some(self.rect())
+    }
+
+    fn size(&self) -> (i32, i32) {
+        (self.x + self.width, self.y + self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Checkbox;
+    use crate::ui::{DispatchEvent, Widget};
+    use runty8_core::{Event, InputEvent, KeyState, MouseButton, MouseEvent};
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Toggle;
+
+    fn click(checkbox: &mut Checkbox<Toggle>, position: (i32, i32)) -> Vec<Toggle> {
+        let mut queue = vec![];
+        let event = Event::Input(InputEvent::Mouse(MouseEvent::Button {
+            button: MouseButton::Left,
+            state: KeyState::Down,
+        }));
+
+        checkbox.on_event(event, position, false, &mut DispatchEvent::new(&mut queue));
+
+        queue
+    }
+
+    #[test]
+    fn clicking_toggles_the_state() {
+        let mut checked = false;
+
+        for _ in click(&mut Checkbox::new(10, 10, 7, 7, checked, Toggle), (12, 16)) {
+            checked = !checked;
+        }
+        assert!(checked);
+
+        for _ in click(&mut Checkbox::new(10, 10, 7, 7, checked, Toggle), (10, 10)) {
+            checked = !checked;
+        }
+        assert!(!checked);
+    }
+
+    #[test]
+    fn clicks_outside_are_ignored() {
+        let mut checkbox = Checkbox::new(10, 10, 7, 7, false, Toggle);
+
+        assert!(click(&mut checkbox, (17, 12)).is_empty());
+        assert!(click(&mut checkbox, (9, 12)).is_empty());
+    }
+}
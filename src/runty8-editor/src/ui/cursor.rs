@@ -50,6 +50,7 @@ impl<'a, Msg: Copy + Debug> Widget for Cursor<'a, Msg> {
         &mut self,
         _: runty8_core::Event,
         cursor_position: (i32, i32),
+        _focused: bool,
         _: &mut DispatchEvent<Self::Msg>,
     ) {
         self.state.cursor_position = cursor_position;
@@ -0,0 +1,144 @@
+use runty8_core::{Event, InputEvent, Key, KeyState, KeyboardEvent, Pico8};
+use std::fmt::Debug;
+
+use super::{DispatchEvent, Element, FocusOrder, Widget};
+
+/// Keeps track of which of its focusable descendants has the keyboard,
+/// moving on to the next one when Tab is pressed.
+pub struct FocusScope<'a, Msg> {
+    state: &'a mut State,
+    content: Element<'a, Msg>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    /// The focusable widget that has the keyboard, counted in the order they get events.
+    focused: Option<usize>,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn focused(&self) -> Option<usize> {
+        self.focused
+    }
+
+    pub fn focus(&mut self, id: Option<usize>) {
+        self.focused = id;
+    }
+}
+
+impl<'a, Msg> FocusScope<'a, Msg> {
+    pub fn new(state: &'a mut State, content: impl Into<Element<'a, Msg>>) -> Self {
+        Self {
+            state,
+            content: content.into(),
+        }
+    }
+}
+
+impl<'a, Msg: Copy + Debug + 'a> Widget for FocusScope<'a, Msg> {
+    type Msg = Msg;
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        cursor_position: (i32, i32),
+        _focused: bool,
+        dispatch_event: &mut DispatchEvent<Self::Msg>,
+    ) {
+        let outer_focus = std::mem::replace(
+            &mut dispatch_event.focus,
+            FocusOrder {
+                focused: self.state.focused,
+                next_id: 0,
+            },
+        );
+
+        let focused = dispatch_event.focus_child(self.content.as_widget());
+        self.content
+            .as_widget_mut()
+            .on_event(event, cursor_position, focused, dispatch_event);
+
+        let focusable_count = dispatch_event.focus.next_id;
+        dispatch_event.focus = outer_focus;
+
+        if let Event::Input(InputEvent::Keyboard(KeyboardEvent {
+            key: Key::Tab,
+            state: KeyState::Down,
+        })) = event
+        {
+            self.state.focused = match self.state.focused {
+                _ if focusable_count == 0 => None,
+                Some(focused) => Some((focused + 1) % focusable_count),
+                None => Some(0),
+            };
+        }
+    }
+
+    fn draw(&mut self, pico8: &mut Pico8) {
+        self.content.as_widget_mut().draw(pico8);
+    }
+
+    fn size(&self) -> (i32, i32) {
+        self.content.as_widget().size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FocusScope, State};
+    use crate::ui::{text_input, text_input::TextInput, DispatchEvent, Tree, Widget};
+    use runty8_core::{Event, InputEvent, Key, KeyState, KeyboardEvent};
+
+    /// Presses `key` on two text inputs, returning their text.
+    fn press(focus: &mut State, inputs: &mut [text_input::State; 2], key: Key) -> (String, String) {
+        let [first, second] = &mut *inputs;
+        let mut queue: Vec<()> = vec![];
+        let mut scope = FocusScope::new(
+            focus,
+            Tree::new()
+                .push(TextInput::new(0, 0, 7, 8, None, first))
+                .push(TextInput::new(0, 8, 7, 8, None, second)),
+        );
+        let event = Event::Input(InputEvent::Keyboard(KeyboardEvent {
+            key,
+            state: KeyState::Down,
+        }));
+
+        scope.on_event(event, (0, 0), false, &mut DispatchEvent::new(&mut queue));
+        drop(scope);
+
+        (inputs[0].text().to_owned(), inputs[1].text().to_owned())
+    }
+
+    #[test]
+    fn tab_cycles_focus() {
+        let mut focus = State::new();
+        let mut inputs = [text_input::State::new(), text_input::State::new()];
+
+        assert_eq!(
+            press(&mut focus, &mut inputs, Key::A),
+            ("".into(), "".into())
+        );
+
+        press(&mut focus, &mut inputs, Key::Tab);
+        assert_eq!(focus.focused(), Some(0));
+        assert_eq!(
+            press(&mut focus, &mut inputs, Key::A),
+            ("a".into(), "".into())
+        );
+
+        press(&mut focus, &mut inputs, Key::Tab);
+        assert_eq!(focus.focused(), Some(1));
+        assert_eq!(
+            press(&mut focus, &mut inputs, Key::B),
+            ("a".into(), "b".into())
+        );
+
+        press(&mut focus, &mut inputs, Key::Tab);
+        assert_eq!(focus.focused(), Some(0));
+    }
+}
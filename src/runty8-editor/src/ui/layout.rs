@@ -0,0 +1,272 @@
+//! Containers that place their children one after the other,
+//! so that each child can be drawn as if it was at `(0, 0)`.
+use runty8_core::{Event, Pico8};
+use std::fmt::Debug;
+
+use super::{receives, translate, DispatchEvent, Element, Widget};
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Horizontal,
+    Vertical,
+}
+
+struct Layout<'a, Msg> {
+    direction: Direction,
+    x: i32,
+    y: i32,
+    spacing: i32,
+    children: Vec<Element<'a, Msg>>,
+}
+
+impl<'a, Msg: Copy + Debug + 'a> Layout<'a, Msg> {
+    fn new(direction: Direction, children: Vec<Element<'a, Msg>>) -> Self {
+        Self {
+            direction,
+            x: 0,
+            y: 0,
+            spacing: 0,
+            children,
+        }
+    }
+
+    /// Where each child's origin is.
+    fn offsets(&self) -> Vec<(i32, i32)> {
+        let mut next = (self.x, self.y);
+
+        self.children
+            .iter()
+            .map(|element| {
+                let offset = next;
+                let (width, height) = element.as_widget().size();
+
+                match self.direction {
+                    Direction::Horizontal => next.0 += width + self.spacing,
+                    Direction::Vertical => next.1 += height + self.spacing,
+                }
+
+                offset
+            })
+            .collect()
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        cursor_position: (i32, i32),
+        dispatch_event: &mut DispatchEvent<Msg>,
+    ) {
+        for ((x, y), element) in self.offsets().into_iter().zip(self.children.iter_mut()) {
+            let event = translate(event, x, y);
+
+            let cursor_position = (cursor_position.0 - x, cursor_position.1 - y);
+            let focused = dispatch_event.focus_child(element.as_widget());
+
+            if receives(element.as_widget(), event, cursor_position) {
+                element
+                    .as_widget_mut()
+                    .on_event(event, cursor_position, focused, dispatch_event);
+            }
+        }
+    }
+
+    fn draw(&mut self, pico8: &mut Pico8) {
+        for ((x, y), element) in self.offsets().into_iter().zip(self.children.iter_mut()) {
+            pico8.append_camera(-x, -y);
+            element.as_widget_mut().draw(pico8);
+            pico8.append_camera(x, y);
+        }
+    }
+
+    fn size(&self) -> (i32, i32) {
+        let sizes = self
+            .children
+            .iter()
+            .map(|element| element.as_widget().size());
+        let gaps = self.spacing * (self.children.len() as i32 - 1).max(0);
+
+        let (width, height) = match self.direction {
+            Direction::Horizontal => sizes.fold((gaps, 0), |(width, height), (w, h)| {
+                (width + w, height.max(h))
+            }),
+            Direction::Vertical => sizes.fold((0, gaps), |(width, height), (w, h)| {
+                (width.max(w), height + h)
+            }),
+        };
+
+        (self.x + width, self.y + height)
+    }
+}
+
+macro_rules! layout {
+    ($(#[$doc:meta])* $name:ident, $direction:expr) => {
+        $(#[$doc])*
+        pub struct $name<'a, Msg> {
+            layout: Layout<'a, Msg>,
+        }
+
+        impl<'a, Msg: Copy + Debug + 'a> $name<'a, Msg> {
+            pub fn new() -> Self {
+                Self::with_children(vec![])
+            }
+
+            pub fn with_children(children: Vec<Element<'a, Msg>>) -> Self {
+                Self {
+                    layout: Layout::new($direction, children),
+                }
+            }
+
+            pub fn push(mut self, element: impl Into<Element<'a, Msg>>) -> Self {
+                self.layout.children.push(element.into());
+                self
+            }
+
+            /// Moves the first child to `(x, y)`.
+            pub fn position(mut self, x: i32, y: i32) -> Self {
+                self.layout.x = x;
+                self.layout.y = y;
+                self
+            }
+
+            /// Leaves `spacing` pixels between each pair of children.
+            pub fn spacing(mut self, spacing: i32) -> Self {
+                self.layout.spacing = spacing;
+                self
+            }
+        }
+
+        impl<'a, Msg: Copy + Debug + 'a> Default for $name<'a, Msg> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<'a, Msg: Copy + Debug + 'a> Widget for $name<'a, Msg> {
+            type Msg = Msg;
+
+            fn on_event(
+                &mut self,
+                event: Event,
+                cursor_position: (i32, i32),
+                _focused: bool,
+                dispatch_event: &mut DispatchEvent<Self::Msg>,
+            ) {
+                self.layout.on_event(event, cursor_position, dispatch_event);
+            }
+
+            fn draw(&mut self, pico8: &mut Pico8) {
+                self.layout.draw(pico8);
+            }
+
+            fn size(&self) -> (i32, i32) {
+                self.layout.size()
+            }
+        }
+    };
+}
+
+layout!(
+    /// Places its children left to right.
+    Row,
+    Direction::Horizontal
+);
+layout!(
+    /// Places its children top to bottom.
+    Column,
+    Direction::Vertical
+);
+
+#[cfg(test)]
+mod tests {
+    use super::{Column, Row};
+    use crate::ui::{
+        button::{self, Button},
+        DispatchEvent, Tree, Widget,
+    };
+    use runty8_core::{Event, InputEvent, KeyState, MouseButton, MouseEvent};
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Msg {
+        First,
+        Second,
+    }
+
+    fn click(widget: &mut dyn Widget<Msg = Msg>, position: (i32, i32)) -> Vec<Msg> {
+        let mut queue = vec![];
+
+        for state in [KeyState::Down, KeyState::Up] {
+            let event = Event::Input(InputEvent::Mouse(MouseEvent::Button {
+                button: MouseButton::Left,
+                state,
+            }));
+            widget.on_event(event, position, false, &mut DispatchEvent::new(&mut queue));
+        }
+
+        queue
+    }
+
+    #[test]
+    fn rows_offset_their_children() {
+        let (mut first, mut second) = (button::State::new(), button::State::new());
+        let mut row = Row::new()
+            .push(Button::new(
+                0,
+                0,
+                8,
+                8,
+                Some(Msg::First),
+                &mut first,
+                Tree::new(),
+            ))
+            .push(Button::new(
+                0,
+                0,
+                8,
+                8,
+                Some(Msg::Second),
+                &mut second,
+                Tree::new(),
+            ))
+            .position(10, 20)
+            .spacing(2);
+
+        assert_eq!(click(&mut row, (10, 20)), [Msg::First]);
+        assert_eq!(click(&mut row, (17, 27)), [Msg::First]);
+        // The gap between the buttons.
+        assert!(click(&mut row, (19, 20)).is_empty());
+        assert_eq!(click(&mut row, (20, 20)), [Msg::Second]);
+        assert_eq!(click(&mut row, (27, 27)), [Msg::Second]);
+        assert!(click(&mut row, (28, 20)).is_empty());
+        assert_eq!(row.size(), (28, 28));
+    }
+
+    #[test]
+    fn columns_stack_their_children() {
+        let (mut first, mut second) = (button::State::new(), button::State::new());
+        let mut column = Column::new()
+            .push(Button::new(
+                0,
+                0,
+                8,
+                4,
+                Some(Msg::First),
+                &mut first,
+                Tree::new(),
+            ))
+            .push(Button::new(
+                0,
+                0,
+                8,
+                4,
+                Some(Msg::Second),
+                &mut second,
+                Tree::new(),
+            ))
+            .spacing(1);
+
+        assert_eq!(click(&mut column, (0, 3)), [Msg::First]);
+        assert!(click(&mut column, (0, 4)).is_empty());
+        assert_eq!(click(&mut column, (0, 5)), [Msg::Second]);
+        assert_eq!(column.size(), (8, 9));
+    }
+}
@@ -0,0 +1,191 @@
+use runty8_core::{Event, InputEvent, MouseEvent, Pico8};
+use std::fmt::Debug;
+
+use super::{translate, DispatchEvent, Element, Rect, Widget};
+
+/// Shows part of a taller child through a viewport, scrolled with the mouse wheel.
+pub struct Scroll<'a, Msg> {
+    viewport: Rect,
+    state: &'a mut State,
+    content: Element<'a, Msg>,
+}
+
+/// How far a [`Scroll`] is scrolled down, kept between frames.
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    offset: i32,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn offset(&self) -> i32 {
+        self.offset
+    }
+}
+
+impl<'a, Msg: Copy + Debug + 'a> Scroll<'a, Msg> {
+    /// Pixels scrolled for each line the wheel moves.
+    const LINE_HEIGHT: i32 = 8;
+    const SCROLLBAR_WIDTH: i32 = 2;
+
+    pub fn new(
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        state: &'a mut State,
+        content: impl Into<Element<'a, Msg>>,
+    ) -> Self {
+        Self {
+            viewport: Rect::new(x, y, width, height),
+            state,
+            content: content.into(),
+        }
+    }
+
+    /// How far down the content can be scrolled before its bottom shows.
+    fn max_offset(&self) -> i32 {
+        (self.content.as_widget().size().1 - self.viewport.height).max(0)
+    }
+
+    /// Where the content's origin is on the screen.
+    fn content_origin(&self) -> (i32, i32) {
+        (self.viewport.x, self.viewport.y - self.state.offset)
+    }
+}
+
+impl<'a, Msg: Copy + Debug + 'a> Widget for Scroll<'a, Msg> {
+    type Msg = Msg;
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        cursor_position: (i32, i32),
+        _focused: bool,
+        dispatch_event: &mut DispatchEvent<Self::Msg>,
+    ) {
+        if let Event::Input(InputEvent::Mouse(MouseEvent::Wheel { y })) = event {
+            if self.viewport.contains(cursor_position.0, cursor_position.1) {
+                self.state.offset -= y * Self::LINE_HEIGHT;
+            }
+        }
+        // Also keeps it in range when the content shrinks.
+        self.state.offset = self.state.offset.clamp(0, self.max_offset());
+
+        let (x, y) = self.content_origin();
+        let focused = dispatch_event.focus_child(self.content.as_widget());
+        self.content.as_widget_mut().on_event(
+            translate(event, x, y),
+            (cursor_position.0 - x, cursor_position.1 - y),
+            focused,
+            dispatch_event,
+        );
+    }
+
+    fn draw(&mut self, pico8: &mut Pico8) {
+        let Rect {
+            x,
+            y,
+            width,
+            height,
+        } = self.viewport;
+        let (content_x, content_y) = self.content_origin();
+
+        let previous_clip = pico8.clip_(x, y, width, height, true);
+        pico8.append_camera(-content_x, -content_y);
+        self.content.as_widget_mut().draw(pico8);
+        pico8.append_camera(content_x, content_y);
+        let (clip_x, clip_y, clip_w, clip_h) = previous_clip;
+        pico8.clip(clip_x, clip_y, clip_w, clip_h);
+
+        let max_offset = self.max_offset();
+        if max_offset > 0 {
+            let content_height = height + max_offset;
+            let thumb_height = (height * height / content_height).max(2);
+            let thumb_y = y + self.state.offset * (height - thumb_height) / max_offset;
+            let bar_x = x + width - Self::SCROLLBAR_WIDTH;
+
+            pico8.rectfill(bar_x, y, x + width - 1, y + height - 1, 5);
+            pico8.rectfill(bar_x, thumb_y, x + width - 1, thumb_y + thumb_height - 1, 6);
+        }
+    }
+
+    fn bounds(&self) -> Option<Rect> {
+        Some(self.viewport)
+    }
+
+    fn size(&self) -> (i32, i32) {
+        (
+            self.viewport.x + self.viewport.width,
+            self.viewport.y + self.viewport.height,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Scroll, State};
+    use crate::ui::{checkbox::Checkbox, DispatchEvent, Widget};
+    use runty8_core::{Event, InputEvent, KeyState, MouseButton, MouseEvent};
+
+    fn wheel(y: i32) -> Event {
+        Event::Input(InputEvent::Mouse(MouseEvent::Wheel { y }))
+    }
+
+    /// Sends `event` to a 16 pixel tall viewport at `(10, 10)`,
+    /// showing 40 pixels of content with a checkbox at its bottom.
+    fn send(state: &mut State, event: Event, cursor_position: (i32, i32)) -> Vec<()> {
+        let mut queue = vec![];
+        let mut scroll = Scroll::new(10, 10, 8, 16, state, Checkbox::new(0, 30, 8, 10, false, ()));
+
+        scroll.on_event(
+            event,
+            cursor_position,
+            false,
+            &mut DispatchEvent::new(&mut queue),
+        );
+
+        queue
+    }
+
+    #[test]
+    fn scrolling_clamps_to_the_content() {
+        let mut state = State::new();
+
+        send(&mut state, wheel(-1), (12, 12));
+        assert_eq!(state.offset(), 8);
+
+        send(&mut state, wheel(-10), (12, 12));
+        assert_eq!(state.offset(), 40 - 16);
+
+        send(&mut state, wheel(10), (12, 12));
+        assert_eq!(state.offset(), 0);
+    }
+
+    #[test]
+    fn ignores_the_wheel_outside_of_the_viewport() {
+        let mut state = State::new();
+
+        send(&mut state, wheel(-1), (12, 30));
+
+        assert_eq!(state.offset(), 0);
+    }
+
+    #[test]
+    fn passes_the_scrolled_cursor_position() {
+        let mut state = State::new();
+        let press = Event::Input(InputEvent::Mouse(MouseEvent::Button {
+            button: MouseButton::Left,
+            state: KeyState::Down,
+        }));
+
+        assert!(send(&mut state, press, (12, 25)).is_empty());
+
+        send(&mut state, wheel(-10), (12, 12));
+        // The bottom of the viewport is now the bottom of the checkbox.
+        assert_eq!(send(&mut state, press, (12, 25)), [()]);
+    }
+}
@@ -1,8 +1,9 @@
+use runty8_core::{Event, InputEvent, KeyState, MouseButton, MouseEvent, Pico8};
 use std::fmt::Debug;
 
 use super::{
     button::{self, Button},
-    DrawFn, Element, Tree,
+    DispatchEvent, DrawFn, Element, Rect, Tree, Widget,
 };
 use crate::pico8::Pico8EditorExt as _;
 
@@ -101,3 +102,228 @@ pub(crate) fn view<'a, Msg: Debug + Copy + 'a>(
         }))
         .into()
 }
+
+/// A horizontal track with a handle that can be dragged to pick a value in `min..=max`.
+pub struct Slider<'a, Msg> {
+    x: i32,
+    y: i32,
+    width: i32,
+    min: i32,
+    max: i32,
+    value: i32,
+    on_change: Box<dyn Fn(i32) -> Msg + 'a>,
+    state: &'a mut DragState,
+}
+
+/// Whether a [`Slider`]'s handle is being dragged, kept between frames.
+#[derive(Debug, Clone, Default)]
+pub struct DragState {
+    dragging: bool,
+}
+
+impl DragState {
+    pub fn new() -> Self {
+        Self { dragging: false }
+    }
+}
+
+impl<'a, Msg> Slider<'a, Msg> {
+    const HEIGHT: i32 = 5;
+
+    /// `min` and `max` can be given in either order.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        x: i32,
+        y: i32,
+        width: i32,
+        min: i32,
+        max: i32,
+        value: i32,
+        on_change: impl Fn(i32) -> Msg + 'a,
+        state: &'a mut DragState,
+    ) -> Self {
+        let (min, max) = (min.min(max), min.max(max));
+
+        Self {
+            x,
+            y,
+            width,
+            min,
+            max,
+            value: value.clamp(min, max),
+            on_change: Box::new(on_change),
+            state,
+        }
+    }
+
+    fn rect(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, Self::HEIGHT)
+    }
+
+    /// The value under the cursor, the track's ends being `min` and `max`.
+    fn value_at(&self, x: i32) -> i32 {
+        let steps = self.max - self.min;
+        let track = (self.width - 1).max(1);
+        let offset = (x - self.x).clamp(0, track);
+
+        // Rounded to the closest step.
+        self.min + (2 * offset * steps + track) / (2 * track)
+    }
+
+    fn handle_x(&self) -> i32 {
+        let steps = (self.max - self.min).max(1);
+
+        self.x + (self.value - self.min) * (self.width - 1) / steps
+    }
+
+    fn drag_to(&mut self, x: i32, dispatch_event: &mut DispatchEvent<'_, Msg>) {
+        let value = self.value_at(x);
+
+        if value != self.value {
+            self.value = value;
+            dispatch_event.call((self.on_change)(value));
+        }
+    }
+}
+
+impl<'a, Msg: Copy + Debug + 'a> Widget for Slider<'a, Msg> {
+    type Msg = Msg;
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        cursor_position: (i32, i32),
+        _focused: bool,
+        dispatch_event: &mut DispatchEvent<'_, Msg>,
+    ) {
+        use Event::Input;
+
+        match event {
+            Input(InputEvent::Mouse(MouseEvent::Button {
+                button: MouseButton::Left,
+                state: KeyState::Down,
+            })) if self.rect().contains(cursor_position.0, cursor_position.1) => {
+                self.state.dragging = true;
+                self.drag_to(cursor_position.0, dispatch_event);
+            }
+            Input(InputEvent::Mouse(MouseEvent::Button {
+                button: MouseButton::Left,
+                state: KeyState::Up,
+            })) => {
+                self.state.dragging = false;
+            }
+            // Keeps following the cursor even when it leaves the track.
+            Input(InputEvent::Mouse(MouseEvent::Move { .. })) if self.state.dragging => {
+                self.drag_to(cursor_position.0, dispatch_event);
+            }
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, pico8: &mut Pico8) {
+        let middle_y = self.y + Self::HEIGHT / 2;
+        let handle_x = self.handle_x();
+
+        pico8.line(self.x, middle_y, self.x + self.width - 1, middle_y, 5);
+        pico8.rectfill(
+            handle_x - 1,
+            self.y,
+            handle_x + 1,
+            self.y + Self::HEIGHT - 1,
+            7,
+        );
+    }
+
+    fn bounds(&self) -> Option<Rect> {
+        Some(self.rect())
+    }
+
+    fn size(&self) -> (i32, i32) {
+        (self.x + self.width, self.y + Self::HEIGHT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DragState, Slider};
+    use crate::ui::{DispatchEvent, Widget};
+    use runty8_core::{Event, InputEvent, KeyState, MouseButton, MouseEvent};
+
+    fn mouse_button(state: KeyState) -> Event {
+        Event::Input(InputEvent::Mouse(MouseEvent::Button {
+            button: MouseButton::Left,
+            state,
+        }))
+    }
+
+    fn mouse_move(x: i32, y: i32) -> Event {
+        Event::Input(InputEvent::Mouse(MouseEvent::Move { x, y }))
+    }
+
+    /// Sends `events` to a slider from 0 to 15 on `10..26`, each with the cursor where it was moved to.
+    fn values(state: &mut DragState, events: &[((i32, i32), Event)]) -> Vec<i32> {
+        let mut queue = vec![];
+
+        for &(cursor_position, event) in events {
+            let mut slider = Slider::new(10, 20, 16, 0, 15, 0, |value| value, state);
+            slider.on_event(
+                event,
+                cursor_position,
+                false,
+                &mut DispatchEvent::new(&mut queue),
+            );
+        }
+
+        queue
+    }
+
+    #[test]
+    fn dragging_dispatches_the_new_value() {
+        let mut state = DragState::new();
+
+        let values = values(
+            &mut state,
+            &[
+                ((13, 22), mouse_button(KeyState::Down)),
+                ((18, 22), mouse_move(18, 22)),
+                // Past the end, and off the track.
+                ((40, 30), mouse_move(40, 30)),
+                ((40, 30), mouse_button(KeyState::Up)),
+                ((12, 22), mouse_move(12, 22)),
+            ],
+        );
+
+        assert_eq!(values, [3, 8, 15]);
+    }
+
+    #[test]
+    fn presses_outside_the_track_dont_drag() {
+        let mut state = DragState::new();
+
+        let values = values(
+            &mut state,
+            &[
+                ((9, 22), mouse_button(KeyState::Down)),
+                ((18, 22), mouse_move(18, 22)),
+            ],
+        );
+
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn reversed_bounds_are_swapped() {
+        let mut state = DragState::new();
+        let mut queue = vec![];
+        let mut slider = Slider::new(10, 20, 16, 15, 0, 20, |value| value, &mut state);
+        assert_eq!(slider.value, 15);
+
+        slider.on_event(
+            mouse_button(KeyState::Down),
+            (10, 22),
+            false,
+            &mut DispatchEvent::new(&mut queue),
+        );
+        assert_eq!(queue, [0]);
+    }
+}
@@ -30,6 +30,7 @@ impl<'a, Msg: Copy + Debug> Widget for Text<'a, Msg> {
         &mut self,
         _event: runty8_core::Event,
         _cursor_position: (i32, i32),
+        _focused: bool,
         _dispatch_event: &mut DispatchEvent<Self::Msg>,
     ) {
     }
@@ -37,4 +38,8 @@ impl<'a, Msg: Copy + Debug> Widget for Text<'a, Msg> {
     fn draw(&mut self, pico8: &mut Pico8) {
         pico8.print(self.text, self.x, self.y, self.color);
     }
+
+    fn size(&self) -> (i32, i32) {
+        (self.x + 4 * self.text.chars().count() as i32, self.y + 5)
+    }
 }
@@ -0,0 +1,274 @@
+use runty8_core::{Color, Event, InputEvent, Key, KeyState, KeyboardEvent, Pico8};
+use std::fmt::Debug;
+
+use super::{DispatchEvent, Widget};
+
+/// An editable line of text, typed with the keyboard while it's focused (see [`super::focus`]).
+pub struct TextInput<'a, Msg> {
+    x: i32,
+    y: i32,
+    color: Color,
+    max_length: usize,
+    on_change: Option<Msg>,
+    state: &'a mut State,
+}
+
+/// A [`TextInput`]'s text and caret, kept between frames.
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    text: String,
+    /// How many characters come before the caret.
+    caret: usize,
+    /// Time since the caret last moved, for blinking.
+    blink_millis: f64,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts with `text`, and the caret at its end.
+    pub fn with_text(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            caret: text.chars().count(),
+            blink_millis: 0.0,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    fn byte_index(&self, caret: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(caret)
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+
+    /// Applies a key press, returning whether the text changed.
+    fn press(&mut self, key: Key, max_length: usize) -> bool {
+        self.blink_millis = 0.0;
+
+        match key {
+            Key::Backspace if self.caret > 0 => {
+                self.caret -= 1;
+                self.text.remove(self.byte_index(self.caret));
+                true
+            }
+            Key::LeftArrow => {
+                self.caret = self.caret.saturating_sub(1);
+                false
+            }
+            Key::RightArrow => {
+                self.caret = (self.caret + 1).min(self.text.chars().count());
+                false
+            }
+            key => match printable(key) {
+                Some(char) if self.text.chars().count() < max_length => {
+                    self.text.insert(self.byte_index(self.caret), char);
+                    self.caret += 1;
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+}
+
+/// The character a key types, if any.
+fn printable(key: Key) -> Option<char> {
+    let char = match key {
+        Key::A => 'a',
+        Key::B => 'b',
+        Key::C => 'c',
+        Key::D => 'd',
+        Key::E => 'e',
+        Key::F => 'f',
+        Key::G => 'g',
+        Key::H => 'h',
+        Key::I => 'i',
+        Key::J => 'j',
+        Key::K => 'k',
+        Key::L => 'l',
+        Key::M => 'm',
+        Key::N => 'n',
+        Key::O => 'o',
+        Key::P => 'p',
+        Key::Q => 'q',
+        Key::R => 'r',
+        Key::S => 's',
+        Key::T => 't',
+        Key::U => 'u',
+        Key::V => 'v',
+        Key::W => 'w',
+        Key::X => 'x',
+        Key::Y => 'y',
+        Key::Z => 'z',
+        Key::Digit0 => '0',
+        Key::Digit1 => '1',
+        Key::Digit2 => '2',
+        Key::Digit3 => '3',
+        Key::Digit4 => '4',
+        Key::Digit5 => '5',
+        Key::Digit6 => '6',
+        Key::Digit7 => '7',
+        Key::Digit8 => '8',
+        Key::Digit9 => '9',
+        _ => return None,
+    };
+
+    Some(char)
+}
+
+impl<'a, Msg> TextInput<'a, Msg> {
+    const BLINK_MILLIS: f64 = 500.0;
+
+    pub fn new(
+        x: i32,
+        y: i32,
+        color: Color,
+        max_length: usize,
+        on_change: Option<Msg>,
+        state: &'a mut State,
+    ) -> Self {
+        Self {
+            x,
+            y,
+            color,
+            max_length,
+            on_change,
+            state,
+        }
+    }
+}
+
+impl<'a, Msg: Copy + Debug + 'a> Widget for TextInput<'a, Msg> {
+    type Msg = Msg;
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        _cursor_position: (i32, i32),
+        focused: bool,
+        dispatch_event: &mut DispatchEvent<'_, Msg>,
+    ) {
+        match event {
+            Event::Input(InputEvent::Keyboard(KeyboardEvent {
+                key,
+                state: KeyState::Down,
+            })) if focused => {
+                let changed = self.state.press(key, self.max_length);
+
+                if let (true, Some(on_change)) = (changed, self.on_change) {
+                    dispatch_event.call(on_change);
+                }
+            }
+            Event::Tick { delta_millis } => {
+                self.state.blink_millis =
+                    (self.state.blink_millis + delta_millis) % (2.0 * Self::BLINK_MILLIS);
+            }
+            _ => {}
+        }
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn draw(&mut self, pico8: &mut Pico8) {
+        pico8.print(&self.state.text, self.x, self.y, self.color);
+
+        if self.state.blink_millis < Self::BLINK_MILLIS {
+            let caret_x = self.x + 4 * self.state.caret as i32 - 1;
+            pico8.line(caret_x, self.y - 1, caret_x, self.y + 5, self.color);
+        }
+    }
+
+    fn size(&self) -> (i32, i32) {
+        (self.x + 4 * self.max_length as i32, self.y + 5)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{State, TextInput};
+    use crate::ui::{DispatchEvent, Widget};
+    use runty8_core::{Event, InputEvent, Key, KeyState, KeyboardEvent};
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Changed;
+
+    /// Presses (and releases) `keys` on an input of at most 5 characters,
+    /// returning how many times it reported a change.
+    fn type_keys(state: &mut State, keys: &[Key]) -> usize {
+        let mut queue = vec![];
+
+        for &key in keys {
+            for key_state in [KeyState::Down, KeyState::Up] {
+                let event = Event::Input(InputEvent::Keyboard(KeyboardEvent {
+                    key,
+                    state: key_state,
+                }));
+                let mut input = TextInput::new(0, 0, 7, 5, Some(Changed), state);
+
+                input.on_event(event, (0, 0), true, &mut DispatchEvent::new(&mut queue));
+            }
+        }
+
+        queue.len()
+    }
+
+    #[test]
+    fn typing_edits_the_text() {
+        let mut state = State::new();
+
+        let changes = type_keys(
+            &mut state,
+            &[Key::H, Key::I, Key::LeftArrow, Key::Digit1, Key::RightArrow],
+        );
+        assert_eq!(state.text(), "h1i");
+        assert_eq!(state.caret(), 3);
+        assert_eq!(changes, 3);
+
+        type_keys(&mut state, &[Key::LeftArrow, Key::Backspace]);
+        assert_eq!(state.text(), "hi");
+        assert_eq!(state.caret(), 1);
+    }
+
+    #[test]
+    fn stops_at_the_max_length() {
+        let mut state = State::with_text("abcd");
+
+        let changes = type_keys(&mut state, &[Key::E, Key::F]);
+
+        assert_eq!(state.text(), "abcde");
+        assert_eq!(changes, 1);
+    }
+
+    #[test]
+    fn ignores_keys_that_dont_type() {
+        let mut state = State::with_text("a");
+
+        let changes = type_keys(
+            &mut state,
+            &[Key::Control, Key::F1, Key::RightArrow, Key::UpArrow],
+        );
+        assert_eq!(state.text(), "a");
+        assert_eq!(changes, 0);
+
+        // Nothing to erase before the caret.
+        let changes = type_keys(
+            &mut state,
+            &[Key::LeftArrow, Key::LeftArrow, Key::Backspace],
+        );
+        assert_eq!(state.text(), "a");
+        assert_eq!(changes, 0);
+    }
+}
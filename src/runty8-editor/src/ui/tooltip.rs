@@ -0,0 +1,184 @@
+use runty8_core::{Event, InputEvent, KeyState, MouseEvent, Pico8};
+use std::fmt::Debug;
+
+use super::{DispatchEvent, Element, Rect, Widget};
+
+/// Shows some text next to the cursor once it's been over its child (inside the child's
+/// [`Widget::bounds`]) for a while. Events still go to the child as if it wasn't there.
+pub struct Tooltip<'a, Msg> {
+    text: &'a str,
+    delay_frames: u32,
+    state: &'a mut State,
+    content: Element<'a, Msg>,
+}
+
+/// How long the cursor has been over a [`Tooltip`]'s child, kept between frames.
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    hovered_frames: u32,
+    cursor_position: (i32, i32),
+    visible: bool,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+}
+
+impl<'a, Msg: Copy + Debug + 'a> Tooltip<'a, Msg> {
+    pub fn new(
+        text: &'a str,
+        delay_frames: u32,
+        state: &'a mut State,
+        content: impl Into<Element<'a, Msg>>,
+    ) -> Self {
+        Self {
+            text,
+            delay_frames,
+            state,
+            content: content.into(),
+        }
+    }
+
+    fn hovered(&self) -> bool {
+        let (x, y) = self.state.cursor_position;
+
+        self.content
+            .as_widget()
+            .bounds()
+            .map_or(false, |bounds| bounds.contains(x, y))
+    }
+}
+
+impl<'a, Msg: Copy + Debug + 'a> Widget for Tooltip<'a, Msg> {
+    type Msg = Msg;
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        cursor_position: (i32, i32),
+        focused: bool,
+        dispatch_event: &mut DispatchEvent<Self::Msg>,
+    ) {
+        self.content
+            .as_widget_mut()
+            .on_event(event, cursor_position, focused, dispatch_event);
+
+        self.state.cursor_position = cursor_position;
+
+        match event {
+            Event::Tick { .. } if self.hovered() => {
+                self.state.hovered_frames = self.state.hovered_frames.saturating_add(1);
+                self.state.visible |= self.state.hovered_frames >= self.delay_frames;
+            }
+            // Clicking hides the tooltip, and starts the delay over.
+            Event::Input(InputEvent::Mouse(MouseEvent::Button {
+                state: KeyState::Down,
+                ..
+            }))
+            | Event::Tick { .. } => {
+                self.state.hovered_frames = 0;
+                self.state.visible = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, pico8: &mut Pico8) {
+        self.content.as_widget_mut().draw(pico8);
+
+        if self.state.visible {
+            let width = 4 * self.text.chars().count() as i32 + 3;
+            let height = 9;
+            // Below and to the right of the cursor, unless that's off screen.
+            let (cursor_x, cursor_y) = self.state.cursor_position;
+            let x = (cursor_x + 4).min(128 - width).max(0);
+            let y = if cursor_y + 6 + height <= 128 {
+                cursor_y + 6
+            } else {
+                cursor_y - 2 - height
+            };
+
+            pico8.rectfill(x, y, x + width - 1, y + height - 1, 1);
+            pico8.rect(x, y, x + width - 1, y + height - 1, 7);
+            pico8.print(self.text, x + 2, y + 2, 7);
+        }
+    }
+
+    fn focusable(&self) -> bool {
+        self.content.as_widget().focusable()
+    }
+
+    fn bounds(&self) -> Option<Rect> {
+        self.content.as_widget().bounds()
+    }
+
+    fn size(&self) -> (i32, i32) {
+        self.content.as_widget().size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{State, Tooltip};
+    use crate::ui::{checkbox::Checkbox, DispatchEvent, Widget};
+    use runty8_core::{Event, InputEvent, KeyState, MouseButton, MouseEvent};
+
+    const TICK: Event = Event::Tick { delta_millis: 33.0 };
+
+    /// Sends `event` to a tooltip shown after 3 frames, over an 8x8 checkbox at `(10, 10)`.
+    fn send(state: &mut State, event: Event, cursor_position: (i32, i32)) -> Vec<()> {
+        let mut queue = vec![];
+        let mut tooltip = Tooltip::new("TOGGLE", 3, state, Checkbox::new(10, 10, 8, 8, false, ()));
+
+        tooltip.on_event(
+            event,
+            cursor_position,
+            false,
+            &mut DispatchEvent::new(&mut queue),
+        );
+
+        queue
+    }
+
+    #[test]
+    fn shows_up_after_the_delay() {
+        let mut state = State::new();
+
+        send(&mut state, TICK, (12, 12));
+        send(&mut state, TICK, (12, 12));
+        assert!(!state.visible());
+
+        send(&mut state, TICK, (12, 12));
+        assert!(state.visible());
+
+        // Leaving starts over.
+        send(&mut state, TICK, (30, 30));
+        assert!(!state.visible());
+        send(&mut state, TICK, (12, 12));
+        assert!(!state.visible());
+    }
+
+    #[test]
+    fn clicks_go_through_and_hide_it() {
+        let mut state = State::new();
+        let press = Event::Input(InputEvent::Mouse(MouseEvent::Button {
+            button: MouseButton::Left,
+            state: KeyState::Down,
+        }));
+
+        for _ in 0..3 {
+            send(&mut state, TICK, (12, 12));
+        }
+        assert_eq!(send(&mut state, press, (12, 12)), [()]);
+        assert!(!state.visible());
+
+        send(&mut state, TICK, (12, 12));
+        assert!(!state.visible());
+    }
+}
@@ -0,0 +1,181 @@
+//! Audio output through ALSA, which is loaded at runtime so that machines without it still run games
+//! (silently). On the Steam Deck, ALSA output goes through PipeWire.
+//! Other platforms don't output any audio yet.
+
+use runty8_core::Audio;
+
+/// Starts outputting `audio` on a background thread, if possible.
+pub(crate) fn play(audio: Audio) {
+    #[cfg(target_os = "linux")]
+    linux::play(audio);
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = audio;
+        log::warn!("Audio output isn't supported on this platform yet");
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use runty8_core::{Audio, SAMPLE_RATE};
+    use std::ffi::{c_char, c_int, c_long, c_uint, c_ulong, c_void, CStr};
+
+    const SND_PCM_STREAM_PLAYBACK: c_int = 0;
+    const SND_PCM_FORMAT_S16_LE: c_int = 2;
+    const SND_PCM_ACCESS_RW_INTERLEAVED: c_int = 3;
+    const LATENCY_MICROS: c_uint = 50_000;
+    const BUFFER_SAMPLES: usize = 512;
+
+    type Open = unsafe extern "C" fn(*mut *mut c_void, *const c_char, c_int, c_int) -> c_int;
+    type SetParams =
+        unsafe extern "C" fn(*mut c_void, c_int, c_int, c_uint, c_uint, c_int, c_uint) -> c_int;
+    type WriteI = unsafe extern "C" fn(*mut c_void, *const c_void, c_ulong) -> c_long;
+    type Recover = unsafe extern "C" fn(*mut c_void, c_int, c_int) -> c_int;
+
+    /// The few libasound functions needed to play audio.
+    struct Alsa {
+        open: Open,
+        set_params: SetParams,
+        write_i: WriteI,
+        recover: Recover,
+    }
+
+    impl Alsa {
+        fn load() -> Result<Self, String> {
+            unsafe {
+                let library = libc::dlopen(
+                    b"libasound.so.2\0".as_ptr() as *const c_char,
+                    libc::RTLD_NOW | libc::RTLD_LOCAL,
+                );
+                if library.is_null() {
+                    return Err("Couldn't load libasound.so.2".to_owned());
+                }
+
+                let symbol = |name: &[u8]| {
+                    let symbol = libc::dlsym(library, name.as_ptr() as *const c_char);
+                    if symbol.is_null() {
+                        let name = CStr::from_bytes_with_nul(name).unwrap_or_default();
+                        Err(format!("Couldn't find {name:?} in libasound"))
+                    } else {
+                        Ok(symbol)
+                    }
+                };
+
+                Ok(Self {
+                    open: std::mem::transmute::<*mut c_void, Open>(symbol(b"snd_pcm_open\0")?),
+                    set_params: std::mem::transmute::<*mut c_void, SetParams>(symbol(
+                        b"snd_pcm_set_params\0",
+                    )?),
+                    write_i: std::mem::transmute::<*mut c_void, WriteI>(symbol(
+                        b"snd_pcm_writei\0",
+                    )?),
+                    recover: std::mem::transmute::<*mut c_void, Recover>(symbol(
+                        b"snd_pcm_recover\0",
+                    )?),
+                })
+            }
+        }
+
+        /// Opens the default output device, as 16 bit mono.
+        fn open_default(&self) -> Result<*mut c_void, String> {
+            let mut pcm = std::ptr::null_mut();
+
+            unsafe {
+                let error = (self.open)(
+                    &mut pcm,
+                    b"default\0".as_ptr() as *const c_char,
+                    SND_PCM_STREAM_PLAYBACK,
+                    0,
+                );
+                if error < 0 {
+                    return Err(format!("Couldn't open audio device (error {error})"));
+                }
+
+                let error = (self.set_params)(
+                    pcm,
+                    SND_PCM_FORMAT_S16_LE,
+                    SND_PCM_ACCESS_RW_INTERLEAVED,
+                    1,
+                    SAMPLE_RATE,
+                    1,
+                    LATENCY_MICROS,
+                );
+                if error < 0 {
+                    return Err(format!("Couldn't configure audio device (error {error})"));
+                }
+            }
+
+            Ok(pcm)
+        }
+
+        /// Writes all of `buffer`, blocking until there's room in the device's buffer
+        /// (which paces the audio thread). Underruns are recovered from.
+        fn write_all(&self, pcm: *mut c_void, buffer: &[i16]) -> Result<(), String> {
+            let mut remaining = buffer;
+
+            while !remaining.is_empty() {
+                let written = unsafe {
+                    (self.write_i)(
+                        pcm,
+                        remaining.as_ptr() as *const c_void,
+                        remaining.len() as _,
+                    )
+                };
+
+                if written < 0 {
+                    let error = unsafe { (self.recover)(pcm, written as c_int, 1) };
+                    if error < 0 {
+                        return Err(format!("Audio device failed (error {error})"));
+                    }
+                } else {
+                    // The device can take fewer samples than it was given.
+                    remaining = &remaining[(written as usize).min(remaining.len())..];
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    pub(super) fn play(audio: Audio) {
+        let alsa = match Alsa::load() {
+            Ok(alsa) => alsa,
+            Err(error) => {
+                log::warn!("{error}, sound is disabled");
+                return;
+            }
+        };
+
+        // Raw pointers aren't `Send`, so the device is opened on the audio thread itself.
+        let spawned = std::thread::Builder::new()
+            .name("runty8-audio".to_owned())
+            .spawn(move || {
+                let pcm = match alsa.open_default() {
+                    Ok(pcm) => pcm,
+                    Err(error) => {
+                        log::warn!("{error}, sound is disabled");
+                        return;
+                    }
+                };
+
+                let mut samples = [0.0; BUFFER_SAMPLES];
+                let mut buffer = [0_i16; BUFFER_SAMPLES];
+                loop {
+                    audio.fill(&mut samples);
+                    for (output, sample) in buffer.iter_mut().zip(samples) {
+                        *output = (sample * i16::MAX as f32) as i16;
+                    }
+
+                    if let Err(error) = alsa.write_all(pcm, &buffer) {
+                        log::error!("{error}, sound is disabled");
+                        return;
+                    }
+                }
+            });
+
+        if let Err(error) = spawned {
+            log::error!("Couldn't start audio thread: {error}");
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Saving what's on screen to disk.
+
+use crate::Hotkey;
+use runty8_core::{Event, RunConfig, ScreenshotScale, COLORS, EXTENDED_COLORS};
+use runty8_winit::ScreenInfo;
+use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Screenshots and GIF recordings, triggered by their keys.
+pub(crate) struct Captures {
+    screenshot_key: Hotkey,
+    screenshot_scale: ScreenshotScale,
+    gif_key: Hotkey,
+    // The game's screen size, in pixels.
+    resolution: (usize, usize),
+    dir: String,
+    // Captures are saved on the next draw, so they include a complete frame.
+    screenshot_requested: bool,
+    gif_requested: bool,
+    recorder: Option<Recorder>,
+}
+
+impl Captures {
+    pub(crate) fn new(config: &RunConfig) -> Self {
+        Self {
+            screenshot_key: Hotkey::new(config.screenshot_key),
+            screenshot_scale: config.screenshot_scale,
+            gif_key: Hotkey::new(config.gif_key),
+            resolution: resolution(config),
+            dir: config.capture_dir.clone().unwrap_or_else(|| ".".to_owned()),
+            screenshot_requested: false,
+            gif_requested: false,
+            recorder: config.gif_key.map(|_| Recorder::new(config)),
+        }
+    }
+
+    pub(crate) fn on_event(&mut self, event: &Event) {
+        self.screenshot_requested |= self.screenshot_key.pressed(event);
+        self.gif_requested |= self.gif_key.pressed(event);
+    }
+
+    pub(crate) fn on_draw(&mut self, pixels: &[u8], screen_info: &ScreenInfo) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(pixels, instant::now());
+        }
+
+        if std::mem::take(&mut self.screenshot_requested) {
+            let scale = match self.screenshot_scale {
+                ScreenshotScale::Native => 1,
+                ScreenshotScale::Window => {
+                    let viewport = screen_info.viewport();
+                    let (width, height) = self.resolution;
+                    (viewport.width as usize / width)
+                        .min(viewport.height as usize / height)
+                        .max(1)
+                }
+            };
+
+            match save_screenshot(pixels, self.resolution, &self.dir, scale) {
+                Ok(path) => log::info!("Saved screenshot to {path}"),
+                Err(error) => log::error!("{error}"),
+            }
+        }
+
+        if std::mem::take(&mut self.gif_requested) {
+            if let Some(recorder) = self.recorder.as_ref() {
+                match recorder.save(&self.dir) {
+                    Ok(path) => log::info!("Saved recording to {path}"),
+                    Err(error) => log::error!("{error}"),
+                }
+            }
+        }
+    }
+}
+
+fn resolution(config: &RunConfig) -> (usize, usize) {
+    let (width, height) = config.resolution;
+
+    (width as usize, height as usize)
+}
+
+/// Upscales a `width` by `height` RGB buffer by a whole `scale`.
+fn scale_pixels(pixels: &[u8], (width, height): (usize, usize), scale: usize) -> Vec<u8> {
+    let mut scaled = Vec::with_capacity(width * height * scale * scale * 3);
+
+    for y in 0..height * scale {
+        for x in 0..width * scale {
+            let index = 3 * (x / scale + (y / scale) * width);
+            scaled.extend_from_slice(&pixels[index..index + 3]);
+        }
+    }
+
+    scaled
+}
+
+/// Milliseconds since the unix epoch, used to give captures unique names.
+fn timestamp() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default()
+}
+
+/// Saves an RGB buffer of the given `resolution` as `screenshot-<timestamp>.png` in `dir`,
+/// returning its path.
+fn save_screenshot(
+    pixels: &[u8],
+    resolution: (usize, usize),
+    dir: &str,
+    scale: usize,
+) -> Result<String, String> {
+    let path = format!("{dir}/screenshot-{}.png", timestamp());
+    let file = std::fs::File::create(&path)
+        .map_err(|error| format!("Couldn't create screenshot {path}: {error}"))?;
+
+    let (width, height) = (resolution.0 * scale, resolution.1 * scale);
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&scale_pixels(pixels, resolution, scale)))
+        .map_err(|error| format!("Couldn't write screenshot {path}: {error}"))?;
+
+    Ok(path)
+}
+
+/// Keeps the last few seconds of frames around, to save them as a GIF.
+struct Recorder {
+    // Palette indices, one byte per pixel.
+    frames: VecDeque<Vec<u8>>,
+    resolution: (usize, usize),
+    max_frames: usize,
+    frame_millis: f64,
+    last_frame_time: Option<f64>,
+    // See `palette_indices`.
+    palette_indices: HashMap<[u8; 3], u8>,
+}
+
+impl Recorder {
+    fn new(config: &RunConfig) -> Self {
+        let max_frames = (config.gif_length_secs * config.gif_fps) as usize;
+
+        Self {
+            frames: VecDeque::with_capacity(max_frames),
+            resolution: resolution(config),
+            max_frames,
+            frame_millis: 1000.0 / config.gif_fps as f64,
+            last_frame_time: None,
+            palette_indices: palette_indices(),
+        }
+    }
+
+    /// Records an RGB buffer drawn at `time` (in milliseconds),
+    /// unless too little time has passed since the last recorded frame.
+    fn record(&mut self, pixels: &[u8], time: f64) {
+        if let Some(last_frame_time) = self.last_frame_time {
+            if time - last_frame_time < self.frame_millis {
+                return;
+            }
+        }
+        // Stay on the capture rate's grid, so slow draws don't make the GIF drift.
+        self.last_frame_time = Some(match self.last_frame_time {
+            Some(last_frame_time) if time - last_frame_time < 2.0 * self.frame_millis => {
+                last_frame_time + self.frame_millis
+            }
+            _ => time,
+        });
+
+        // Once full, the oldest frame's buffer is reused for the new one.
+        let mut frame = if self.frames.len() == self.max_frames {
+            self.frames.pop_front().unwrap_or_default()
+        } else {
+            Vec::with_capacity(pixels.len() / 3)
+        };
+        quantize_into(pixels, &self.palette_indices, &mut frame);
+        self.frames.push_back(frame);
+    }
+
+    /// Saves the recorded frames as `recording-<timestamp>.gif` in `dir`, returning its path.
+    fn save(&self, dir: &str) -> Result<String, String> {
+        let path = format!("{dir}/recording-{}.gif", timestamp());
+        let delay_centis = (self.frame_millis / 10.0).round() as u16;
+        let (width, height) = self.resolution;
+        let gif = crate::gif::encode(
+            self.frames.iter().map(Vec::as_slice),
+            (width as u16, height as u16),
+            &palette(),
+            delay_centis,
+        );
+
+        std::fs::write(&path, gif)
+            .map_err(|error| format!("Couldn't write recording {path}: {error}"))?;
+
+        Ok(path)
+    }
+}
+
+/// The 16 Pico8 colors followed by the 16 extended ones.
+fn palette() -> [u32; 32] {
+    let mut palette = [0; 32];
+    palette[..16].copy_from_slice(&COLORS);
+    palette[16..].copy_from_slice(&EXTENDED_COLORS);
+
+    palette
+}
+
+/// The index in [`palette`] of each of its colors' RGB bytes.
+fn palette_indices() -> HashMap<[u8; 3], u8> {
+    let mut indices = HashMap::new();
+
+    for (index, color) in palette().into_iter().enumerate() {
+        let [_, r, g, b] = color.to_be_bytes();
+        indices.entry([r, g, b]).or_insert(index as u8);
+    }
+
+    indices
+}
+
+#[cfg(test)]
+fn quantize(pixels: &[u8]) -> Vec<u8> {
+    let mut indices = vec![];
+    quantize_into(pixels, &palette_indices(), &mut indices);
+
+    indices
+}
+
+/// Maps each RGB pixel to its index in [`palette`], replacing what's in `indices`.
+///
+/// The screen only shows palette colors, which are looked up in `palette_indices`.
+/// Anything else (e.g. from a custom screen palette) gets the closest color.
+fn quantize_into(pixels: &[u8], palette_indices: &HashMap<[u8; 3], u8>, indices: &mut Vec<u8>) {
+    indices.clear();
+    indices.extend(pixels.chunks_exact(3).map(|rgb| {
+        palette_indices
+            .get(rgb)
+            .copied()
+            .unwrap_or_else(|| closest_color(rgb))
+    }));
+}
+
+/// The index of the color in [`palette`] closest to `rgb`.
+fn closest_color(rgb: &[u8]) -> u8 {
+    let palette = palette();
+    let distance = |color: u32| {
+        let [_, r, g, b] = color.to_be_bytes();
+
+        [(r, rgb[0]), (g, rgb[1]), (b, rgb[2])]
+            .into_iter()
+            .map(|(a, b)| (a as i32 - b as i32).pow(2))
+            .sum::<i32>()
+    };
+
+    (0..palette.len())
+        .min_by_key(|&index| distance(palette[index]))
+        .unwrap() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{palette, quantize, scale_pixels, Recorder};
+    use runty8_core::RunConfig;
+
+    #[test]
+    fn scaling_repeats_each_pixel() {
+        let mut pixels = vec![0; 128 * 128 * 3];
+        pixels[..3].copy_from_slice(&[1, 2, 3]);
+
+        let scaled = scale_pixels(&pixels, (128, 128), 2);
+
+        assert_eq!(scaled.len(), 256 * 256 * 3);
+        assert_eq!(scaled[..6], [1, 2, 3, 1, 2, 3]);
+        assert_eq!(scaled[256 * 3..256 * 3 + 6], [1, 2, 3, 1, 2, 3]);
+        assert_eq!(scaled[6..9], [0, 0, 0]);
+    }
+
+    #[test]
+    fn native_scale_is_unchanged() {
+        let pixels: Vec<u8> = (0..128 * 128 * 3).map(|i| i as u8).collect();
+
+        assert_eq!(scale_pixels(&pixels, (128, 128), 1), pixels);
+    }
+
+    #[test]
+    fn scaling_keeps_the_aspect_ratio() {
+        let pixels = vec![0; 4 * 2 * 3];
+
+        assert_eq!(scale_pixels(&pixels, (4, 2), 3).len(), 12 * 6 * 3);
+    }
+
+    #[test]
+    fn quantize_finds_palette_colors() {
+        // Black, red (0xFF004D), almost white (0xFFF1E8) and the extended dark red (0xBE1250).
+        let pixels = [
+            0, 0, 0, 0xFF, 0x00, 0x4D, 0xFE, 0xF0, 0xE8, 0xBE, 0x12, 0x50,
+        ];
+
+        assert_eq!(quantize(&pixels), vec![0, 8, 7, 16 + 8]);
+
+        // Every palette color is its own index.
+        let pixels: Vec<u8> = palette()
+            .iter()
+            .flat_map(|color| color.to_be_bytes()[1..].to_vec())
+            .collect();
+        assert_eq!(quantize(&pixels), (0..32).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn recorder_keeps_the_last_frames_at_the_capture_rate() {
+        let config = RunConfig {
+            gif_length_secs: 1,
+            gif_fps: 10,
+            ..RunConfig::default()
+        };
+        let mut recorder = Recorder::new(&config);
+        let pixels = vec![0; 128 * 128 * 3];
+
+        // Drawing at 60 fps for 2 seconds.
+        for frame in 0..120 {
+            recorder.record(&pixels, frame as f64 * 1000.0 / 60.0);
+        }
+
+        assert_eq!(recorder.frames.len(), 10);
+        assert_eq!(recorder.last_frame_time, Some(1900.0));
+    }
+}
@@ -0,0 +1,280 @@
+//! Gamepad support through the Linux joystick API (`/dev/input/js*`).
+//!
+//! Button and axis numbers follow the Xbox layout used by the `xpad` driver,
+//! which is also how Steam Input exposes the Steam Deck's built-in controller.
+//! Other platforms don't report any gamepad events yet.
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use runty8_core::{GamepadAxis, GamepadButton, GamepadEvent, InputEvent, KeyState};
+
+/// Polls connected gamepads for new events.
+pub(crate) struct Gamepads {
+    #[cfg(target_os = "linux")]
+    inner: linux::Gamepads,
+}
+
+impl Gamepads {
+    pub(crate) fn new() -> Self {
+        Self {
+            #[cfg(target_os = "linux")]
+            inner: linux::Gamepads::new(),
+        }
+    }
+
+    /// Events that happened since the last call.
+    pub(crate) fn poll(&mut self) -> Vec<InputEvent> {
+        #[cfg(target_os = "linux")]
+        return self.inner.poll();
+
+        #[cfg(not(target_os = "linux"))]
+        return vec![];
+    }
+}
+
+const BUTTON: u8 = 0x01;
+const AXIS: u8 = 0x02;
+
+/// Translates a raw joystick event into Runty8 gamepad events.
+///
+/// The d-pad is reported as a pair of axes, which become button events.
+fn translate(event_type: u8, number: u8, value: i16) -> Vec<GamepadEvent> {
+    let button = |button, pressed: bool| GamepadEvent::Button {
+        button,
+        state: if pressed {
+            KeyState::Down
+        } else {
+            KeyState::Up
+        },
+    };
+    let axis = |axis| GamepadEvent::Axis {
+        axis,
+        value: (value as f32 / i16::MAX as f32).max(-1.0),
+    };
+
+    match (event_type, number) {
+        (BUTTON, number) => {
+            let pressed = value != 0;
+            match number {
+                0 => vec![button(GamepadButton::South, pressed)],
+                1 => vec![button(GamepadButton::East, pressed)],
+                2 => vec![button(GamepadButton::West, pressed)],
+                3 => vec![button(GamepadButton::North, pressed)],
+                6 => vec![button(GamepadButton::Select, pressed)],
+                7 => vec![button(GamepadButton::Start, pressed)],
+                _ => vec![],
+            }
+        }
+        (AXIS, 0) => vec![axis(GamepadAxis::LeftStickX)],
+        (AXIS, 1) => vec![axis(GamepadAxis::LeftStickY)],
+        (AXIS, 6) => vec![
+            button(GamepadButton::DPadLeft, value < 0),
+            button(GamepadButton::DPadRight, value > 0),
+        ],
+        (AXIS, 7) => vec![
+            button(GamepadButton::DPadUp, value < 0),
+            button(GamepadButton::DPadDown, value > 0),
+        ],
+        _ => vec![],
+    }
+}
+
+/// What a device is holding, to release it if the device goes away.
+#[derive(Default)]
+struct Held {
+    buttons: Vec<GamepadButton>,
+    axes: Vec<GamepadAxis>,
+}
+
+impl Held {
+    fn track(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Button { button, state } => {
+                self.buttons.retain(|&other| other != button);
+                if state == KeyState::Down {
+                    self.buttons.push(button);
+                }
+            }
+            GamepadEvent::Axis { axis, value } => {
+                self.axes.retain(|&other| other != axis);
+                if value != 0.0 {
+                    self.axes.push(axis);
+                }
+            }
+        }
+    }
+
+    /// Events letting go of everything held.
+    fn release(self) -> impl Iterator<Item = GamepadEvent> {
+        let buttons = self.buttons.into_iter().map(|button| GamepadEvent::Button {
+            button,
+            state: KeyState::Up,
+        });
+        let axes = self
+            .axes
+            .into_iter()
+            .map(|axis| GamepadEvent::Axis { axis, value: 0.0 });
+
+        buttons.chain(axes)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::Held;
+    use runty8_core::InputEvent;
+    use std::fs::{File, OpenOptions};
+    use std::io::{ErrorKind, Read};
+    use std::os::unix::fs::OpenOptionsExt;
+
+    const MAX_DEVICES: usize = 4;
+    const SCAN_INTERVAL_MILLIS: f64 = 1000.0;
+    // Set on the synthetic events the driver sends when a device is opened.
+    const INIT: u8 = 0x80;
+
+    pub(crate) struct Gamepads {
+        devices: [Option<(File, Held)>; MAX_DEVICES],
+        last_scan: f64,
+    }
+
+    impl Gamepads {
+        pub(crate) fn new() -> Self {
+            let mut gamepads = Self {
+                devices: Default::default(),
+                last_scan: 0.0,
+            };
+            gamepads.scan();
+
+            gamepads
+        }
+
+        /// Opens gamepads that were plugged in since the last scan.
+        fn scan(&mut self) {
+            self.last_scan = instant::now();
+
+            for (index, device) in self.devices.iter_mut().enumerate() {
+                if device.is_none() {
+                    *device = OpenOptions::new()
+                        .read(true)
+                        .custom_flags(libc::O_NONBLOCK)
+                        .open(format!("/dev/input/js{index}"))
+                        .ok()
+                        .map(|file| (file, Held::default()));
+                }
+            }
+        }
+
+        pub(crate) fn poll(&mut self) -> Vec<InputEvent> {
+            if instant::now() - self.last_scan > SCAN_INTERVAL_MILLIS {
+                self.scan();
+            }
+
+            let mut events = vec![];
+            for device in self.devices.iter_mut() {
+                let Some((file, held)) = device else { continue };
+
+                // struct js_event { u32 time; s16 value; u8 type; u8 number; }
+                let mut raw = [0; 8];
+                loop {
+                    match file.read(&mut raw) {
+                        Ok(8) => {
+                            let value = i16::from_ne_bytes([raw[4], raw[5]]);
+                            let event_type = raw[6] & !INIT;
+                            let number = raw[7];
+
+                            for event in super::translate(event_type, number, value) {
+                                held.track(event);
+                                events.push(InputEvent::Gamepad(event));
+                            }
+                        }
+                        Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                        // Short read or error: the gamepad was most likely unplugged.
+                        // Whatever it was holding is released, rather than left stuck down.
+                        _ => {
+                            if let Some((_, held)) = device.take() {
+                                events.extend(held.release().map(InputEvent::Gamepad));
+                            }
+                            break;
+                        }
+                    }
+                }
+            }
+
+            events
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{translate, Held, AXIS, BUTTON};
+    use runty8_core::{GamepadAxis, GamepadButton, GamepadEvent, KeyState};
+
+    #[test]
+    fn a_button_is_south() {
+        assert!(matches!(
+            translate(BUTTON, 0, 1)[..],
+            [GamepadEvent::Button {
+                button: GamepadButton::South,
+                state: KeyState::Down
+            }]
+        ));
+    }
+
+    #[test]
+    fn dpad_axis_becomes_buttons() {
+        assert!(matches!(
+            translate(AXIS, 6, i16::MIN)[..],
+            [
+                GamepadEvent::Button {
+                    button: GamepadButton::DPadLeft,
+                    state: KeyState::Down
+                },
+                GamepadEvent::Button {
+                    button: GamepadButton::DPadRight,
+                    state: KeyState::Up
+                }
+            ]
+        ));
+    }
+
+    #[test]
+    fn stick_is_normalized() {
+        let events = translate(AXIS, 0, i16::MIN);
+
+        assert!(matches!(
+            events[..],
+            [GamepadEvent::Axis {
+                axis: GamepadAxis::LeftStickX,
+                value
+            }] if value == -1.0
+        ));
+    }
+
+    #[test]
+    fn unplugging_releases_what_was_held() {
+        let mut held = Held::default();
+        for event in translate(BUTTON, 0, 1)
+            .into_iter()
+            .chain(translate(BUTTON, 1, 1))
+            .chain(translate(BUTTON, 1, 0))
+            .chain(translate(AXIS, 0, 1000))
+        {
+            held.track(event);
+        }
+
+        let released: Vec<_> = held.release().collect();
+        assert!(matches!(
+            released[..],
+            [
+                GamepadEvent::Button {
+                    button: GamepadButton::South,
+                    state: KeyState::Up
+                },
+                GamepadEvent::Axis {
+                    axis: GamepadAxis::LeftStickX,
+                    value
+                }
+            ] if value == 0.0
+        ));
+    }
+}
@@ -0,0 +1,223 @@
+//! Minimal animated GIF encoder for frames using Pico8's 32 colors (with the extended ones).
+
+use std::collections::HashMap;
+
+// 32 colors need 5 bits per pixel.
+const MIN_CODE_SIZE: u8 = 5;
+const MAX_CODE_SIZE: u8 = 12;
+
+/// Encodes `width` by `height` frames of palette indices (one byte per pixel, `0..32`)
+/// as a looping GIF.
+///
+/// `palette` holds the `0xRRGGBB` value of each index, and `delay_centis`
+/// how long each frame is shown, in hundredths of a second.
+pub(crate) fn encode<'a>(
+    frames: impl IntoIterator<Item = &'a [u8]>,
+    (width, height): (u16, u16),
+    palette: &[u32; 32],
+    delay_centis: u16,
+) -> Vec<u8> {
+    let mut gif = b"GIF89a".to_vec();
+
+    // Logical screen descriptor: global color table of 2^(4 + 1) colors, 8 bits per channel.
+    gif.extend_from_slice(&width.to_le_bytes());
+    gif.extend_from_slice(&height.to_le_bytes());
+    gif.extend_from_slice(&[0b1111_0100, 0, 0]);
+    for color in palette {
+        let [_, r, g, b] = color.to_be_bytes();
+        gif.extend_from_slice(&[r, g, b]);
+    }
+
+    // Loop forever.
+    gif.extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+    for frame in frames {
+        // Graphic control extension: frame delay.
+        gif.extend_from_slice(&[0x21, 0xF9, 0x04, 0]);
+        gif.extend_from_slice(&delay_centis.to_le_bytes());
+        gif.extend_from_slice(&[0, 0]);
+
+        // Image descriptor: the whole screen, using the global color table.
+        gif.push(0x2C);
+        gif.extend_from_slice(&[0, 0, 0, 0]);
+        gif.extend_from_slice(&width.to_le_bytes());
+        gif.extend_from_slice(&height.to_le_bytes());
+        gif.push(0);
+
+        gif.push(MIN_CODE_SIZE);
+        for block in lzw(frame).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0);
+    }
+
+    gif.push(0x3B);
+    gif
+}
+
+/// Packs variable-width codes into bytes, least significant bit first.
+struct BitWriter {
+    bytes: Vec<u8>,
+    current: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.current |= (code as u32) << self.bits;
+        self.bits += size;
+
+        while self.bits >= 8 {
+            self.bytes.push(self.current as u8);
+            self.current >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.current as u8);
+        }
+
+        self.bytes
+    }
+}
+
+/// GIF flavored LZW compression.
+fn lzw(pixels: &[u8]) -> Vec<u8> {
+    let clear_code: u16 = 1 << MIN_CODE_SIZE;
+    let end_code = clear_code + 1;
+
+    let mut writer = BitWriter {
+        bytes: vec![],
+        current: 0,
+        bits: 0,
+    };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end_code + 1;
+    let mut code_size = MIN_CODE_SIZE + 1;
+
+    writer.write(clear_code, code_size);
+
+    let mut pixels = pixels.iter().copied();
+    let Some(first) = pixels.next() else {
+        writer.write(end_code, code_size);
+        return writer.finish();
+    };
+
+    let mut prefix = first as u16;
+    for pixel in pixels {
+        if let Some(&code) = table.get(&(prefix, pixel)) {
+            prefix = code;
+            continue;
+        }
+
+        writer.write(prefix, code_size);
+
+        if next_code == 1 << MAX_CODE_SIZE {
+            writer.write(clear_code, code_size);
+            table.clear();
+            next_code = end_code + 1;
+            code_size = MIN_CODE_SIZE + 1;
+        } else {
+            table.insert((prefix, pixel), next_code);
+            if next_code == 1 << code_size {
+                code_size += 1;
+            }
+            next_code += 1;
+        }
+
+        prefix = pixel as u16;
+    }
+
+    writer.write(prefix, code_size);
+    writer.write(end_code, code_size);
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, lzw, MIN_CODE_SIZE};
+
+    // Straightforward GIF LZW decoder, to check the encoder against.
+    fn unlzw(bytes: &[u8]) -> Vec<u8> {
+        let clear_code = 1 << MIN_CODE_SIZE;
+        let end_code = clear_code + 1;
+
+        let mut bits = bytes
+            .iter()
+            .flat_map(|byte| (0..8).map(move |bit| (byte >> bit) & 1));
+        let mut read = |size: u8| -> usize {
+            (0..size).fold(0, |code, bit| code | (bits.next().unwrap() as usize) << bit)
+        };
+
+        let initial_table: Vec<Vec<u8>> = (0..clear_code)
+            .map(|color| vec![color as u8])
+            .chain([vec![], vec![]])
+            .collect();
+
+        let mut table = initial_table.clone();
+        let mut code_size = MIN_CODE_SIZE + 1;
+        let mut previous: Option<Vec<u8>> = None;
+        let mut output = vec![];
+
+        loop {
+            let code = read(code_size);
+            if code == clear_code {
+                table = initial_table.clone();
+                code_size = MIN_CODE_SIZE + 1;
+                previous = None;
+                continue;
+            }
+            if code == end_code {
+                return output;
+            }
+
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => [&previous[..], &previous[..1]].concat(),
+                (None, None) => panic!("Invalid code {code}"),
+            };
+            output.extend_from_slice(&entry);
+
+            if let Some(previous) = previous {
+                table.push([&previous[..], &entry[..1]].concat());
+                if table.len() == 1 << code_size && code_size < 12 {
+                    code_size += 1;
+                }
+            }
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trips() {
+        let frames: [Vec<u8>; 3] = [
+            vec![0; 128 * 128],
+            (0..128 * 128).map(|i| (i % 32) as u8).collect(),
+            (0..128 * 128)
+                .map(|i: usize| ((i * 7919) % 13 + (i / 129) % 3) as u8)
+                .collect(),
+        ];
+
+        for frame in frames {
+            assert_eq!(unlzw(&lzw(&frame)), frame);
+        }
+    }
+
+    #[test]
+    fn gif_is_framed_correctly() {
+        let frame = vec![3; 128 * 128];
+        let gif = encode([&frame[..], &frame[..]], (128, 128), &[0; 32], 3);
+
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(gif.last(), Some(&0x3B));
+        assert_eq!(
+            gif.windows(2)
+                .filter(|bytes| bytes == &[0x21, 0xF9])
+                .count(),
+            2
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use glow::{Context, HasContext};
+use runty8_core::{DisplayFilter, RunConfig};
 
 const VERTEX_SHADER: &str = r#"precision highp float;
 
@@ -21,12 +22,19 @@ const vec2 all_tex_coords[6] = vec2[6](
 
 out vec2 v_tex_coords;
 
+// -1.0 when drawing to a texture for the next pass, which has its rows the other way around.
+uniform float flip_y;
+// 1.0 when the input texture has the top row first (as uploaded from the game's screen),
+// 0.0 when it's bottom up. See `RunConfig::flip_screen_y`.
+uniform float flip_rows;
+
 void main() {
     vec2 position = verts[gl_VertexID];
     vec2 tex_coords = all_tex_coords[gl_VertexID];
 
-    v_tex_coords = tex_coords;
-    gl_Position = vec4(position, 0.0, 1.0);
+    // (0, 0) is the top left corner of the screen, when its rows are flipped.
+    v_tex_coords = vec2(tex_coords.x, mix(tex_coords.y, 1.0 - tex_coords.y, flip_rows));
+    gl_Position = vec4(position.x, position.y * flip_y, 0.0, 1.0);
 }
 "#;
 
@@ -38,28 +46,67 @@ out vec4 color;
 uniform sampler2D tex;
 
 void main() {
-    float x = v_tex_coords.x;
-    float y = 1.0 - v_tex_coords.y;
+    color = texture(tex, v_tex_coords);
+}
+"#;
+
+// `DisplayFilter::Crt`.
+const CRT_SHADER: &str = r#"precision highp float;
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D tex;
+uniform float scanline_strength;
+uniform float curvature;
+uniform float screen_width;
+uniform float screen_height;
+
+void main() {
+    // Barrel distortion: the further from the center, the further out the sampled point,
+    // so the screen looks like it bulges towards the viewer.
+    vec2 centered = v_tex_coords * 2.0 - 1.0;
+    vec2 distorted = centered * (1.0 + curvature * 0.25 * dot(centered, centered));
+    vec2 tex_coords = distorted * 0.5 + 0.5;
 
-    color = texture(tex, vec2(x, y));
+    // What got pushed out of the screen is left black, at the corners.
+    if (any(lessThan(tex_coords, vec2(0.0))) || any(greaterThan(tex_coords, vec2(1.0)))) {
+        color = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    // Clamped to the centers of the edge pixels, so nothing from outside the texture gets blended in.
+    vec2 half_pixel = 0.5 / vec2(screen_width, screen_height);
+    tex_coords = clamp(tex_coords, half_pixel, 1.0 - half_pixel);
+    vec3 pixel = texture(tex, tex_coords).rgb;
+
+    // Full brightness in the middle of each pixel row, darkest between rows.
+    float row = tex_coords.y * screen_height - 0.5;
+    float between_rows = 0.5 - 0.5 * cos(6.2831853 * row);
+
+    color = vec4(pixel * (1.0 - scanline_strength * between_rows), 1.0);
 }
 "#;
 
-pub(crate) unsafe fn make_program(gl: &Context, shader_version: &str) -> glow::Program {
-    let program = gl.create_program().expect("Cannot create program");
+unsafe fn make_program(
+    gl: &Context,
+    shader_version: &str,
+    fragment_shader: &str,
+) -> Result<glow::Program, String> {
+    let program = gl.create_program()?;
 
     let shader_sources = [
         (glow::VERTEX_SHADER, VERTEX_SHADER),
-        (glow::FRAGMENT_SHADER, FRAGMENT_SHADER),
+        (glow::FRAGMENT_SHADER, fragment_shader),
     ];
 
     for (shader_type, shader_source) in shader_sources.into_iter() {
-        let shader = gl.create_shader(shader_type).expect("Cannot create shader");
+        let shader = gl.create_shader(shader_type)?;
         gl.shader_source(shader, &format!("{shader_version}\n{shader_source}"));
         gl.compile_shader(shader);
 
         if !gl.get_shader_compile_status(shader) {
-            panic!("{}", gl.get_shader_info_log(shader));
+            return Err(gl.get_shader_info_log(shader));
         }
         gl.attach_shader(program, shader);
     }
@@ -67,13 +114,15 @@ pub(crate) unsafe fn make_program(gl: &Context, shader_version: &str) -> glow::P
     gl.link_program(program);
 
     if !gl.get_program_link_status(program) {
-        panic!("{}", gl.get_program_info_log(program));
+        return Err(gl.get_program_info_log(program));
     }
-    program
+    Ok(program)
 }
 
-pub(crate) unsafe fn make_texture(gl: &Context) -> glow::Texture {
-    let texture = gl.create_texture().unwrap();
+unsafe fn make_texture(gl: &Context) -> Result<glow::Texture, String> {
+    let texture = gl
+        .create_texture()
+        .map_err(|error| format!("Cannot create texture: {error}"))?;
 
     gl.bind_texture(glow::TEXTURE_2D, Some(texture));
 
@@ -89,28 +138,337 @@ pub(crate) unsafe fn make_texture(gl: &Context) -> glow::Texture {
         glow::NEAREST as i32,
     );
 
-    texture
+    Ok(texture)
 }
 
-pub(crate) unsafe fn upload_pixels(gl: &Context, texture: glow::Texture, pixels: &[u8]) {
+/// Makes the texture the game's screen is uploaded to, with room for `width` x `height` RGB pixels.
+unsafe fn make_screen_texture(
+    gl: &Context,
+    (width, height): (i32, i32),
+) -> Result<glow::Texture, String> {
+    let texture = make_texture(gl)?;
+
+    // Rows of RGB pixels needn't be 4-byte aligned.
+    gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+    gl.tex_image_2d(
+        glow::TEXTURE_2D,
+        0,
+        glow::RGB8 as i32,
+        width,
+        height,
+        0,
+        glow::RGB,
+        glow::UNSIGNED_BYTE,
+        None,
+    );
+
+    Ok(texture)
+}
+
+/// Overwrites the screen texture's pixels, reusing its storage.
+unsafe fn upload_pixels(
+    gl: &Context,
+    texture: glow::Texture,
+    (width, height): (i32, i32),
+    pixels: &[u8],
+) {
     gl.active_texture(glow::TEXTURE0);
 
     gl.bind_texture(glow::TEXTURE_2D, Some(texture));
 
-    gl.tex_image_2d(
+    gl.tex_sub_image_2d(
         glow::TEXTURE_2D,
         0,
-        glow::RGB8 as i32,
-        128,
-        128,
         0,
+        0,
+        width,
+        height,
         glow::RGB,
         glow::UNSIGNED_BYTE,
-        Some(pixels),
+        glow::PixelUnpackData::Slice(pixels),
     );
 }
 
-pub(crate) unsafe fn use_texture(gl: &Context, program: glow::Program) {
-    let tex_location = gl.get_uniform_location(program, "tex").unwrap();
-    gl.uniform_1_i32(Some(&tex_location), 0);
+/// The pixels last uploaded to the screen texture, to skip uploading them again
+/// when the game didn't draw anything new.
+#[derive(Default)]
+struct UploadedPixels {
+    pixels: Vec<u8>,
+}
+
+impl UploadedPixels {
+    /// Whether `pixels` differ from the previous ones, remembering them if so.
+    fn update(&mut self, pixels: &[u8]) -> bool {
+        if self.pixels == pixels {
+            return false;
+        }
+
+        self.pixels.clear();
+        self.pixels.extend_from_slice(pixels);
+        true
+    }
+}
+
+/// A fragment shader the screen goes through, and where its uniforms are.
+struct Pass {
+    program: glow::Program,
+    tex: Option<glow::UniformLocation>,
+    resolution: Option<glow::UniformLocation>,
+    time: Option<glow::UniformLocation>,
+    flip_y: Option<glow::UniformLocation>,
+    flip_rows: Option<glow::UniformLocation>,
+}
+
+impl Pass {
+    /// Compiles `fragment_shader`, setting the uniforms in `constants` once and for all.
+    unsafe fn new(
+        gl: &Context,
+        shader_version: &str,
+        fragment_shader: &str,
+        constants: &[(&str, f32)],
+    ) -> Result<Self, String> {
+        let program = make_program(gl, shader_version, fragment_shader)?;
+
+        gl.use_program(Some(program));
+        for &(name, value) in constants {
+            gl.uniform_1_f32(gl.get_uniform_location(program, name).as_ref(), value);
+        }
+
+        Ok(Self {
+            program,
+            tex: gl.get_uniform_location(program, "tex"),
+            resolution: gl.get_uniform_location(program, "resolution"),
+            time: gl.get_uniform_location(program, "time"),
+            flip_y: gl.get_uniform_location(program, "flip_y"),
+            flip_rows: gl.get_uniform_location(program, "flip_rows"),
+        })
+    }
+}
+
+/// A texture a pass draws to, for the next one to read.
+struct Target {
+    framebuffer: glow::Framebuffer,
+    texture: glow::Texture,
+}
+
+/// Draws the game's screen through a chain of fragment shaders: the [`DisplayFilter`]'s,
+/// and then [`RunConfig::fragment_shaders`].
+pub(crate) struct Pipeline {
+    screen: glow::Texture,
+    /// Size of `screen`: the game's resolution.
+    screen_size: (i32, i32),
+    uploaded: UploadedPixels,
+    passes: Vec<Pass>,
+    /// Intermediate textures, alternated between passes.
+    targets: Vec<Target>,
+    /// Size of `targets`: the viewport's.
+    target_size: (i32, i32),
+    start_millis: f64,
+    /// [`RunConfig::flip_screen_y`].
+    flip_screen_y: bool,
+}
+
+impl Pipeline {
+    /// Compiles the shaders `config` asks for, or the passthrough one if there are none.
+    ///
+    /// Fails with the driver's compiler output if a shader doesn't compile.
+    pub(crate) unsafe fn new(
+        gl: &Context,
+        shader_version: &str,
+        config: &RunConfig,
+    ) -> Result<Self, String> {
+        let mut passes = vec![];
+        let (width, height) = config.resolution;
+        let screen_size = [
+            ("screen_width", width as f32),
+            ("screen_height", height as f32),
+        ];
+
+        if let DisplayFilter::Crt {
+            scanline_strength,
+            curvature,
+        } = config.display_filter
+        {
+            let constants = [
+                ("scanline_strength", scanline_strength),
+                ("curvature", curvature),
+                screen_size[0],
+                screen_size[1],
+            ];
+            passes.push(
+                Pass::new(gl, shader_version, CRT_SHADER, &constants)
+                    .map_err(|error| format!("Cannot compile CRT shader: {error}"))?,
+            );
+        }
+
+        for (index, shader) in config.fragment_shaders.iter().enumerate() {
+            passes.push(
+                Pass::new(gl, shader_version, shader, &screen_size)
+                    .map_err(|error| format!("Cannot compile fragment shader {index}: {error}"))?,
+            );
+        }
+
+        if passes.is_empty() {
+            passes.push(
+                Pass::new(gl, shader_version, FRAGMENT_SHADER, &[])
+                    .map_err(|error| format!("Cannot compile built-in shader: {error}"))?,
+            );
+        }
+
+        let screen_size = (width as i32, height as i32);
+
+        Ok(Self {
+            screen: make_screen_texture(gl, screen_size)?,
+            screen_size,
+            uploaded: UploadedPixels::default(),
+            passes,
+            targets: vec![],
+            target_size: (0, 0),
+            start_millis: instant::now(),
+            flip_screen_y: config.flip_screen_y,
+        })
+    }
+
+    /// Makes sure there are intermediate textures of the viewport's size, if needed.
+    unsafe fn prepare_targets(&mut self, gl: &Context, size: (i32, i32)) -> Result<(), String> {
+        let needed = (self.passes.len() - 1).min(2);
+
+        if self.targets.len() == needed && self.target_size == size {
+            return Ok(());
+        }
+
+        for target in self.targets.drain(..) {
+            gl.delete_framebuffer(target.framebuffer);
+            gl.delete_texture(target.texture);
+        }
+
+        for _ in 0..needed {
+            let texture = make_texture(gl)?;
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA8 as i32,
+                size.0,
+                size.1,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                None,
+            );
+
+            let framebuffer = match gl.create_framebuffer() {
+                Ok(framebuffer) => framebuffer,
+                Err(error) => {
+                    gl.delete_texture(texture);
+                    return Err(format!("Cannot create framebuffer: {error}"));
+                }
+            };
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                Some(texture),
+                0,
+            );
+            if gl.check_framebuffer_status(glow::FRAMEBUFFER) != glow::FRAMEBUFFER_COMPLETE {
+                // Back to drawing to the window, without the half made target.
+                gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+                gl.delete_framebuffer(framebuffer);
+                gl.delete_texture(texture);
+                return Err(format!(
+                    "Cannot draw to a {}x{} framebuffer",
+                    size.0, size.1
+                ));
+            }
+
+            self.targets.push(Target {
+                framebuffer,
+                texture,
+            });
+        }
+        gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+
+        self.target_size = size;
+        Ok(())
+    }
+
+    /// Draws `pixels` (RGB, at the game's resolution) to the `viewport` of a window `window_height` pixels tall,
+    /// clearing the rest of the window.
+    pub(crate) unsafe fn draw(
+        &mut self,
+        gl: &Context,
+        pixels: &[u8],
+        viewport: runty8_winit::Viewport,
+        window_height: i32,
+    ) -> Result<(), String> {
+        let size = (viewport.width, viewport.height);
+        let time = ((instant::now() - self.start_millis) / 1000.0) as f32;
+
+        self.prepare_targets(gl, size)?;
+        if self.uploaded.update(pixels) {
+            upload_pixels(gl, self.screen, self.screen_size, pixels);
+        }
+
+        let mut input = self.screen;
+        let last = self.passes.len() - 1;
+
+        for (index, pass) in self.passes.iter().enumerate() {
+            let flip_y = if index == last {
+                gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+                // Clearing ignores the viewport, so this also paints the borders.
+                gl.clear(glow::COLOR_BUFFER_BIT);
+                // GL measures `y` from the bottom of the window.
+                gl.viewport(
+                    viewport.x,
+                    window_height - viewport.y - viewport.height,
+                    viewport.width,
+                    viewport.height,
+                );
+                1.0
+            } else {
+                let target = &self.targets[index % 2];
+                gl.bind_framebuffer(glow::FRAMEBUFFER, Some(target.framebuffer));
+                gl.viewport(0, 0, size.0, size.1);
+                -1.0
+            };
+
+            gl.use_program(Some(pass.program));
+            gl.uniform_1_i32(pass.tex.as_ref(), 0);
+            gl.uniform_2_f32(pass.resolution.as_ref(), size.0 as f32, size.1 as f32);
+            gl.uniform_1_f32(pass.time.as_ref(), time);
+            gl.uniform_1_f32(pass.flip_y.as_ref(), flip_y);
+            // Only the screen can be bottom up, the targets are drawn with the top row first.
+            let flip_rows = index != 0 || self.flip_screen_y;
+            gl.uniform_1_f32(pass.flip_rows.as_ref(), if flip_rows { 1.0 } else { 0.0 });
+
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D, Some(input));
+            gl.draw_arrays(glow::TRIANGLES, 0, 6);
+
+            if index != last {
+                input = self.targets[index % 2].texture;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UploadedPixels;
+
+    #[test]
+    fn unchanged_pixels_are_not_uploaded_again() {
+        let mut uploaded = UploadedPixels::default();
+        let mut pixels = vec![0; 128 * 128 * 3];
+
+        assert!(uploaded.update(&pixels));
+        assert!(!uploaded.update(&pixels));
+
+        pixels[5] = 0xff;
+        assert!(uploaded.update(&pixels));
+        assert!(!uploaded.update(&pixels));
+    }
 }
@@ -0,0 +1,54 @@
+use runty8_core::{Event, InputEvent, Key, KeyState, KeyboardEvent};
+
+/// A key that triggers something once per press.
+///
+/// Holding a key down makes the system repeat its press, which is ignored until the key
+/// is released.
+#[derive(Debug, Clone, Copy)]
+pub struct Hotkey {
+    key: Option<Key>,
+    down: bool,
+}
+
+impl Hotkey {
+    /// A hotkey for `key`, or one that never triggers for `None`.
+    pub fn new(key: Option<Key>) -> Self {
+        Self { key, down: false }
+    }
+
+    /// Whether `event` is a new press of the key.
+    pub fn pressed(&mut self, event: &Event) -> bool {
+        let &Event::Input(InputEvent::Keyboard(KeyboardEvent { key, state })) = event else {
+            return false;
+        };
+        if Some(key) != self.key {
+            return false;
+        }
+
+        let was_down = std::mem::replace(&mut self.down, state == KeyState::Down);
+        self.down && !was_down
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Hotkey;
+    use runty8_core::{Event, InputEvent, Key, KeyState, KeyboardEvent};
+
+    fn key(key: Key, state: KeyState) -> Event {
+        Event::Input(InputEvent::Keyboard(KeyboardEvent { key, state }))
+    }
+
+    #[test]
+    fn repeated_presses_are_ignored() {
+        let mut hotkey = Hotkey::new(Some(Key::F11));
+
+        assert!(hotkey.pressed(&key(Key::F11, KeyState::Down)));
+        assert!(!hotkey.pressed(&key(Key::F11, KeyState::Down)));
+        assert!(!hotkey.pressed(&key(Key::A, KeyState::Down)));
+        assert!(!hotkey.pressed(&key(Key::F11, KeyState::Up)));
+        assert!(hotkey.pressed(&key(Key::F11, KeyState::Down)));
+
+        assert!(!Hotkey::new(None).pressed(&key(Key::F11, KeyState::Down)));
+    }
+}
@@ -3,14 +3,25 @@
 //! Winit/Glow/Glutin powered event loop for Runty8 applications.
 
 use glow::HasContext;
-use runty8_core::Event;
+use runty8_core::{Audio, Event, FramePacing, RunConfig, WindowMode};
 use runty8_winit::{Runty8EventExt as _, ScreenInfo};
+use std::cell::RefCell;
 use winit::{
+    event::StartCause,
     event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
+    window::{Fullscreen, Icon, WindowBuilder},
 };
 
+mod audio;
+#[cfg(not(target_arch = "wasm32"))]
+mod capture;
+mod gamepad;
+#[cfg(not(target_arch = "wasm32"))]
+mod gif;
 mod gl;
+mod hotkey;
+
+pub use hotkey::Hotkey;
 
 #[cfg(not(target_arch = "wasm32"))]
 type Window = glutin::WindowedContext<glutin::PossiblyCurrent>;
@@ -18,53 +29,137 @@ type Window = glutin::WindowedContext<glutin::PossiblyCurrent>;
 #[cfg(target_arch = "wasm32")]
 type Window = winit::window::Window;
 
+/// Start playing the game's sounds in the background, where supported (currently Linux).
+pub fn play_audio(audio: Audio) {
+    audio::play(audio);
+}
+
 /// Create a window (or canvas, in wasm) and respond to events on it.
+///
+/// Only returns if the window or its graphics can't be set up, e.g. when a shader doesn't compile.
+/// Errors while running are logged instead: a frame that can't be drawn is skipped,
+/// and losing the graphics context ends the event loop.
 pub fn event_loop(
+    config: &RunConfig,
     mut on_event: impl FnMut(Event, &mut ControlFlow, &dyn Fn(&[u8], &mut ControlFlow), &dyn Fn(&str))
         + 'static,
-) {
-    let (width, height) = get_window_size();
+) -> Result<(), String> {
+    let (width, height) = config.window_size;
 
-    let mut screen_info = ScreenInfo::new(width, height);
+    let mut screen_info = ScreenInfo::new(width.into(), height.into());
+    screen_info.scaling = config.scaling;
+    screen_info.resolution = config.resolution;
 
     let event_loop = EventLoop::new();
 
-    let (window, gl, shader_version) = make_window_and_context(&event_loop, &screen_info);
+    let (window, gl, shader_version) = make_window_and_context(&event_loop, &screen_info, config)?;
     screen_info.scale_factor = winit_window(&window).scale_factor();
     log::info!("New scale factor: {}", screen_info.scale_factor);
+    // Fullscreen windows (and window managers) can ignore the requested size.
+    screen_info.logical_size = winit_window(&window)
+        .inner_size()
+        .to_logical(screen_info.scale_factor);
 
-    let texture = unsafe {
+    let pipeline = unsafe {
         let vertex_array = gl
             .create_vertex_array()
-            .expect("Cannot create vertex array");
+            .map_err(|error| format!("Cannot create vertex array: {error}"))?;
         gl.bind_vertex_array(Some(vertex_array));
 
-        gl.clear_color(0.1, 0.2, 0.3, 1.0);
-
-        let program = gl::make_program(&gl, shader_version);
-        gl.use_program(Some(program));
-        let texture = gl::make_texture(&gl);
-        gl::use_texture(&gl, program);
+        let [_, r, g, b] = config.border_color.to_be_bytes();
+        gl.clear_color(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0);
 
-        texture
+        RefCell::new(gl::Pipeline::new(&gl, shader_version, config)?)
     };
 
     let mut current_time = instant::now();
+    let mut gamepads = gamepad::Gamepads::new();
+
+    let captures = RefCell::new(capture::Captures::new(config));
 
-    winit_window(&window).set_cursor_visible(false);
+    let mut fullscreen_key = Hotkey::new(config.fullscreen_key);
+    let frame_pacing = config.frame_pacing;
+    let frame_duration =
+        std::time::Duration::from_secs_f64(1.0 / f64::from(config.fps.max(config.update_fps())));
+
+    winit_window(&window).set_cursor_visible(config.cursor_visible);
     // TODO: Initial render.
     // EDIT: Actually I think this handles itself through the Tick from Init? Maybe? Not sure.
     // => Test it
     // gl::upload_pixels(&gl, texture, pico8.draw_data.buffer());
     event_loop.run(move |winit_event, _, control_flow| {
-        let event: Option<Event> =
-            Event::from_winit(&winit_event, &mut current_time, &mut screen_info);
+        // The viewport and cursor mapping follow in `Event::from_winit`.
+        #[cfg(not(target_arch = "wasm32"))]
+        match &winit_event {
+            winit::event::Event::WindowEvent {
+                event: winit::event::WindowEvent::Resized(new_size),
+                ..
+            } => window.resize(*new_size),
+            winit::event::Event::WindowEvent {
+                event: winit::event::WindowEvent::ScaleFactorChanged { new_inner_size, .. },
+                ..
+            } => window.resize(**new_inner_size),
+            _ => {}
+        }
+
+        if let (FramePacing::WaitUntil, winit::event::Event::NewEvents(cause)) =
+            (frame_pacing, &winit_event)
+        {
+            let frame_start = match *cause {
+                StartCause::Init => Some(instant::Instant::now()),
+                StartCause::ResumeTimeReached {
+                    requested_resume, ..
+                } => Some(requested_resume),
+                // Woken up by an event, the next frame is still due at the same time.
+                StartCause::WaitCancelled { .. } | StartCause::Poll => None,
+            };
+
+            if let Some(frame_start) = frame_start {
+                // A late frame doesn't make the following ones hurry to catch up.
+                let next_frame = (frame_start + frame_duration).max(instant::Instant::now());
+                *control_flow = ControlFlow::WaitUntil(next_frame);
+            }
+        }
 
-        if let Some(event) = event {
-            let draw: &dyn Fn(&[u8], &mut ControlFlow) = &|pixels, _control_flow| {
-                draw(&gl, texture, pixels);
+        // Gamepad events are delivered right before the tick they happened in.
+        let mut events: Vec<Event> = match winit_event {
+            winit::event::Event::NewEvents(_) => {
+                gamepads.poll().into_iter().map(Event::Input).collect()
+            }
+            _ => vec![],
+        };
+        events.extend(Event::from_winit(
+            &winit_event,
+            &mut current_time,
+            &mut screen_info,
+        ));
+
+        for event in events {
+            captures.borrow_mut().on_event(&event);
+
+            if fullscreen_key.pressed(&event) {
+                toggle_fullscreen(winit_window(&window));
+            }
+
+            let draw: &dyn Fn(&[u8], &mut ControlFlow) = &|pixels, control_flow| {
+                if let Err(error) = draw(&gl, &mut pipeline.borrow_mut(), &screen_info, pixels) {
+                    log::error!("Cannot draw frame: {error}");
+                    return;
+                }
                 #[cfg(not(target_arch = "wasm32"))]
-                window.swap_buffers().unwrap();
+                match window.swap_buffers() {
+                    Ok(()) => {}
+                    Err(glutin::ContextError::ContextLost) => {
+                        log::error!("Lost the graphics context, exiting");
+                        *control_flow = ControlFlow::Exit;
+                        return;
+                    }
+                    Err(error) => log::error!("Cannot show frame: {error}"),
+                }
+                #[cfg(target_arch = "wasm32")]
+                let _ = control_flow;
+
+                captures.borrow_mut().on_draw(pixels, &screen_info);
             };
 
             let set_title: &dyn Fn(&str) = &|title| set_title(&window, title);
@@ -74,31 +169,52 @@ pub fn event_loop(
     })
 }
 
-fn get_window_size() -> (f64, f64) {
-    #[cfg(not(feature = "steamdeck"))]
-    return (640.0, 640.0);
-    #[cfg(feature = "steamdeck")]
-    return (320.0, 320.0);
+/// Switches between a window and borderless fullscreen.
+///
+/// The window gets resized, which updates the viewport through the usual `Resized` event.
+fn toggle_fullscreen(window: &winit::window::Window) {
+    let fullscreen = match window.fullscreen() {
+        Some(_) => None,
+        None => Some(Fullscreen::Borderless(None)),
+    };
+
+    window.set_fullscreen(fullscreen);
 }
 
-fn draw(gl: &glow::Context, texture: glow::Texture, pixels: &[u8]) {
-    unsafe {
-        gl::upload_pixels(gl, texture, pixels);
-        gl.clear(glow::COLOR_BUFFER_BIT);
-        gl.draw_arrays(glow::TRIANGLES, 0, 6);
-    }
+fn draw(
+    gl: &glow::Context,
+    pipeline: &mut gl::Pipeline,
+    screen_info: &ScreenInfo,
+    pixels: &[u8],
+) -> Result<(), String> {
+    let window_height = screen_info.physical_size().height as i32;
+
+    unsafe { pipeline.draw(gl, pixels, screen_info.viewport(), window_height) }
 }
 
 fn make_window_and_context(
     event_loop: &EventLoop<()>,
     screen_info: &ScreenInfo,
-) -> (Window, glow::Context, &'static str) {
+    config: &RunConfig,
+) -> Result<(Window, glow::Context, &'static str), String> {
+    let fullscreen = match config.window_mode {
+        WindowMode::Windowed => None,
+        WindowMode::Fullscreen => Some(Fullscreen::Borderless(None)),
+    };
+    let icon = config
+        .icon
+        .as_ref()
+        .map(|icon| Icon::from_rgba(icon.rgba.clone(), icon.width, icon.height))
+        .transpose()
+        .map_err(|error| format!("Invalid window icon: {error}"))?;
     let window_builder = WindowBuilder::new()
         .with_inner_size(screen_info.logical_size)
-        .with_title("Runty8");
+        .with_fullscreen(fullscreen)
+        .with_title(&config.title)
+        .with_window_icon(icon);
 
     #[cfg(not(target_arch = "wasm32"))]
-    return native::make_window_and_context(window_builder, event_loop);
+    return native::make_window_and_context(window_builder, event_loop, config.vsync);
 
     #[cfg(target_arch = "wasm32")]
     return wasm::make_window_and_context(window_builder, event_loop);
@@ -120,23 +236,99 @@ fn set_title(window: &Window, title: &str) {
 
 #[cfg(not(target_arch = "wasm32"))]
 mod native {
-    use glutin::{event_loop::EventLoop, ContextBuilder, ContextWrapper};
+    use glutin::{
+        event_loop::EventLoop, Api, ContextBuilder, ContextWrapper, GlProfile, GlRequest,
+    };
 
-    pub(crate) fn make_window_and_context(
-        window_builder: glutin::window::WindowBuilder,
-        event_loop: &EventLoop<()>,
-    ) -> (
+    /// The window, its GL functions and the `#version` line for its shaders.
+    type WindowAndContext = (
         glutin::WindowedContext<glutin::PossiblyCurrent>,
         glow::Context,
         &'static str,
-    ) {
+    );
+
+    /// A kind of graphics context to ask for, and the GLSL version its shaders need.
+    struct ContextConfig {
+        name: &'static str,
+        gl: GlRequest,
+        profile: Option<GlProfile>,
+        srgb: bool,
+        shader_version: &'static str,
+    }
+
+    /// Tried in order until one works: some drivers (e.g. in the Steam Deck's gaming mode,
+    /// or over remote desktops) only offer older or more limited contexts.
+    const CONTEXT_CONFIGS: [ContextConfig; 3] = [
+        ContextConfig {
+            name: "default OpenGL",
+            gl: GlRequest::Latest,
+            profile: None,
+            srgb: true,
+            shader_version: "#version 410",
+        },
+        ContextConfig {
+            name: "OpenGL 3.3 core, no sRGB",
+            gl: GlRequest::Specific(Api::OpenGl, (3, 3)),
+            profile: Some(GlProfile::Core),
+            srgb: false,
+            shader_version: "#version 330",
+        },
+        ContextConfig {
+            name: "OpenGL ES 3.0, no sRGB",
+            gl: GlRequest::Specific(Api::OpenGlEs, (3, 0)),
+            profile: None,
+            srgb: false,
+            shader_version: "#version 300 es",
+        },
+    ];
+
+    /// Creates the window with the first of [`CONTEXT_CONFIGS`] the system supports.
+    /// Fails with every attempt's error if none is.
+    pub(crate) fn make_window_and_context(
+        window_builder: glutin::window::WindowBuilder,
+        event_loop: &EventLoop<()>,
+        vsync: bool,
+    ) -> Result<WindowAndContext, String> {
+        let mut errors = vec![];
+
+        for config in &CONTEXT_CONFIGS {
+            log::info!("Creating a {} context", config.name);
+
+            match try_context(window_builder.clone(), event_loop, vsync, config) {
+                Ok(window_and_context) => return Ok(window_and_context),
+                Err(error) => {
+                    log::warn!("Cannot create a {} context: {error}", config.name);
+                    errors.push(format!("{}: {error}", config.name));
+                }
+            }
+        }
+
+        Err(format!(
+            "Cannot create a graphics context, tried:\n  {}",
+            errors.join("\n  ")
+        ))
+    }
+
+    fn try_context(
+        window_builder: glutin::window::WindowBuilder,
+        event_loop: &EventLoop<()>,
+        vsync: bool,
+        config: &ContextConfig,
+    ) -> Result<WindowAndContext, String> {
+        let mut builder = ContextBuilder::new()
+            .with_gl(config.gl)
+            .with_srgb(config.srgb)
+            .with_vsync(vsync);
+        if let Some(profile) = config.profile {
+            builder = builder.with_gl_profile(profile);
+        }
+
         let window = unsafe {
-            ContextBuilder::new()
-                .with_vsync(true)
+            builder
                 .build_windowed(window_builder, event_loop)
-                .unwrap()
+                .map_err(|error| format!("Cannot create window: {error}"))?
                 .make_current()
-                .unwrap()
+                .map_err(|(_, error)| format!("Cannot use the graphics context: {error}"))?
         };
 
         let gl = unsafe {
@@ -145,7 +337,26 @@ mod native {
             })
         };
 
-        (window, gl, "#version 410")
+        Ok((window, gl, config.shader_version))
+    }
+}
+
+// Files can't be saved from the browser (yet), so captures are no-ops there.
+#[cfg(target_arch = "wasm32")]
+mod capture {
+    use runty8_core::{Event, RunConfig};
+    use runty8_winit::ScreenInfo;
+
+    pub(crate) struct Captures;
+
+    impl Captures {
+        pub(crate) fn new(_config: &RunConfig) -> Self {
+            Self
+        }
+
+        pub(crate) fn on_event(&mut self, _event: &Event) {}
+
+        pub(crate) fn on_draw(&mut self, _pixels: &[u8], _screen_info: &ScreenInfo) {}
     }
 }
 
@@ -159,11 +370,13 @@ mod wasm {
     pub(crate) fn make_window_and_context(
         window_builder: WindowBuilder,
         event_loop: &EventLoop<()>,
-    ) -> (Window, glow::Context, &'static str) {
-        let window = window_builder.build(event_loop).unwrap();
+    ) -> Result<(Window, glow::Context, &'static str), String> {
+        let window = window_builder
+            .build(event_loop)
+            .map_err(|error| format!("Cannot create canvas: {error}"))?;
         let gl = insert_canvas_and_create_context(&window);
 
-        (window, gl, "#version 300 es")
+        Ok((window, gl, "#version 300 es"))
     }
 
     fn insert_canvas_and_create_context(window: &Window) -> glow::Context {
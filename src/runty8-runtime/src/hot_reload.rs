@@ -0,0 +1,197 @@
+//! Reloads the sprite sheet, map and flags when their files change, for `RunConfig::hot_reload`.
+//!
+//! The files are polled rather than watched, a few times per second,
+//! and a change is only picked up once the file has stopped changing
+//! (editors often save in several steps).
+
+use runty8_core::{Flags, Map, Resources, SpriteSheet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+pub(crate) struct AssetWatcher {
+    assets_path: PathBuf,
+    files: Vec<WatchedFile>,
+    last_check: Instant,
+}
+
+struct WatchedFile {
+    file_name: String,
+    version: Option<Version>,
+    // Changed since the previous check, reloaded once it stays the same for a check.
+    settling: bool,
+}
+
+/// What's compared to tell whether a file changed.
+#[derive(PartialEq, Eq, Clone, Copy)]
+struct Version {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl AssetWatcher {
+    pub(crate) fn new(assets_path: &str) -> Self {
+        let assets_path = PathBuf::from(assets_path);
+        let files = [
+            SpriteSheet::file_name(),
+            Map::file_name(),
+            Flags::file_name(),
+        ]
+        .into_iter()
+        .map(|file_name| WatchedFile {
+            version: version(&assets_path.join(&file_name)),
+            file_name,
+            settling: false,
+        })
+        .collect();
+
+        Self {
+            assets_path,
+            files,
+            last_check: Instant::now(),
+        }
+    }
+
+    /// Watches the assets in `assets_path` from now on, if they aren't the ones watched already,
+    /// e.g. after `Pico8::load` switched carts.
+    pub(crate) fn watch(&mut self, assets_path: &str) {
+        if self.assets_path != Path::new(assets_path) {
+            *self = Self::new(assets_path);
+        }
+    }
+
+    /// Reloads the files that changed into `resources`.
+    /// Files that can't be read or parsed are logged and skipped, keeping the previous assets.
+    pub(crate) fn reload_changes(&mut self, resources: &mut Resources) {
+        for file_name in self.changed_files(Instant::now()) {
+            let path = self.assets_path.join(&file_name);
+
+            match std::fs::read_to_string(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|contents| reload(resources, &file_name, &contents))
+            {
+                Ok(()) => log::info!("Reloaded {}", path.display()),
+                Err(error) => log::error!("Couldn't reload {}: {error}", path.display()),
+            }
+        }
+    }
+
+    /// The files that changed and then stayed the same since the last check, at most
+    /// every [`CHECK_INTERVAL`].
+    fn changed_files(&mut self, now: Instant) -> Vec<String> {
+        if now.duration_since(self.last_check) < CHECK_INTERVAL {
+            return vec![];
+        }
+        self.last_check = now;
+
+        let assets_path = &self.assets_path;
+        self.files
+            .iter_mut()
+            .filter_map(|file| {
+                let version = version(&assets_path.join(&file.file_name));
+
+                if version != file.version {
+                    file.version = version;
+                    file.settling = true;
+                    None
+                } else if file.settling && version.is_some() {
+                    file.settling = false;
+                    Some(file.file_name.clone())
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+fn version(path: &Path) -> Option<Version> {
+    let metadata = std::fs::metadata(path).ok()?;
+
+    Some(Version {
+        modified: metadata.modified().ok()?,
+        len: metadata.len(),
+    })
+}
+
+fn reload(resources: &mut Resources, file_name: &str, contents: &str) -> Result<(), String> {
+    if file_name == SpriteSheet::file_name() {
+        resources.sprite_sheet = SpriteSheet::deserialize(contents)?;
+    } else if file_name == Map::file_name() {
+        resources.map = Map::deserialize(contents)?;
+    } else if file_name == Flags::file_name() {
+        resources.sprite_flags = Flags::deserialize(contents)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AssetWatcher, CHECK_INTERVAL};
+    use runty8_core::serialize::Serialize;
+    use runty8_core::{Flags, Map, Resources};
+    use std::time::Instant;
+
+    #[test]
+    fn reloads_changed_files_once_they_settle() {
+        let dir = std::env::temp_dir().join("runty8-hot-reload-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let assets_path = dir.to_str().unwrap();
+        crate::create_directory(assets_path).unwrap();
+
+        let mut resources = Resources::from_dir(assets_path).unwrap();
+        let mut watcher = AssetWatcher::new(assets_path);
+        let start = Instant::now();
+
+        let mut map = Map::new();
+        map.mset(1, 1, 4);
+        std::fs::write(dir.join(Map::file_name()), map.serialize()).unwrap();
+
+        // Too soon to check, then still settling.
+        assert!(watcher.changed_files(start).is_empty());
+        assert!(watcher.changed_files(start + CHECK_INTERVAL).is_empty());
+        assert_eq!(
+            watcher.changed_files(start + 2 * CHECK_INTERVAL),
+            vec![Map::file_name()]
+        );
+        assert!(watcher.changed_files(start + 3 * CHECK_INTERVAL).is_empty());
+
+        let map = std::fs::read_to_string(dir.join(Map::file_name())).unwrap();
+        super::reload(&mut resources, &Map::file_name(), &map).unwrap();
+        assert_eq!(resources.map.mget(1, 1), 4);
+
+        // Malformed files keep the previous assets.
+        assert!(super::reload(&mut resources, &Map::file_name(), "not a map").is_err());
+        assert_eq!(resources.map.mget(1, 1), 4);
+    }
+
+    #[test]
+    fn follows_the_loaded_cart() {
+        let dir = std::env::temp_dir().join("runty8-hot-reload-load-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let (a, b) = (dir.join("a"), dir.join("b"));
+        for cart in [&a, &b] {
+            crate::create_directory(cart.to_str().unwrap()).unwrap();
+        }
+
+        let mut watcher = AssetWatcher::new(a.to_str().unwrap());
+        watcher.watch(a.to_str().unwrap());
+        assert_eq!(watcher.assets_path, a);
+
+        watcher.watch(b.to_str().unwrap());
+        assert_eq!(watcher.assets_path, b);
+        let start = Instant::now();
+        // Only the new cart's changes count.
+        std::fs::write(b.join(Map::file_name()), "").unwrap();
+        std::fs::write(a.join(Map::file_name()), "").unwrap();
+        std::fs::write(a.join(Flags::file_name()), "").unwrap();
+
+        assert!(watcher.changed_files(start + CHECK_INTERVAL).is_empty());
+        assert_eq!(
+            watcher.changed_files(start + 2 * CHECK_INTERVAL),
+            vec![Map::file_name()]
+        );
+    }
+}
@@ -2,19 +2,60 @@
 
 //! Run a standalone Runty8 game natively or in wasm.
 
-use runty8_core::{App, Event, Input, Pico8, Resources};
-use runty8_event_loop::event_loop;
+use runty8_core::{App, Event, Input, InputEvent, Pico8, Replay, Resources, RunConfig, Timestep};
+use runty8_event_loop::{event_loop, play_audio, Hotkey};
 use winit::event_loop::ControlFlow;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod hot_reload;
+
+/// A frame of the screen, as the RGB bytes of each pixel, row by row.
+pub type Frame = Vec<u8>;
+
 /// Runs a standalone Runty8 game.
 pub fn run<Game: App + 'static>(resources: Resources) -> std::io::Result<()> {
+    run_with_config::<Game>(resources, RunConfig::default())
+}
+
+/// Runs a standalone Runty8 game with custom settings.
+pub fn run_with_config<Game: App + 'static>(
+    resources: Resources,
+    config: RunConfig,
+) -> std::io::Result<()> {
+    config
+        .validate()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+    let mut config = config;
+    config
+        .capture_dir
+        .get_or_insert_with(|| resources.assets_path.clone());
+
     let mut pico8 = Pico8::new(resources);
+    pico8.apply_config(&config);
+
+    play_audio(pico8.audio());
+
     let mut game = Game::init(&mut pico8);
     let mut input = Input::new();
+    input.set_gamepad_deadzone(config.gamepad_deadzone);
+    input.set_button_map(config.button_map.clone());
 
-    const DELTA_TIME: f64 = 1000.0 / 30.0;
+    let mut timestep = Timestep::new(config.update_fps(), config.fps);
+    let mut exited = false;
+    let mut fps_overlay_key = Hotkey::new(config.fps_overlay_key);
+    // Real time since the previous draw, for `Pico8::record_frame_time`.
+    let mut undrawn_millis = 0.0;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut watcher = config
+        .hot_reload
+        .then(|| hot_reload::AssetWatcher::new(&pico8.resources.assets_path));
+    #[cfg(target_arch = "wasm32")]
+    if config.hot_reload {
+        log::warn!("hot_reload isn't supported on the web");
+    }
 
-    let mut accumulated_delta = 0.0;
     let on_event = move |event,
                          control_flow: &mut ControlFlow,
                          draw: &dyn Fn(&[u8], &mut ControlFlow),
@@ -23,30 +64,307 @@ pub fn run<Game: App + 'static>(resources: Resources) -> std::io::Result<()> {
             set_title(&new_title);
         }
 
+        if fps_overlay_key.pressed(&event) {
+            pico8.toggle_fps_overlay();
+        }
+
         match event {
             Event::Tick { delta_millis } => {
-                accumulated_delta += delta_millis;
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(watcher) = &mut watcher {
+                    watcher.watch(&pico8.resources.assets_path);
+                    watcher.reload_changes(&mut pico8.resources);
+                }
 
-                while accumulated_delta > DELTA_TIME {
-                    pico8.state.update_input(&input);
+                pico8.advance_real_time(delta_millis);
+                undrawn_millis += delta_millis;
+                let steps = timestep.advance(delta_millis);
 
-                    game.update(&mut pico8);
-                    game.draw(&mut pico8);
+                for _ in 0..steps.updates {
+                    pico8.state.update_input(&input);
+                    pico8.update_app(&mut game);
+                }
 
-                    draw(pico8.draw_data.buffer(), control_flow);
+                if steps.draw {
+                    let draw_start = instant::now();
+                    pico8.draw_app(&mut game);
+                    let draw_millis = instant::now() - draw_start;
+                    pico8.record_frame_time(std::mem::take(&mut undrawn_millis), draw_millis);
 
-                    accumulated_delta -= DELTA_TIME;
+                    draw(pico8.display_buffer(), control_flow);
                 }
             }
             Event::Input(input_event) => {
                 input.on_event(input_event);
             }
             Event::WindowClosed => {
+                if !exited {
+                    exited = true;
+                    game.on_exit(&mut pico8);
+                }
                 *control_flow = ControlFlow::Exit;
             }
         }
     };
 
-    event_loop(on_event);
+    event_loop(&config, on_event)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))
+}
+
+/// Sets up `assets_path` for a new game: creates the directory, and blank asset files
+/// (sprite sheet, flags, map, sound effects and music) in it.
+///
+/// Files that are already there are left alone, so it's safe to call on every start.
+/// Native only: on the web, missing assets are already blank.
+pub fn create_directory(assets_path: &str) -> std::io::Result<()> {
+    std::fs::create_dir_all(assets_path)?;
+
+    for asset in Resources::blank(assets_path).serialize() {
+        let path = std::path::Path::new(assets_path).join(&asset.file_name);
+        if !path.exists() {
+            std::fs::write(path, asset.serialized)?;
+        }
+    }
+
     Ok(())
 }
+
+/// Runs a game for `frames` updates without opening a window,
+/// and returns what's drawn after each of them.
+///
+/// Useful for testing carts, since nothing depends on timing or a display.
+/// Only the game's settings in `config` apply (e.g. [`RunConfig::rng_seed`], which carts
+/// calling `rnd` need to draw the same frames every time), not the window's.
+pub fn run_headless<Game: App>(
+    resources: Resources,
+    config: &RunConfig,
+    frames: usize,
+) -> Vec<Frame> {
+    run_headless_with_input::<Game>(resources, config, frames, |_| vec![])
+}
+
+/// Like [`run_headless`], with `input(frame)` giving the input events
+/// that happen before each update (counted from 0).
+pub fn run_headless_with_input<Game: App>(
+    resources: Resources,
+    config: &RunConfig,
+    frames: usize,
+    mut input: impl FnMut(usize) -> Vec<InputEvent>,
+) -> Vec<Frame> {
+    let mut keys = Input::new();
+    keys.set_gamepad_deadzone(config.gamepad_deadzone);
+    keys.set_button_map(config.button_map.clone());
+
+    run_headless_frames::<Game>(resources, config, frames, |frame, pico8| {
+        for event in input(frame) {
+            keys.on_event(event);
+        }
+        pico8.state.update_input(&keys);
+    })
+}
+
+/// Like [`run_headless`], playing back the buttons of each frame of `replay`.
+pub fn run_headless_replay<Game: App>(
+    resources: Resources,
+    config: &RunConfig,
+    replay: &Replay,
+) -> Vec<Frame> {
+    run_headless_frames::<Game>(resources, config, replay.len(), |frame, pico8| {
+        if let Some(buttons) = replay.get(frame) {
+            pico8.state.update_buttons(buttons);
+        }
+    })
+}
+
+fn run_headless_frames<Game: App>(
+    resources: Resources,
+    config: &RunConfig,
+    frames: usize,
+    mut update_input: impl FnMut(usize, &mut Pico8),
+) -> Vec<Frame> {
+    let mut pico8 = Pico8::new(resources);
+    pico8.apply_config(config);
+
+    let mut game = Game::init(&mut pico8);
+    let frame_millis = 1000.0 / f64::from(config.update_fps());
+
+    (0..frames)
+        .map(|frame| {
+            pico8.advance_real_time(frame_millis);
+            update_input(frame, &mut pico8);
+            pico8.update_app(&mut game);
+            pico8.draw_app(&mut game);
+
+            pico8.display_buffer().to_vec()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        create_directory, run_headless, run_headless_replay, run_headless_with_input, Frame,
+    };
+    use runty8_core::{
+        App, Button, Flags, Input, InputEvent, Key, KeyState, KeyboardEvent, Map, Pico8, Replay,
+        Resources, RunConfig, Sfx, SpriteSheet,
+    };
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    struct Walker {
+        x: i32,
+    }
+
+    impl App for Walker {
+        fn init(_: &mut Pico8) -> Self {
+            Self { x: 0 }
+        }
+
+        fn update(&mut self, pico8: &mut Pico8) {
+            if pico8.btn(Button::Right) {
+                self.x += 1;
+            }
+        }
+
+        fn draw(&mut self, pico8: &mut Pico8) {
+            pico8.cls(0);
+            pico8.pset(self.x, 0, 7);
+        }
+    }
+
+    fn resources() -> Resources {
+        Resources::blank(".")
+    }
+
+    fn lit_pixel(frame: &Frame) -> Option<usize> {
+        (0..128).find(|x| frame[3 * x] != 0)
+    }
+
+    #[test]
+    fn captures_a_frame_per_update() {
+        let frames = run_headless::<Walker>(resources(), &RunConfig::default(), 3);
+
+        assert_eq!(frames.len(), 3);
+        assert!(frames.iter().all(|frame| lit_pixel(frame) == Some(0)));
+    }
+
+    fn script(frame: usize) -> Vec<InputEvent> {
+        let key = |state| {
+            vec![InputEvent::Keyboard(KeyboardEvent {
+                key: Key::RightArrow,
+                state,
+            })]
+        };
+
+        match frame {
+            1 => key(KeyState::Down),
+            3 => key(KeyState::Up),
+            _ => vec![],
+        }
+    }
+
+    fn hash(frame: &Frame) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        frame.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn feeds_scripted_input() {
+        let frames =
+            run_headless_with_input::<Walker>(resources(), &RunConfig::default(), 5, script);
+
+        let positions: Vec<_> = frames.iter().map(lit_pixel).collect();
+        assert_eq!(positions, [Some(0), Some(1), Some(2), Some(2), Some(2)]);
+    }
+
+    #[test]
+    fn recorded_input_replays_the_same_frames() {
+        let mut input = Input::new();
+        let mut replay = Replay::new();
+        for frame in 0..5 {
+            for event in script(frame) {
+                input.on_event(event);
+            }
+            replay.record(&input);
+        }
+
+        let config = RunConfig::default();
+        let live = run_headless_with_input::<Walker>(resources(), &config, 5, script);
+        let replayed = run_headless_replay::<Walker>(resources(), &config, &replay);
+
+        assert_eq!(
+            live.iter().map(hash).collect::<Vec<_>>(),
+            replayed.iter().map(hash).collect::<Vec<_>>()
+        );
+    }
+
+    // Scatters a pixel at random every frame.
+    struct Noise;
+
+    impl App for Noise {
+        fn init(_: &mut Pico8) -> Self {
+            Self
+        }
+
+        fn update(&mut self, _: &mut Pico8) {}
+
+        fn draw(&mut self, pico8: &mut Pico8) {
+            let (x, y) = (pico8.rnd(128.0), pico8.rnd(128.0));
+            pico8.pset(x as i32, y as i32, 7);
+        }
+    }
+
+    #[test]
+    fn seeded_replays_draw_the_same_frames() {
+        let mut replay = Replay::new();
+        for _ in 0..20 {
+            replay.record(&Input::new());
+        }
+        let replay_with = |seed| {
+            let config = RunConfig {
+                rng_seed: Some(seed),
+                ..RunConfig::default()
+            };
+
+            run_headless_replay::<Noise>(resources(), &config, &replay)
+        };
+
+        assert_eq!(replay_with(3), replay_with(3));
+        assert_ne!(replay_with(3), replay_with(4));
+    }
+
+    #[test]
+    fn create_directory_scaffolds_blank_assets() {
+        let dir = std::env::temp_dir().join("runty8-create-directory-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let assets_path = dir.join("assets");
+        let assets_path = assets_path.to_str().unwrap();
+
+        create_directory(assets_path).unwrap();
+        let resources = Resources::from_dir(assets_path).unwrap();
+        assert_eq!(resources.map.mget(0, 1), 0);
+
+        // Existing assets aren't overwritten.
+        let map_path = format!("{assets_path}/{}", Map::file_name());
+        let mut map = resources.map;
+        map.mset(0, 1, 3);
+        std::fs::write(
+            &map_path,
+            runty8_core::serialize::Serialize::serialize(&map),
+        )
+        .unwrap();
+
+        create_directory(assets_path).unwrap();
+        let resources = Resources::from_dir(assets_path).unwrap();
+        assert_eq!(resources.map.mget(0, 1), 3);
+        for file_name in [
+            SpriteSheet::file_name(),
+            Flags::file_name(),
+            Sfx::file_name(),
+        ] {
+            assert!(dir.join("assets").join(file_name).exists());
+        }
+    }
+}
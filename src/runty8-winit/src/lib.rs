@@ -2,8 +2,10 @@
 
 //! Compatibility layer for Runty8 crates that need to leverage winit.
 
-use runty8_core::{Event, InputEvent, Key, KeyState, KeyboardEvent, MouseButton, MouseEvent};
-use winit::dpi::{LogicalPosition, LogicalSize};
+use runty8_core::{
+    Event, InputEvent, Key, KeyState, KeyboardEvent, MouseButton, MouseEvent, ScalingMode,
+};
+use winit::dpi::{LogicalSize, PhysicalSize};
 
 /// Information about the current viewport for rendering.
 #[derive(Debug)]
@@ -12,16 +14,96 @@ pub struct ScreenInfo {
     pub scale_factor: f64,
     /// Display resolution in "logical" units.
     pub logical_size: LogicalSize<f64>,
+    /// How the game's screen is scaled to fit the window.
+    pub scaling: ScalingMode,
+    /// Size of the game's screen, in pixels (see `RunConfig::resolution`).
+    pub resolution: (u32, u32),
+    /// Where the cursor last was, in physical pixels relative to the window.
+    pub cursor_position: Option<(f64, f64)>,
+}
+
+/// Area of the window the game's screen is drawn to, in physical pixels.
+///
+/// `x` and `y` are measured from the window's top left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    /// Distance from the left edge of the window.
+    pub x: i32,
+    /// Distance from the top edge of the window.
+    pub y: i32,
+    /// Width of the drawn area.
+    pub width: i32,
+    /// Height of the drawn area.
+    pub height: i32,
 }
 
 impl ScreenInfo {
-    ///
+    /// Create a [`ScreenInfo`] with the given logical size, a scale factor of 1
+    /// and Pico8's 128x128 screen.
     pub fn new(width: f64, height: f64) -> Self {
         Self {
             scale_factor: 1.0,
             logical_size: LogicalSize::new(width, height),
+            scaling: ScalingMode::Stretch,
+            resolution: (128, 128),
+            cursor_position: None,
+        }
+    }
+
+    /// Window size in physical pixels.
+    pub fn physical_size(&self) -> PhysicalSize<u32> {
+        self.logical_size.to_physical(self.scale_factor)
+    }
+
+    /// Where in the window the game's screen should be drawn.
+    pub fn viewport(&self) -> Viewport {
+        let PhysicalSize { width, height } = self.physical_size();
+
+        match self.scaling {
+            ScalingMode::Stretch => Viewport {
+                x: 0,
+                y: 0,
+                width: width as i32,
+                height: height as i32,
+            },
+            ScalingMode::IntegerPixelPerfect => {
+                let (screen_width, screen_height) = self.resolution;
+                let scale = (width / screen_width).min(height / screen_height).max(1);
+                let (scaled_width, scaled_height) = (screen_width * scale, screen_height * scale);
+
+                Viewport {
+                    x: (width as i32 - scaled_width as i32) / 2,
+                    y: (height as i32 - scaled_height as i32) / 2,
+                    width: scaled_width as i32,
+                    height: scaled_height as i32,
+                }
+            }
         }
     }
+
+    /// Converts a cursor position (in physical pixels, relative to the window)
+    /// to the game screen's coordinates, which go out of `0..128` outside of the viewport.
+    ///
+    /// This is the only place window positions become game positions: [`MouseEvent::Move`]
+    /// (and so `stat(32)`, `stat(33)` and the editor's widgets) goes through it.
+    /// Both the window and the game measure `y` from the top, so no flip is needed here.
+    /// GL measures it from the bottom, which only the drawing code deals with.
+    pub fn to_screen_position(&self, x: f64, y: f64) -> (i32, i32) {
+        let viewport = self.viewport();
+        let to_screen = |position: f64, start: i32, length: i32, screen_length: u32| {
+            ((position - start as f64) / length as f64 * f64::from(screen_length)).floor() as i32
+        };
+
+        (
+            to_screen(x, viewport.x, viewport.width, self.resolution.0),
+            to_screen(y, viewport.y, viewport.height, self.resolution.1),
+        )
+    }
+
+    /// Like [`ScreenInfo::to_screen_position`], for a position in logical pixels.
+    pub fn logical_to_screen_position(&self, x: f64, y: f64) -> (i32, i32) {
+        self.to_screen_position(x * self.scale_factor, y * self.scale_factor)
+    }
 }
 
 /// Extension trait to convert a [`winit::event::Event`] into a [`runty8_core::Event`].
@@ -50,38 +132,61 @@ impl Runty8EventExt for Event {
                 &winit::event::WindowEvent::Resized(new_size) => {
                     screen_info.logical_size = new_size.to_logical(screen_info.scale_factor);
 
-                    None
+                    remap_cursor(screen_info)
                 }
-                &winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
-                    screen_info.scale_factor = scale_factor;
-                    None
+                // E.g. when moving to a monitor with a different DPI.
+                winit::event::WindowEvent::ScaleFactorChanged {
+                    scale_factor,
+                    new_inner_size,
+                } => {
+                    screen_info.scale_factor = *scale_factor;
+                    screen_info.logical_size = new_inner_size.to_logical(*scale_factor);
+
+                    remap_cursor(screen_info)
                 }
                 winit::event::WindowEvent::CursorMoved { position, .. } => {
-                    let logical_mouse: LogicalPosition<f64> =
-                        position.to_logical(screen_info.scale_factor);
-
-                    let window_size = screen_info.logical_size;
-                    Some(Event::Input(InputEvent::Mouse(MouseEvent::Move {
-                        x: (logical_mouse.x / window_size.width * 128.).floor() as i32,
-                        y: (logical_mouse.y / window_size.height * 128.).floor() as i32,
-                    })))
+                    screen_info.cursor_position = Some((position.x, position.y));
+                    let (x, y) = screen_info.to_screen_position(position.x, position.y);
+
+                    Some(Event::Input(InputEvent::Mouse(MouseEvent::Move { x, y })))
+                }
+                winit::event::WindowEvent::CursorLeft { .. } => {
+                    screen_info.cursor_position = None;
+                    None
                 }
                 winit::event::WindowEvent::MouseInput {
-                    button: winit::event::MouseButton::Left,
+                    button,
                     state: input_state,
                     ..
                 } => {
+                    let button = match button {
+                        winit::event::MouseButton::Left => MouseButton::Left,
+                        winit::event::MouseButton::Right => MouseButton::Right,
+                        winit::event::MouseButton::Middle => MouseButton::Middle,
+                        winit::event::MouseButton::Other(_) => return None,
+                    };
                     let mouse_button_state = match input_state {
                         ElementState::Pressed => KeyState::Down,
                         ElementState::Released => KeyState::Up,
                     };
 
                     let mouse_event = MouseEvent::Button {
-                        button: MouseButton::Left,
+                        button,
                         state: mouse_button_state,
                     };
                     Some(Event::Input(InputEvent::Mouse(mouse_event)))
                 }
+                winit::event::WindowEvent::MouseWheel { delta, .. } => {
+                    let y = match *delta {
+                        winit::event::MouseScrollDelta::LineDelta(_, y) => y.round() as i32,
+                        // Touchpads scroll by pixels, count a line every 16 of them.
+                        winit::event::MouseScrollDelta::PixelDelta(position) => {
+                            (position.y / 16.0).round() as i32
+                        }
+                    };
+
+                    (y != 0).then_some(Event::Input(InputEvent::Mouse(MouseEvent::Wheel { y })))
+                }
                 winit::event::WindowEvent::KeyboardInput { input, .. } => {
                     KeyboardEvent::from_winit(*input)
                         .map(InputEvent::Keyboard)
@@ -91,20 +196,31 @@ impl Runty8EventExt for Event {
             },
             winit::event::Event::NewEvents(cause) => match cause {
                 winit::event::StartCause::Init => Some(Event::Tick { delta_millis: 0.0 }),
-                winit::event::StartCause::Poll => {
+                // With `FramePacing::Poll` and `FramePacing::WaitUntil` respectively.
+                winit::event::StartCause::Poll
+                | winit::event::StartCause::ResumeTimeReached { .. } => {
                     let new_time = instant::now();
                     let delta_millis = new_time - *current_time;
                     *current_time = new_time;
 
                     Some(Event::Tick { delta_millis })
                 }
-                winit::event::StartCause::ResumeTimeReached { .. } => None,
                 winit::event::StartCause::WaitCancelled { .. } => None,
             },
+            // Also sent after `CloseRequested`, but the loop can end without one.
+            winit::event::Event::LoopDestroyed => Some(Event::WindowClosed),
             _ => None,
         }
     }
 }
+/// The cursor's position in the game after the viewport changed, since it moved relative to it.
+fn remap_cursor(screen_info: &ScreenInfo) -> Option<Event> {
+    let (x, y) = screen_info.cursor_position?;
+    let (x, y) = screen_info.to_screen_position(x, y);
+
+    Some(Event::Input(InputEvent::Mouse(MouseEvent::Move { x, y })))
+}
+
 trait Runty8KeyboardEventExt: Sized {
     fn from_winit(input: winit::event::KeyboardInput) -> Option<Self>;
 }
@@ -157,6 +273,16 @@ impl Runty8KeyExt for Key {
             VirtualKeyCode::X => Some(Self::X),
             VirtualKeyCode::Y => Some(Self::Y),
             VirtualKeyCode::Z => Some(Self::Z),
+            VirtualKeyCode::Key0 => Some(Self::Digit0),
+            VirtualKeyCode::Key1 => Some(Self::Digit1),
+            VirtualKeyCode::Key2 => Some(Self::Digit2),
+            VirtualKeyCode::Key3 => Some(Self::Digit3),
+            VirtualKeyCode::Key4 => Some(Self::Digit4),
+            VirtualKeyCode::Key5 => Some(Self::Digit5),
+            VirtualKeyCode::Key6 => Some(Self::Digit6),
+            VirtualKeyCode::Key7 => Some(Self::Digit7),
+            VirtualKeyCode::Key8 => Some(Self::Digit8),
+            VirtualKeyCode::Key9 => Some(Self::Digit9),
             VirtualKeyCode::LControl => Some(Self::Control),
             VirtualKeyCode::Left => Some(Self::LeftArrow),
             VirtualKeyCode::Right => Some(Self::RightArrow),
@@ -165,6 +291,20 @@ impl Runty8KeyExt for Key {
             VirtualKeyCode::Escape => Some(Self::Escape),
             VirtualKeyCode::LAlt => Some(Self::Alt),
             VirtualKeyCode::Space => Some(Self::Enter),
+            VirtualKeyCode::Back => Some(Self::Backspace),
+            VirtualKeyCode::Tab => Some(Self::Tab),
+            VirtualKeyCode::F1 => Some(Self::F1),
+            VirtualKeyCode::F2 => Some(Self::F2),
+            VirtualKeyCode::F3 => Some(Self::F3),
+            VirtualKeyCode::F4 => Some(Self::F4),
+            VirtualKeyCode::F5 => Some(Self::F5),
+            VirtualKeyCode::F6 => Some(Self::F6),
+            VirtualKeyCode::F7 => Some(Self::F7),
+            VirtualKeyCode::F8 => Some(Self::F8),
+            VirtualKeyCode::F9 => Some(Self::F9),
+            VirtualKeyCode::F10 => Some(Self::F10),
+            VirtualKeyCode::F11 => Some(Self::F11),
+            VirtualKeyCode::F12 => Some(Self::F12),
             _ => None,
         }
     }
@@ -184,3 +324,166 @@ impl Runty8KeyStateExt for KeyState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Runty8EventExt, ScreenInfo, Viewport};
+    use runty8_core::{Event, InputEvent, MouseEvent, ScalingMode};
+    use winit::dpi::{PhysicalPosition, PhysicalSize};
+    use winit::event::{DeviceId, WindowEvent};
+    use winit::window::WindowId;
+
+    fn window_event(event: WindowEvent<'_>) -> winit::event::Event<'_, ()> {
+        winit::event::Event::WindowEvent {
+            // Only used to tell windows apart, which doesn't matter here.
+            window_id: unsafe { WindowId::dummy() },
+            event,
+        }
+    }
+
+    fn screen_info(width: f64, height: f64, scaling: ScalingMode) -> ScreenInfo {
+        ScreenInfo {
+            scaling,
+            ..ScreenInfo::new(width, height)
+        }
+    }
+
+    #[test]
+    fn stretch_covers_the_whole_window() {
+        let screen_info = screen_info(1280.0, 800.0, ScalingMode::Stretch);
+
+        assert_eq!(
+            screen_info.viewport(),
+            Viewport {
+                x: 0,
+                y: 0,
+                width: 1280,
+                height: 800
+            }
+        );
+    }
+
+    #[test]
+    fn integer_scaling_centers_the_largest_whole_scale() {
+        let screen_info = screen_info(1280.0, 800.0, ScalingMode::IntegerPixelPerfect);
+
+        assert_eq!(
+            screen_info.viewport(),
+            Viewport {
+                x: 256,
+                y: 16,
+                width: 768,
+                height: 768
+            }
+        );
+    }
+
+    #[test]
+    fn integer_scaling_uses_physical_pixels() {
+        let screen_info = ScreenInfo {
+            scale_factor: 2.0,
+            ..screen_info(320.0, 320.0, ScalingMode::IntegerPixelPerfect)
+        };
+
+        assert_eq!(screen_info.viewport().width, 640);
+    }
+
+    #[test]
+    fn cursor_maps_through_the_viewport() {
+        let centered = screen_info(1280.0, 800.0, ScalingMode::IntegerPixelPerfect);
+
+        assert_eq!(centered.to_screen_position(256.0, 16.0), (0, 0));
+        assert_eq!(centered.to_screen_position(1023.0, 783.0), (127, 127));
+        assert_eq!(centered.to_screen_position(250.0, 8.0), (-1, -2));
+
+        let stretched = screen_info(256.0, 512.0, ScalingMode::Stretch);
+        assert_eq!(stretched.to_screen_position(128.0, 128.0), (64, 32));
+    }
+
+    #[test]
+    fn logical_positions_account_for_the_scale_factor() {
+        let screen_info = ScreenInfo {
+            scale_factor: 2.0,
+            ..screen_info(640.0, 400.0, ScalingMode::IntegerPixelPerfect)
+        };
+
+        // 1280x800 physical pixels, so the same viewport as without scaling at that size.
+        assert_eq!(screen_info.logical_to_screen_position(128.0, 8.0), (0, 0));
+        assert_eq!(
+            screen_info.logical_to_screen_position(320.0, 200.0),
+            (64, 64)
+        );
+        assert_eq!(
+            screen_info.logical_to_screen_position(511.75, 391.75),
+            (127, 127)
+        );
+        assert_eq!(screen_info.logical_to_screen_position(0.0, 0.0), (-43, -3));
+    }
+
+    #[test]
+    fn cursor_maps_through_letterboxed_viewports() {
+        // 2x scale, with 8 pixel bars on the left and right.
+        let wide = screen_info(272.0, 256.0, ScalingMode::IntegerPixelPerfect);
+        assert_eq!(wide.to_screen_position(8.0, 0.0), (0, 0));
+        assert_eq!(wide.to_screen_position(263.0, 255.0), (127, 127));
+        assert_eq!(wide.to_screen_position(264.0, 128.0), (128, 64));
+
+        // 1x scale, with 36 pixel bars at the top and bottom.
+        let tall = screen_info(128.0, 200.0, ScalingMode::IntegerPixelPerfect);
+        assert_eq!(tall.to_screen_position(0.0, 36.0), (0, 0));
+        assert_eq!(tall.to_screen_position(127.0, 163.0), (127, 127));
+    }
+
+    #[test]
+    fn integer_scaling_never_goes_below_one() {
+        let screen_info = screen_info(100.0, 100.0, ScalingMode::IntegerPixelPerfect);
+
+        assert_eq!(screen_info.viewport().width, 128);
+    }
+
+    #[test]
+    fn integer_scaling_fits_other_resolutions() {
+        let screen_info = ScreenInfo {
+            resolution: (256, 128),
+            ..screen_info(1280.0, 800.0, ScalingMode::IntegerPixelPerfect)
+        };
+
+        assert_eq!(
+            screen_info.viewport(),
+            Viewport {
+                x: 0,
+                y: 80,
+                width: 1280,
+                height: 640
+            }
+        );
+        assert_eq!(screen_info.to_screen_position(1279.0, 719.0), (255, 127));
+    }
+
+    #[test]
+    fn resizing_keeps_the_cursor_in_game_coordinates() {
+        let mut screen_info = screen_info(256.0, 256.0, ScalingMode::Stretch);
+        let mut time = 0.0;
+
+        #[allow(deprecated)]
+        let moved = window_event(WindowEvent::CursorMoved {
+            device_id: unsafe { DeviceId::dummy() },
+            position: PhysicalPosition::new(128.0, 128.0),
+            modifiers: Default::default(),
+        });
+        let resized = window_event(WindowEvent::Resized(PhysicalSize::new(512, 256)));
+
+        let mut cursor_moves_to = |event, screen_info: &mut ScreenInfo| match Event::from_winit(
+            event,
+            &mut time,
+            screen_info,
+        ) {
+            Some(Event::Input(InputEvent::Mouse(MouseEvent::Move { x, y }))) => Some((x, y)),
+            _ => None,
+        };
+
+        assert_eq!(cursor_moves_to(&moved, &mut screen_info), Some((64, 64)));
+        assert_eq!(cursor_moves_to(&resized, &mut screen_info), Some((32, 64)));
+        assert_eq!(screen_info.viewport().width, 512);
+    }
+}
@@ -3,31 +3,39 @@
 //! Entrypoints for all games using runty8.
 
 #[doc(inline)]
-pub use runty8_core::{flr, load_assets, mid, rnd, sin, App, Button, Pico8};
+pub use runty8_core::{flr, load_assets, mid, rnd, sin, App, Button, Pico8, RunConfig};
 
 use runty8_core::Resources;
 
 #[doc(inline)]
-pub use runty8_editor::run_app as run_editor;
+pub use runty8_editor::{run_app as run_editor, run_app_with_config as run_editor_with_config};
 
 #[doc(inline)]
-pub use runty8_runtime::run;
+pub use runty8_runtime::{run, run_with_config};
 
 /// Run your game in the Editor in `debug` mode, and in the standalone Runtime in `release`.
 pub fn debug_run<Game: App + 'static>(resources: Resources) -> std::io::Result<()> {
+    debug_run_with_config::<Game>(resources, RunConfig::default())
+}
+
+/// Like [`debug_run`], but with custom settings.
+pub fn debug_run_with_config<Game: App + 'static>(
+    resources: Resources,
+    config: RunConfig,
+) -> std::io::Result<()> {
     let run = {
         #[cfg(debug_assertions)]
         {
             println!("Running editor...");
-            run_editor::<Game>
+            run_editor_with_config::<Game>
         }
 
         #[cfg(not(debug_assertions))]
         {
             println!("Running runtime...");
-            runty8_runtime::run::<Game>
+            runty8_runtime::run_with_config::<Game>
         }
     };
 
-    run(resources)
+    run(resources, config)
 }